@@ -0,0 +1,286 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::cookies;
+use crate::error::AppError;
+
+// ============ Types ============
+
+/// Disk used by the files the app and sidecar leave in the artifacts directory
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactUsage {
+    #[serde(rename = "totalBytes")]
+    pub total_bytes: u64,
+    #[serde(rename = "byType")]
+    pub by_type: ArtifactBytes,
+    #[serde(rename = "fileCount")]
+    pub file_count: u64,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactBytes {
+    pub screenshots: u64,
+    pub cookies: u64,
+    pub other: u64,
+}
+
+/// Result of `cleanup_artifacts`
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ArtifactCleanup {
+    #[serde(rename = "deletedFiles")]
+    pub deleted_files: u64,
+    #[serde(rename = "freedBytes")]
+    pub freed_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArtifactType {
+    Screenshot,
+    Cookies,
+    Other,
+}
+
+/// Entries of the artifacts directory that hold state rather than artifacts: browser user
+/// data for persistent contexts and the sidecar's profile cache
+const NOT_ARTIFACTS: &[&str] = &["profiles", "profile-cache.json"];
+
+const SCREENSHOT_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "webp"];
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+// ============ Directory ============
+
+/// The sidecar's `data` directory, which it resolves against the working directory it
+/// inherits from the app. Screenshots land in `data/screenshots`.
+pub fn artifacts_dir() -> Result<PathBuf, AppError> {
+    Ok(std::env::current_dir()?.join("data"))
+}
+
+/// `dir` resolved inside `root` (already canonical), or `root` itself when not given.
+/// Anything that resolves outside `root` (an absolute path elsewhere, `..`, a symlink) is refused.
+fn resolve_within(root: &Path, dir: Option<&str>) -> Result<PathBuf, AppError> {
+    let Some(dir) = dir.map(str::trim).filter(|dir| !dir.is_empty()) else {
+        return Ok(root.to_path_buf());
+    };
+
+    let dir = Path::new(dir);
+    let resolved = if dir.is_absolute() { dir.to_path_buf() } else { root.join(dir) }.canonicalize()?;
+    if !resolved.starts_with(root) {
+        return Err(AppError::Validation(format!("'{}' is outside the artifacts directory {}", dir.display(), root.display())));
+    }
+    Ok(resolved)
+}
+
+// ============ Usage ============
+
+/// Sizes of the artifact files in the artifacts directory `root`, or just in its subdirectory
+/// `dir`. A missing `root` counts as empty.
+pub fn artifact_usage(root: &Path, dir: Option<&str>) -> Result<ArtifactUsage, AppError> {
+    let mut usage = ArtifactUsage::default();
+    if !root.exists() {
+        return Ok(usage);
+    }
+    let root = root.canonicalize()?;
+    let dir = resolve_within(&root, dir)?;
+
+    for file in artifact_files(&root, &dir)? {
+        usage.total_bytes += file.size;
+        usage.file_count += 1;
+        match file.kind {
+            ArtifactType::Screenshot => usage.by_type.screenshots += file.size,
+            ArtifactType::Cookies => usage.by_type.cookies += file.size,
+            ArtifactType::Other => usage.by_type.other += file.size,
+        }
+    }
+    Ok(usage)
+}
+
+/// Delete artifact files under `root` last modified more than `older_than_days` days ago.
+/// `protected` (the database) and its journal files are never deleted, in case the database
+/// was moved into the artifacts directory. A file that can't be deleted is logged and skipped.
+pub fn cleanup_artifacts(root: &Path, older_than_days: u32, protected: &Path) -> Result<ArtifactCleanup, AppError> {
+    let age = Duration::from_secs(u64::from(older_than_days) * SECONDS_PER_DAY);
+    let cutoff = SystemTime::now().checked_sub(age).unwrap_or(SystemTime::UNIX_EPOCH);
+
+    let mut cleanup = ArtifactCleanup::default();
+    if !root.exists() {
+        return Ok(cleanup);
+    }
+    let root = root.canonicalize()?;
+
+    for file in artifact_files(&root, &root)? {
+        if file.modified > cutoff || cookies::is_protected(&file.path, protected) {
+            continue;
+        }
+        match std::fs::remove_file(&file.path) {
+            Ok(()) => {
+                cleanup.deleted_files += 1;
+                cleanup.freed_bytes += file.size;
+            }
+            Err(e) => log::warn!("Could not delete artifact {}: {}", file.path.display(), e),
+        }
+    }
+    Ok(cleanup)
+}
+
+struct ArtifactFile {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+    kind: ArtifactType,
+}
+
+/// Regular files under `dir`, skipping `NOT_ARTIFACTS` at the top of `root`. Symlinks are
+/// not followed, so the walk never leaves `root`.
+fn artifact_files(root: &Path, dir: &Path) -> Result<Vec<ArtifactFile>, AppError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let path = entry.path();
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            if NOT_ARTIFACTS.iter().any(|name| relative == Path::new(name)) {
+                continue;
+            }
+
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                pending.push(path);
+            } else if metadata.is_file() {
+                files.push(ArtifactFile {
+                    kind: artifact_type(relative),
+                    size: metadata.len(),
+                    modified: metadata.modified()?,
+                    path,
+                });
+            }
+        }
+    }
+    Ok(files)
+}
+
+/// By top-level folder (`screenshots`, `cookies`), else by extension, with `.json` and
+/// `.txt` counting as cookies only when the name says so
+fn artifact_type(relative: &Path) -> ArtifactType {
+    let mut components = relative.components();
+    let top = components.next().and_then(|c| c.as_os_str().to_str()).unwrap_or("");
+    let nested = components.next().is_some();
+    match top {
+        "screenshots" if nested => return ArtifactType::Screenshot,
+        "cookies" if nested => return ArtifactType::Cookies,
+        _ => {}
+    }
+
+    let name = relative.file_name().and_then(|n| n.to_str()).unwrap_or("").to_ascii_lowercase();
+    let extension = relative.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    if SCREENSHOT_EXTENSIONS.contains(&extension.as_str()) {
+        ArtifactType::Screenshot
+    } else if matches!(extension.as_str(), "json" | "txt") && name.contains("cookie") {
+        ArtifactType::Cookies
+    } else {
+        ArtifactType::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// An artifacts directory with mixed files, and a directory outside it, both deleted on drop
+    struct Fixture {
+        root: PathBuf,
+        outside: PathBuf,
+    }
+
+    impl Drop for Fixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.root);
+            let _ = std::fs::remove_dir_all(&self.outside);
+        }
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            static NEXT: AtomicUsize = AtomicUsize::new(0);
+            let base = std::env::temp_dir().join(format!(
+                "mmo-test-artifacts-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)
+            ));
+            let fixture = Fixture { root: base.join("data"), outside: base.join("outside") };
+            std::fs::create_dir_all(&fixture.outside).unwrap();
+            std::fs::write(fixture.outside.join("keep.png"), [0u8; 50]).unwrap();
+
+            for dir in ["screenshots/a", "cookies", "profiles/p1", "misc"] {
+                std::fs::create_dir_all(fixture.root.join(dir)).unwrap();
+            }
+            for (path, size) in [
+                ("screenshots/a/x.bin", 100), ("shot.png", 10), ("cookies/s1.txt", 20),
+                ("misc/my-cookies.json", 5), ("misc/notes.json", 7),
+                // Not artifacts: browser state and the database
+                ("profiles/p1/Cookies", 1000), ("profile-cache.json", 1000), ("mmo.db", 3), ("mmo.db-wal", 4),
+            ] {
+                std::fs::write(fixture.root.join(path), vec![0u8; size]).unwrap();
+            }
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&fixture.outside, fixture.root.join("link")).unwrap();
+            fixture
+        }
+
+        fn age(&self, paths: &[&str], days: u64) {
+            let modified = SystemTime::now() - Duration::from_secs(days * SECONDS_PER_DAY);
+            for path in paths {
+                std::fs::File::options().write(true).open(self.root.join(path)).unwrap().set_modified(modified).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn usage_is_broken_down_by_type() {
+        let fixture = Fixture::new();
+        let usage = artifact_usage(&fixture.root, None).unwrap();
+        assert_eq!(usage, ArtifactUsage {
+            total_bytes: 149,
+            by_type: ArtifactBytes { screenshots: 110, cookies: 25, other: 14 },
+            file_count: 7,
+        });
+        assert_eq!(artifact_usage(&fixture.root, Some("screenshots")).unwrap().total_bytes, 100);
+        assert_eq!(artifact_usage(&fixture.root.join("none"), None).unwrap(), ArtifactUsage::default());
+    }
+
+    #[test]
+    fn usage_stays_inside_the_artifacts_directory() {
+        let fixture = Fixture::new();
+        assert!(artifact_usage(&fixture.root, Some("..")).is_err());
+        assert!(artifact_usage(&fixture.root, Some(fixture.outside.to_str().unwrap())).is_err());
+        #[cfg(unix)]
+        assert!(artifact_usage(&fixture.root, Some("link")).is_err());
+    }
+
+    #[test]
+    fn cleanup_deletes_only_old_artifacts() {
+        let fixture = Fixture::new();
+        let protected = fixture.root.join("mmo.db");
+        assert_eq!(cleanup_artifacts(&fixture.root, 1, &protected).unwrap(), ArtifactCleanup::default());
+
+        fixture.age(&["shot.png", "cookies/s1.txt", "mmo.db", "mmo.db-wal", "profile-cache.json"], 3);
+        let cleanup = cleanup_artifacts(&fixture.root, 2, &protected).unwrap();
+        assert_eq!(cleanup, ArtifactCleanup { deleted_files: 2, freed_bytes: 30 });
+        assert!(!fixture.root.join("shot.png").exists());
+        for kept in ["mmo.db", "mmo.db-wal", "profile-cache.json", "profiles/p1/Cookies", "screenshots/a/x.bin"] {
+            assert!(fixture.root.join(kept).exists(), "{}", kept);
+        }
+
+        let cleanup = cleanup_artifacts(&fixture.root, 0, &protected).unwrap();
+        assert_eq!(cleanup, ArtifactCleanup { deleted_files: 3, freed_bytes: 112 });
+        assert!(fixture.outside.join("keep.png").exists());
+        assert!(protected.exists());
+    }
+}
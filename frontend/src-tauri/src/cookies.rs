@@ -0,0 +1,674 @@
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::error::AppError;
+
+// ============ Types ============
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CookieFormat {
+    #[serde(rename = "json")]
+    Json,
+    #[serde(rename = "netscape")]
+    Netscape,
+    #[serde(rename = "header")]
+    HeaderString,
+    #[serde(rename = "editthiscookie")]
+    EditThisCookie,
+    #[serde(rename = "base64")]
+    Base64,
+}
+
+impl CookieFormat {
+    pub const ALL: [CookieFormat; 5] = [
+        CookieFormat::Json,
+        CookieFormat::Netscape,
+        CookieFormat::HeaderString,
+        CookieFormat::EditThisCookie,
+        CookieFormat::Base64,
+    ];
+
+    /// Name used on the wire and by the sidecar's cookie manager
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CookieFormat::Json => "json",
+            CookieFormat::Netscape => "netscape",
+            CookieFormat::HeaderString => "header",
+            CookieFormat::EditThisCookie => "editthiscookie",
+            CookieFormat::Base64 => "base64",
+        }
+    }
+
+    /// File extension for an export in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CookieFormat::Json | CookieFormat::EditThisCookie => "json",
+            CookieFormat::Netscape | CookieFormat::HeaderString | CookieFormat::Base64 => "txt",
+        }
+    }
+}
+
+impl TryFrom<&str> for CookieFormat {
+    type Error = AppError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let wanted = value.trim().to_ascii_lowercase();
+        CookieFormat::ALL
+            .into_iter()
+            .find(|f| f.as_str() == wanted)
+            .ok_or_else(|| {
+                let valid: Vec<&str> = CookieFormat::ALL.iter().map(|f| f.as_str()).collect();
+                AppError::Validation(format!("Unknown cookie format '{}'. Valid formats: {}", value, valid.join(", ")))
+            })
+    }
+}
+
+/// Cookie in the canonical (Playwright) shape the sidecar's `importCookies` expects
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires: Option<f64>,
+    #[serde(rename = "httpOnly")]
+    pub http_only: bool,
+    pub secure: bool,
+    #[serde(rename = "sameSite", skip_serializing_if = "Option::is_none")]
+    pub same_site: Option<String>,
+}
+
+// ============ Conversion ============
+
+/// Convert a cookie string between formats without a browser session.
+/// `domain` is only used for header strings, which carry no domain of their own.
+pub fn convert_cookies(input: &str, from: CookieFormat, to: CookieFormat, domain: Option<&str>) -> Result<String, AppError> {
+    let cookies = parse_cookies(input, from, domain)?;
+    serialize_cookies(&cookies, to)
+}
+
+pub fn parse_cookies(input: &str, format: CookieFormat, domain: Option<&str>) -> Result<Vec<Cookie>, AppError> {
+    match format {
+        CookieFormat::Json | CookieFormat::EditThisCookie => parse_json(input),
+        CookieFormat::Netscape => parse_netscape(input),
+        CookieFormat::HeaderString => {
+            let domain = domain
+                .filter(|d| !d.trim().is_empty())
+                .ok_or_else(|| AppError::Validation("A domain is required to import a cookie header string".to_string()))?;
+            parse_header(input, domain)
+        }
+        CookieFormat::Base64 => {
+            let bytes = base64::engine::general_purpose::STANDARD
+                .decode(input.trim())
+                .map_err(|e| AppError::Validation(format!("Invalid base64 cookie data: {}", e)))?;
+            let json = String::from_utf8(bytes).map_err(|e| AppError::Validation(format!("Invalid base64 cookie data: {}", e)))?;
+            parse_json(&json)
+        }
+    }
+}
+
+pub fn serialize_cookies(cookies: &[Cookie], format: CookieFormat) -> Result<String, AppError> {
+    match format {
+        CookieFormat::Json => serde_json::to_string_pretty(cookies).map_err(AppError::from),
+        CookieFormat::EditThisCookie => {
+            let etc: Vec<Value> = cookies.iter().map(to_edit_this_cookie).collect();
+            serde_json::to_string_pretty(&etc).map_err(AppError::from)
+        }
+        CookieFormat::Netscape => Ok(to_netscape(cookies)),
+        CookieFormat::HeaderString => Ok(cookies
+            .iter()
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect::<Vec<_>>()
+            .join("; ")),
+        CookieFormat::Base64 => {
+            let json = serde_json::to_string(cookies)?;
+            Ok(base64::engine::general_purpose::STANDARD.encode(json))
+        }
+    }
+}
+
+/// Cookies as returned by the sidecar's `exportCookies` (a Playwright cookie array)
+pub fn cookies_from_json(value: &Value) -> Result<Vec<Cookie>, AppError> {
+    let items = value.as_array().ok_or_else(|| AppError::Validation("Cookie JSON must be an array".to_string()))?;
+    items.iter().map(cookie_from_value).collect()
+}
+
+/// Cookies from a JSON export of unknown origin, as the sidecar's `importCookies` expects them.
+/// Accepts a bare array (Playwright, EditThisCookie, Cookie-Editor, Selenium), an object with a
+/// `cookies` array (Playwright storage state, J2Team's `{ url, cookies }`) or a single cookie.
+/// Cookies without a domain take the host of the envelope's `url`, if there is one.
+pub fn normalize_cookie_import(raw: Value) -> Result<Vec<Cookie>, AppError> {
+    let (items, url_host) = match raw {
+        Value::Array(items) => (items, None),
+        Value::Object(mut object) => match object.remove("cookies") {
+            Some(Value::Array(items)) => {
+                let host = object.get("url").and_then(Value::as_str).and_then(url_host);
+                (items, host)
+            }
+            Some(_) => return Err(AppError::Validation("'cookies' must be an array".to_string())),
+            None => (vec![Value::Object(object)], None),
+        },
+        _ => return Err(AppError::Validation("Cookie export must be a JSON array or object".to_string())),
+    };
+
+    items.iter()
+        .map(|item| {
+            let mut cookie = cookie_from_value(item)?;
+            if cookie.domain.is_empty() {
+                cookie.domain = url_host.clone()
+                    .ok_or_else(|| AppError::Validation(format!("Cookie '{}' has no domain", cookie.name)))?;
+            }
+            Ok(cookie)
+        })
+        .collect()
+}
+
+// ============ Files ============
+
+/// Serialize `cookies` into `path`, creating its parent directory if missing, and
+/// encrypting the contents when a passphrase is given.
+/// Refuses to write over `protected` (the database) or its journal files.
+/// Returns the absolute path written.
+pub fn write_cookies_file(
+    cookies: &[Cookie],
+    path: &str,
+    format: CookieFormat,
+    protected: &Path,
+    passphrase: Option<&str>,
+) -> Result<PathBuf, AppError> {
+    let path = path.trim();
+    if path.is_empty() {
+        return Err(AppError::Validation("File path is required".to_string()));
+    }
+
+    let path = Path::new(path);
+    let path = if path.is_absolute() { path.to_path_buf() } else { std::env::current_dir()?.join(path) };
+    let file_name = path.file_name()
+        .ok_or_else(|| AppError::Validation(format!("'{}' is not a file path", path.display())))?
+        .to_owned();
+    let parent = path.parent()
+        .ok_or_else(|| AppError::Validation(format!("'{}' has no parent directory", path.display())))?;
+    std::fs::create_dir_all(parent)?;
+
+    // Compare resolved paths so symlinks and `..` can't sneak past the check
+    let target = parent.canonicalize()?.join(&file_name);
+    if is_protected(&target, protected) {
+        return Err(AppError::Validation(format!("Refusing to overwrite the database file '{}'", target.display())));
+    }
+
+    let contents = serialize_cookies(cookies, format)?;
+    let contents = match passphrase {
+        Some(passphrase) => encrypt_cookies(&contents, format, passphrase)?,
+        None => contents,
+    };
+    std::fs::write(&target, contents)?;
+    Ok(target)
+}
+
+/// The file itself, plus SQLite's `-wal`, `-shm` and `-journal` siblings
+pub(crate) fn is_protected(target: &Path, protected: &Path) -> bool {
+    let protected = protected.canonicalize().unwrap_or_else(|_| protected.to_path_buf());
+    let resolved = target.canonicalize().unwrap_or_else(|_| target.to_path_buf());
+    if resolved == protected {
+        return true;
+    }
+
+    let same_dir = resolved.parent() == protected.parent();
+    match (resolved.file_name(), protected.file_name()) {
+        (Some(name), Some(db_name)) if same_dir => ["-wal", "-shm", "-journal"].iter().any(|suffix| {
+            let mut sibling = db_name.to_owned();
+            sibling.push(suffix);
+            name == sibling
+        }),
+        _ => false,
+    }
+}
+
+// ============ Encryption ============
+
+/// `format` value identifying an encrypted cookie file
+const ENVELOPE_FORMAT: &str = "mmo-cookies-encrypted";
+const ENVELOPE_VERSION: u32 = 1;
+
+/// PBKDF2-HMAC-SHA256 rounds for new files. Stored in the envelope, so it can be raised later.
+const KDF_ROUNDS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Encrypted cookie file: AES-256-GCM over the serialized cookies, keyed by PBKDF2 of the passphrase
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope {
+    format: String,
+    version: u32,
+    kdf: String,
+    iterations: u32,
+    salt: String,
+    nonce: String,
+    /// Format of the plaintext cookies
+    #[serde(rename = "cookieFormat")]
+    cookie_format: CookieFormat,
+    ciphertext: String,
+}
+
+/// Encrypt serialized cookies into a versioned JSON envelope
+pub fn encrypt_cookies(plaintext: &str, format: CookieFormat, passphrase: &str) -> Result<String, AppError> {
+    encrypt_with_rounds(plaintext, format, passphrase, KDF_ROUNDS)
+}
+
+fn encrypt_with_rounds(plaintext: &str, format: CookieFormat, passphrase: &str, rounds: u32) -> Result<String, AppError> {
+    if passphrase.is_empty() {
+        return Err(AppError::Validation("Passphrase cannot be empty".to_string()));
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = cipher_for(passphrase, &salt, rounds)?;
+    let ciphertext = cipher.encrypt(Nonce::from_slice(&nonce), plaintext.as_bytes())
+        .map_err(|_| AppError::Validation("Failed to encrypt cookies".to_string()))?;
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let envelope = Envelope {
+        format: ENVELOPE_FORMAT.to_string(),
+        version: ENVELOPE_VERSION,
+        kdf: "pbkdf2-sha256".to_string(),
+        iterations: rounds,
+        salt: b64.encode(salt),
+        nonce: b64.encode(nonce),
+        cookie_format: format,
+        ciphertext: b64.encode(ciphertext),
+    };
+    serde_json::to_string_pretty(&envelope).map_err(AppError::from)
+}
+
+/// Whether file contents are an encrypted envelope rather than plaintext cookies
+pub fn is_encrypted(contents: &str) -> bool {
+    serde_json::from_str::<Value>(contents.trim())
+        .map(|v| v.get("format").and_then(Value::as_str) == Some(ENVELOPE_FORMAT))
+        .unwrap_or(false)
+}
+
+/// Decrypt an envelope back to the serialized cookies and their format
+pub fn decrypt_cookies(contents: &str, passphrase: &str) -> Result<(String, CookieFormat), AppError> {
+    let envelope: Envelope = serde_json::from_str(contents.trim())
+        .map_err(|e| AppError::Validation(format!("Invalid encrypted cookie file: {}", e)))?;
+    if envelope.format != ENVELOPE_FORMAT || envelope.kdf != "pbkdf2-sha256" {
+        return Err(AppError::Validation("Not an encrypted cookie file".to_string()));
+    }
+    if envelope.version > ENVELOPE_VERSION {
+        return Err(AppError::Validation(format!(
+            "Encrypted cookie file version {} is newer than this app supports", envelope.version
+        )));
+    }
+
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let decode = |field: &str, value: &str| b64.decode(value)
+        .map_err(|e| AppError::Validation(format!("Invalid {} in encrypted cookie file: {}", field, e)));
+    let salt = decode("salt", &envelope.salt)?;
+    let nonce = decode("nonce", &envelope.nonce)?;
+    let ciphertext = decode("ciphertext", &envelope.ciphertext)?;
+    if nonce.len() != NONCE_LEN {
+        return Err(AppError::Validation("Invalid nonce in encrypted cookie file".to_string()));
+    }
+
+    // GCM authentication fails for a wrong passphrase and for tampered files alike
+    let cipher = cipher_for(passphrase, &salt, envelope.iterations)?;
+    let plaintext = cipher.decrypt(Nonce::from_slice(&nonce), ciphertext.as_slice())
+        .map_err(|_| AppError::Validation("Wrong passphrase or corrupted cookie file".to_string()))?;
+    let plaintext = String::from_utf8(plaintext)
+        .map_err(|e| AppError::Validation(format!("Decrypted cookies are not text: {}", e)))?;
+
+    Ok((plaintext, envelope.cookie_format))
+}
+
+fn cipher_for(passphrase: &str, salt: &[u8], rounds: u32) -> Result<Aes256Gcm, AppError> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    Aes256Gcm::new_from_slice(&key).map_err(|_| AppError::Validation("Invalid encryption key".to_string()))
+}
+
+// ============ Domain Patterns ============
+
+/// Validate a cookie domain pattern: an exact domain (`example.com`, `.example.com`)
+/// or a leading wildcard (`*.example.com`, matching the domain and its subdomains).
+/// Returns the pattern trimmed and lowercased.
+pub fn normalize_domain_pattern(pattern: &str) -> Result<String, AppError> {
+    let normalized = pattern.trim().to_ascii_lowercase();
+    let (wildcard, domain) = match normalized.strip_prefix("*.") {
+        Some(rest) => (true, rest),
+        None => (false, normalized.as_str()),
+    };
+    let domain = domain.strip_prefix('.').unwrap_or(domain);
+
+    let labels: Vec<&str> = domain.split('.').collect();
+    let valid_labels = labels.iter().all(|label| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    });
+
+    // A wildcard over a bare TLD (`*.com`) would match nearly everything
+    if !valid_labels || (wildcard && labels.len() < 2) {
+        return Err(AppError::Validation(format!(
+            "Invalid cookie domain pattern '{}' (expected example.com or *.example.com)",
+            pattern
+        )));
+    }
+
+    Ok(normalized)
+}
+
+// ============ Parsers ============
+
+fn parse_json(input: &str) -> Result<Vec<Cookie>, AppError> {
+    let parsed: Value = serde_json::from_str(input.trim()).map_err(|e| AppError::Validation(format!("Invalid cookie JSON: {}", e)))?;
+    cookies_from_json(&parsed)
+}
+
+/// Build a canonical cookie from a JSON object in Playwright, EditThisCookie/Cookie-Editor
+/// (`expirationDate`, `hostOnly`, `session`) or Selenium (`expiry`) shape
+fn cookie_from_value(value: &Value) -> Result<Cookie, AppError> {
+    let name = value.get("name").and_then(Value::as_str).ok_or_else(|| AppError::Validation("Cookie is missing 'name'".to_string()))?;
+    let str_field = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or("");
+
+    let expires = ["expires", "expirationDate", "expiry"].iter()
+        .find_map(|key| value.get(*key).and_then(Value::as_f64))
+        .and_then(normalize_expires)
+        .filter(|_| value.get("session").and_then(Value::as_bool) != Some(true));
+
+    // Host-only cookies have no leading dot in Playwright; domain cookies need one
+    let domain = str_field("domain");
+    let domain = match value.get("hostOnly").and_then(Value::as_bool) {
+        Some(true) => domain.trim_start_matches('.').to_string(),
+        Some(false) if !domain.is_empty() && !domain.starts_with('.') => format!(".{}", domain),
+        _ => domain.to_string(),
+    };
+
+    Ok(Cookie {
+        name: name.to_string(),
+        value: str_field("value").to_string(),
+        domain,
+        path: Some(str_field("path")).filter(|p| !p.is_empty()).unwrap_or("/").to_string(),
+        expires,
+        http_only: value.get("httpOnly").and_then(Value::as_bool).unwrap_or(false),
+        secure: value.get("secure").and_then(Value::as_bool).unwrap_or(false),
+        same_site: value.get("sameSite").and_then(Value::as_str).map(normalize_same_site),
+    })
+}
+
+fn parse_netscape(input: &str) -> Result<Vec<Cookie>, AppError> {
+    let mut cookies = Vec::new();
+
+    for line in input.lines() {
+        // curl marks HttpOnly cookies with a prefix on the domain column
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(rest) => (rest, true),
+            None => (line, false),
+        };
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+
+        let parts: Vec<&str> = line.split('\t').collect();
+        if parts.len() < 7 {
+            continue;
+        }
+
+        cookies.push(Cookie {
+            name: parts[5].trim().to_string(),
+            value: parts[6].trim().to_string(),
+            domain: parts[0].trim().trim_start_matches('.').to_string(),
+            path: Some(parts[2].trim()).filter(|p| !p.is_empty()).unwrap_or("/").to_string(),
+            expires: parts[4].trim().parse::<f64>().ok().and_then(normalize_expires),
+            http_only,
+            secure: parts[3].trim().eq_ignore_ascii_case("TRUE"),
+            same_site: Some("Lax".to_string()),
+        });
+    }
+
+    if cookies.is_empty() {
+        return Err(AppError::Validation("No cookies found in Netscape data".to_string()));
+    }
+    Ok(cookies)
+}
+
+fn parse_header(input: &str, domain: &str) -> Result<Vec<Cookie>, AppError> {
+    let input = input.trim();
+    let input = input.strip_prefix("Cookie:").unwrap_or(input);
+
+    input
+        .split(';')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=')
+                .ok_or_else(|| AppError::Validation(format!("Invalid cookie pair '{}' (expected name=value)", pair)))?;
+            Ok(Cookie {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                domain: domain.trim().to_string(),
+                path: "/".to_string(),
+                expires: None,
+                http_only: false,
+                secure: false,
+                same_site: Some("Lax".to_string()),
+            })
+        })
+        .collect()
+}
+
+// ============ Serializers ============
+
+fn to_netscape(cookies: &[Cookie]) -> String {
+    let mut lines = vec![
+        "# Netscape HTTP Cookie File".to_string(),
+        "# https://curl.se/docs/http-cookies.html".to_string(),
+        "# This file was generated by MMO Express".to_string(),
+        String::new(),
+    ];
+
+    for cookie in cookies {
+        let include_subdomains = cookie.domain.starts_with('.');
+        let domain = if include_subdomains { cookie.domain.clone() } else { format!(".{}", cookie.domain) };
+        lines.push(format!(
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            domain,
+            if include_subdomains { "TRUE" } else { "FALSE" },
+            cookie.path,
+            if cookie.secure { "TRUE" } else { "FALSE" },
+            cookie.expires.map(|e| e.floor() as i64).unwrap_or(0),
+            cookie.name,
+            cookie.value
+        ));
+    }
+
+    lines.join("\n")
+}
+
+fn to_edit_this_cookie(cookie: &Cookie) -> Value {
+    let same_site = match cookie.same_site.as_deref() {
+        Some("Lax") => "lax",
+        Some("Strict") => "strict",
+        Some("None") => "no_restriction",
+        _ => "unspecified",
+    };
+
+    json!({
+        "domain": cookie.domain,
+        "expirationDate": cookie.expires,
+        "hostOnly": !cookie.domain.starts_with('.'),
+        "httpOnly": cookie.http_only,
+        "name": cookie.name,
+        "path": cookie.path,
+        "sameSite": same_site,
+        "secure": cookie.secure,
+        "session": cookie.expires.is_none(),
+        "storeId": "0",
+        "value": cookie.value,
+    })
+}
+
+// ============ Helpers ============
+
+/// Expiry in seconds since epoch; session cookies (<= 0) have none. Millisecond values are scaled down.
+fn normalize_expires(expires: f64) -> Option<f64> {
+    if expires <= 0.0 {
+        None
+    } else if expires > 9_999_999_999.0 {
+        Some(expires / 1000.0)
+    } else {
+        Some(expires)
+    }
+}
+
+/// Host of an `http(s)://host[:port]/...` URL, lowercased
+fn url_host(url: &str) -> Option<String> {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next()?.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_ascii_lowercase();
+    Some(host).filter(|h| !h.is_empty())
+}
+
+/// Same mapping as the sidecar's `normalizeSameSite`
+fn normalize_same_site(same_site: &str) -> String {
+    match same_site.to_ascii_lowercase().as_str() {
+        "" | "unspecified" | "no_restriction" | "none" => "None",
+        "strict" => "Strict",
+        _ => "Lax",
+    }
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COOKIES: &str = "sid=abc123; theme=dark";
+
+    /// `KDF_ROUNDS` takes seconds per key in a debug build; the round count travels in the
+    /// envelope, so decryption works the same either way
+    fn encrypt(plaintext: &str, format: CookieFormat, passphrase: &str) -> Result<String, AppError> {
+        encrypt_with_rounds(plaintext, format, passphrase, 1_000)
+    }
+
+    #[test]
+    fn encrypted_cookies_round_trip() {
+        let envelope = encrypt(COOKIES, CookieFormat::HeaderString, "correct horse").unwrap();
+        assert!(is_encrypted(&envelope));
+        assert!(!envelope.contains("abc123"));
+
+        let (plaintext, format) = decrypt_cookies(&envelope, "correct horse").unwrap();
+        assert_eq!(plaintext, COOKIES);
+        assert_eq!(format, CookieFormat::HeaderString);
+    }
+
+    #[test]
+    fn wrong_passphrase_and_tampering_are_rejected() {
+        let envelope = encrypt(COOKIES, CookieFormat::HeaderString, "correct horse").unwrap();
+        let wrong = decrypt_cookies(&envelope, "battery staple");
+        assert_eq!(wrong, Err(AppError::Validation("Wrong passphrase or corrupted cookie file".to_string())));
+
+        let mut tampered: Value = serde_json::from_str(&envelope).unwrap();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let mut ciphertext = b64.decode(tampered["ciphertext"].as_str().unwrap()).unwrap();
+        ciphertext[0] ^= 1;
+        tampered["ciphertext"] = Value::String(b64.encode(ciphertext));
+        assert!(decrypt_cookies(&tampered.to_string(), "correct horse").is_err());
+    }
+
+    #[test]
+    fn plaintext_and_empty_passphrases_are_not_envelopes() {
+        assert!(!is_encrypted("[]"));
+        assert!(!is_encrypted(COOKIES));
+        assert!(matches!(encrypt_cookies(COOKIES, CookieFormat::Json, ""), Err(AppError::Validation(_))));
+        assert!(matches!(decrypt_cookies("[]", "pw"), Err(AppError::Validation(_))));
+    }
+
+    fn import(raw: Value) -> Vec<Cookie> {
+        normalize_cookie_import(raw).unwrap()
+    }
+
+    #[test]
+    fn playwright_cookies_import_unchanged() {
+        let cookie = json!({
+            "name": "sid", "value": "abc", "domain": ".example.com", "path": "/app",
+            "expires": 1_900_000_000.0, "httpOnly": true, "secure": true, "sameSite": "Strict"
+        });
+        assert_eq!(import(json!([cookie.clone()])), vec![Cookie {
+            name: "sid".into(),
+            value: "abc".into(),
+            domain: ".example.com".into(),
+            path: "/app".into(),
+            expires: Some(1_900_000_000.0),
+            http_only: true,
+            secure: true,
+            same_site: Some("Strict".into()),
+        }]);
+
+        // Playwright storage state and a lone cookie object
+        let state = import(json!({ "cookies": [cookie.clone()], "origins": [] }));
+        assert_eq!(state, import(json!([cookie.clone()])));
+        assert_eq!(import(cookie), state);
+    }
+
+    #[test]
+    fn edit_this_cookie_exports_are_mapped() {
+        let cookies = import(json!([
+            {
+                "domain": "example.com", "expirationDate": 1_900_000_000.5, "hostOnly": true,
+                "httpOnly": false, "name": "host", "path": "/", "sameSite": "no_restriction",
+                "secure": true, "session": false, "storeId": "0", "value": "1", "id": 1
+            },
+            {
+                "domain": "example.com", "hostOnly": false, "name": "domain", "path": "",
+                "sameSite": "unspecified", "session": true, "value": "2", "id": 2
+            }
+        ]));
+        assert_eq!(cookies[0].domain, "example.com");
+        assert_eq!(cookies[0].expires, Some(1_900_000_000.5));
+        assert_eq!(cookies[0].same_site.as_deref(), Some("None"));
+        assert_eq!(cookies[1].domain, ".example.com");
+        assert_eq!(cookies[1].path, "/");
+        assert_eq!(cookies[1].expires, None);
+    }
+
+    #[test]
+    fn cookie_editor_and_selenium_exports_are_mapped() {
+        let editor = import(json!([{
+            "domain": ".example.com", "expirationDate": 1_900_000_000_000.0, "hostOnly": false,
+            "httpOnly": true, "name": "ce", "path": "/", "sameSite": "lax", "secure": false, "session": false, "value": "x"
+        }]));
+        assert_eq!(editor[0].expires, Some(1_900_000_000.0), "millisecond expiry is scaled to seconds");
+        assert_eq!(editor[0].same_site.as_deref(), Some("Lax"));
+        assert!(editor[0].http_only);
+
+        let selenium = import(json!([{ "name": "se", "value": "y", "domain": "example.com", "expiry": 1_900_000_000 }]));
+        assert_eq!(selenium[0].expires, Some(1_900_000_000.0));
+        assert_eq!(selenium[0].same_site, None);
+    }
+
+    #[test]
+    fn j2team_exports_take_the_url_host_for_missing_domains() {
+        let cookies = import(json!({
+            "url": "https://User@Shop.Example.com:8443/cart?x=1",
+            "cookies": [{ "name": "a", "value": "1" }, { "name": "b", "value": "2", "domain": ".other.com" }]
+        }));
+        assert_eq!(cookies[0].domain, "shop.example.com");
+        assert_eq!(cookies[1].domain, ".other.com");
+
+        assert!(matches!(normalize_cookie_import(json!([{ "name": "a", "value": "1" }])), Err(AppError::Validation(_))));
+        assert!(matches!(normalize_cookie_import(json!({ "cookies": "a=1" })), Err(AppError::Validation(_))));
+        assert!(matches!(normalize_cookie_import(json!("a=1")), Err(AppError::Validation(_))));
+        assert!(matches!(normalize_cookie_import(json!([{ "value": "1", "domain": "x.com" }])), Err(AppError::Validation(_))));
+    }
+}
@@ -0,0 +1,431 @@
+use flate2::read::DeflateDecoder;
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::AppError;
+
+// ============ Types ============
+
+/// What a CRX package will install, read from its header and manifest without installing it
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct CrxInfo {
+    /// Extension id Chrome will assign (32 letters a-p)
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    /// API permissions followed by host permissions, as listed in the manifest
+    pub permissions: Vec<String>,
+    /// 2 or 3
+    #[serde(rename = "crxVersion")]
+    pub crx_version: u32,
+}
+
+const CRX_MAGIC: &[u8; 4] = b"Cr24";
+
+/// Largest header a CRX may declare; real ones are a few KB
+const MAX_HEADER_BYTES: usize = 1024 * 1024;
+
+/// Largest manifest (or locale messages file) inflated from the archive
+const MAX_MANIFEST_BYTES: u64 = 1024 * 1024;
+
+// ZIP record signatures
+const ZIP_END_OF_CENTRAL_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP_CENTRAL_DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const ZIP_LOCAL_FILE_HEADER: u32 = 0x0403_4b50;
+
+// ============ Inspection ============
+
+/// `parse_crx` on the file at `path`
+pub fn inspect_crx(path: &Path) -> Result<CrxInfo, AppError> {
+    let bytes = std::fs::read(path)?;
+    parse_crx(&bytes)
+}
+
+/// Read a CRX2 or CRX3 package: the id from its public key (or the id CRX3 signs), then name,
+/// version and permissions from `manifest.json` in the embedded ZIP. `__MSG_*__` names are
+/// resolved from the default locale.
+pub fn parse_crx(bytes: &[u8]) -> Result<CrxInfo, AppError> {
+    if bytes.len() < 12 || &bytes[..4] != CRX_MAGIC {
+        return Err(corrupt("missing the Cr24 header, so it is not a CRX file"));
+    }
+    let crx_version = read_u32(bytes, 4)?;
+
+    let (id, archive) = match crx_version {
+        2 => {
+            let key_len = read_u32(bytes, 8)? as usize;
+            let signature_len = read_u32(bytes, 12)? as usize;
+            let key = slice(bytes, 16, key_len)?;
+            let archive_start = 16usize.checked_add(key_len).and_then(|n| n.checked_add(signature_len))
+                .ok_or_else(|| corrupt("header lengths overflow"))?;
+            (id_from_public_key(key), slice(bytes, archive_start, bytes.len().saturating_sub(archive_start))?)
+        }
+        3 => {
+            let header_len = read_u32(bytes, 8)? as usize;
+            if header_len > MAX_HEADER_BYTES {
+                return Err(corrupt("header is too large"));
+            }
+            let header = slice(bytes, 12, header_len)?;
+            (crx3_id(header)?, &bytes[12 + header_len..])
+        }
+        other => return Err(corrupt(&format!("unsupported CRX version {}", other))),
+    };
+
+    let manifest: Value = serde_json::from_slice(&zip_file(archive, "manifest.json")?
+        .ok_or_else(|| corrupt("the archive has no manifest.json"))?)
+        .map_err(|e| corrupt(&format!("manifest.json is not valid JSON: {}", e)))?;
+
+    let text = |key: &str| manifest.get(key).and_then(Value::as_str).unwrap_or("").to_string();
+    let mut name = text("name");
+    if let Some(message) = name.strip_prefix("__MSG_").and_then(|rest| rest.strip_suffix("__")) {
+        if let Some(localized) = localized_message(archive, &text("default_locale"), message)? {
+            name = localized;
+        }
+    }
+
+    let permissions = ["permissions", "host_permissions"]
+        .iter()
+        .filter_map(|key| manifest.get(key).and_then(Value::as_array))
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect();
+
+    Ok(CrxInfo { id, name, version: text("version"), permissions, crx_version })
+}
+
+/// CRX3 header (`CrxFileHeader` protobuf): the id is `signed_header_data.crx_id` (fields
+/// 10000 then 1). Older CRX3 files may lack it; then it comes from the first RSA key proof
+/// (`sha256_with_rsa.public_key`, fields 2 then 1), as for CRX2.
+fn crx3_id(header: &[u8]) -> Result<String, AppError> {
+    let fields = protobuf_fields(header)?;
+    let signed_id = fields.iter()
+        .filter(|(number, _)| *number == 10000)
+        .map(|(_, data)| protobuf_fields(data))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .find(|(number, data)| *number == 1 && data.len() == 16)
+        .map(|(_, data)| id_from_bytes(data));
+    if let Some(id) = signed_id {
+        return Ok(id);
+    }
+
+    let key = fields.iter()
+        .filter(|(number, _)| *number == 2)
+        .map(|(_, data)| protobuf_fields(data))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .find(|(number, _)| *number == 1)
+        .map(|(_, key)| key);
+    key.map(id_from_public_key).ok_or_else(|| corrupt("the CRX3 header has no extension id or public key"))
+}
+
+/// Chrome's extension id: the first 16 bytes of the key's SHA-256, each nibble as a letter a-p
+fn id_from_public_key(key: &[u8]) -> String {
+    id_from_bytes(&Sha256::digest(key)[..16])
+}
+
+fn id_from_bytes(bytes: &[u8]) -> String {
+    bytes.iter()
+        .flat_map(|byte| [byte >> 4, byte & 0x0f])
+        .map(|nibble| char::from(b'a' + nibble))
+        .collect()
+}
+
+/// `message` from `_locales/<locale>/messages.json`, matched case-insensitively like Chrome does
+fn localized_message(archive: &[u8], locale: &str, message: &str) -> Result<Option<String>, AppError> {
+    if locale.is_empty() {
+        return Ok(None);
+    }
+    let Some(messages) = zip_file(archive, &format!("_locales/{}/messages.json", locale))? else {
+        return Ok(None);
+    };
+    let messages: Value = serde_json::from_slice(&messages).unwrap_or(Value::Null);
+    Ok(messages.as_object().and_then(|messages| {
+        messages.iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(message))
+            .and_then(|(_, entry)| entry.get("message").and_then(Value::as_str))
+            .map(str::to_string)
+    }))
+}
+
+// ============ Formats ============
+
+/// `(field number, bytes)` for each length-delimited field of a protobuf message; other wire
+/// types are skipped
+fn protobuf_fields(mut data: &[u8]) -> Result<Vec<(u64, &[u8])>, AppError> {
+    let mut fields = Vec::new();
+    while !data.is_empty() {
+        let key = read_varint(&mut data)?;
+        match key & 0x7 {
+            0 => {
+                read_varint(&mut data)?;
+            }
+            1 => data = data.get(8..).ok_or_else(|| corrupt("truncated header"))?,
+            2 => {
+                let len = read_varint(&mut data)? as usize;
+                if len > data.len() {
+                    return Err(corrupt("truncated header"));
+                }
+                let (value, rest) = data.split_at(len);
+                fields.push((key >> 3, value));
+                data = rest;
+            }
+            5 => data = data.get(4..).ok_or_else(|| corrupt("truncated header"))?,
+            _ => return Err(corrupt("malformed header")),
+        }
+    }
+    Ok(fields)
+}
+
+fn read_varint(data: &mut &[u8]) -> Result<u64, AppError> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = data.split_first().ok_or_else(|| corrupt("truncated header"))?;
+        *data = rest;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(corrupt("malformed header"))
+}
+
+/// The contents of `name` in a ZIP archive, found through the central directory.
+/// Stored and deflated entries are supported, which is all Chrome writes.
+fn zip_file(archive: &[u8], name: &str) -> Result<Option<Vec<u8>>, AppError> {
+    // The end record is 22 bytes plus a comment of up to 64 KB
+    let search_from = archive.len().saturating_sub(22 + 0xffff);
+    let end = (search_from..archive.len().saturating_sub(21))
+        .rev()
+        .find(|&at| read_u32(archive, at).ok() == Some(ZIP_END_OF_CENTRAL_DIRECTORY))
+        .ok_or_else(|| corrupt("the embedded ZIP archive is missing or truncated"))?;
+    let entries = read_u16(archive, end + 10)?;
+    let mut at = read_u32(archive, end + 16)? as usize;
+
+    for _ in 0..entries {
+        if read_u32(archive, at)? != ZIP_CENTRAL_DIRECTORY_ENTRY {
+            return Err(corrupt("the ZIP central directory is damaged"));
+        }
+        let method = read_u16(archive, at + 10)?;
+        let compressed_size = read_u32(archive, at + 20)? as usize;
+        let size = u64::from(read_u32(archive, at + 24)?);
+        let name_len = read_u16(archive, at + 28)? as usize;
+        let extra_len = read_u16(archive, at + 30)? as usize;
+        let comment_len = read_u16(archive, at + 32)? as usize;
+        let local_header = read_u32(archive, at + 42)? as usize;
+        let entry_name = slice(archive, at + 46, name_len)?;
+        at += 46 + name_len + extra_len + comment_len;
+
+        if entry_name != name.as_bytes() {
+            continue;
+        }
+        if size > MAX_MANIFEST_BYTES {
+            return Err(corrupt(&format!("{} is too large", name)));
+        }
+        if read_u32(archive, local_header)? != ZIP_LOCAL_FILE_HEADER {
+            return Err(corrupt(&format!("the ZIP entry for {} is damaged", name)));
+        }
+        let data_start = local_header + 30
+            + read_u16(archive, local_header + 26)? as usize
+            + read_u16(archive, local_header + 28)? as usize;
+        let data = slice(archive, data_start, compressed_size)?;
+
+        let contents = match method {
+            0 => data.to_vec(),
+            8 => {
+                let mut contents = Vec::new();
+                DeflateDecoder::new(data)
+                    .take(MAX_MANIFEST_BYTES)
+                    .read_to_end(&mut contents)
+                    .map_err(|e| corrupt(&format!("{} could not be decompressed: {}", name, e)))?;
+                contents
+            }
+            other => return Err(corrupt(&format!("{} uses unsupported ZIP compression method {}", name, other))),
+        };
+        return Ok(Some(contents));
+    }
+    Ok(None)
+}
+
+fn slice(bytes: &[u8], start: usize, len: usize) -> Result<&[u8], AppError> {
+    start.checked_add(len)
+        .and_then(|end| bytes.get(start..end))
+        .ok_or_else(|| corrupt("the file is truncated"))
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> Result<u32, AppError> {
+    let raw = slice(bytes, at, 4)?;
+    Ok(u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]))
+}
+
+fn read_u16(bytes: &[u8], at: usize) -> Result<u16, AppError> {
+    let raw = slice(bytes, at, 2)?;
+    Ok(u16::from_le_bytes([raw[0], raw[1]]))
+}
+
+fn corrupt(reason: &str) -> AppError {
+    AppError::Validation(format!("Invalid CRX file: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const MANIFEST: &str = r#"{
+        "manifest_version": 3, "name": "__MSG_appName__", "version": "1.2.3", "default_locale": "en",
+        "permissions": ["storage", "cookies"], "host_permissions": ["<all_urls>"]
+    }"#;
+
+    /// A minimal zip of `(name, contents, deflated)` entries
+    fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        let mut central = Vec::new();
+        for (name, data, deflate) in files {
+            let body = if *deflate {
+                let mut encoder = flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data).unwrap();
+                encoder.finish().unwrap()
+            } else {
+                data.to_vec()
+            };
+            let method: u16 = if *deflate { 8 } else { 0 };
+            let sizes = [(body.len() as u32).to_le_bytes(), (data.len() as u32).to_le_bytes()].concat();
+            let offset = out.len() as u32;
+
+            out.extend(ZIP_LOCAL_FILE_HEADER.to_le_bytes());
+            out.extend([20, 0, 0, 0]);
+            out.extend(method.to_le_bytes());
+            out.extend([0; 8]); // time, date, crc
+            out.extend(&sizes);
+            out.extend((name.len() as u16).to_le_bytes());
+            out.extend([0; 2]);
+            out.extend(name.as_bytes());
+            out.extend(&body);
+
+            central.extend(ZIP_CENTRAL_DIRECTORY_ENTRY.to_le_bytes());
+            central.extend([20, 0, 20, 0, 0, 0]);
+            central.extend(method.to_le_bytes());
+            central.extend([0; 8]);
+            central.extend(&sizes);
+            central.extend((name.len() as u16).to_le_bytes());
+            central.extend([0; 12]); // extra and comment lengths, disk, attributes
+            central.extend(offset.to_le_bytes());
+            central.extend(name.as_bytes());
+        }
+
+        let central_offset = out.len() as u32;
+        out.extend(&central);
+        out.extend(ZIP_END_OF_CENTRAL_DIRECTORY.to_le_bytes());
+        out.extend([0; 4]);
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((files.len() as u16).to_le_bytes());
+        out.extend((central.len() as u32).to_le_bytes());
+        out.extend(central_offset.to_le_bytes());
+        out.extend([0; 2]);
+        out
+    }
+
+    fn archive() -> Vec<u8> {
+        zip(&[
+            ("background.js", b"console.log(1)", false),
+            ("_locales/en/messages.json", br#"{"appname": {"message": "My Ext"}}"#, false),
+            ("manifest.json", MANIFEST.as_bytes(), true),
+        ])
+    }
+
+    /// A length-delimited protobuf field
+    fn field(number: u64, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for mut value in [number << 3 | 2, data.len() as u64] {
+            while value >= 0x80 {
+                out.push((value & 0x7f) as u8 | 0x80);
+                value >>= 7;
+            }
+            out.push(value as u8);
+        }
+        out.extend(data);
+        out
+    }
+
+    fn crx2(key: &[u8], archive: &[u8]) -> Vec<u8> {
+        let mut crx = CRX_MAGIC.to_vec();
+        crx.extend(2u32.to_le_bytes());
+        crx.extend((key.len() as u32).to_le_bytes());
+        crx.extend(3u32.to_le_bytes());
+        crx.extend(key);
+        crx.extend(b"sig");
+        crx.extend(archive);
+        crx
+    }
+
+    fn crx3(header: &[u8], archive: &[u8]) -> Vec<u8> {
+        let mut crx = CRX_MAGIC.to_vec();
+        crx.extend(3u32.to_le_bytes());
+        crx.extend((header.len() as u32).to_le_bytes());
+        crx.extend(header);
+        crx.extend(archive);
+        crx
+    }
+
+    fn key_proof() -> Vec<u8> {
+        field(2, &[field(1, b"key"), field(2, b"sig")].concat())
+    }
+
+    #[test]
+    fn crx2_id_comes_from_the_public_key() {
+        let info = parse_crx(&crx2(b"public-key-der", &archive())).unwrap();
+
+        let hash = Sha256::digest(b"public-key-der");
+        let expected: String = hash[..16].iter().flat_map(|b| [b >> 4, b & 15]).map(|n| (b'a' + n) as char).collect();
+        assert_eq!(info.id, expected);
+        assert_eq!((info.name.as_str(), info.version.as_str(), info.crx_version), ("My Ext", "1.2.3", 2));
+        assert_eq!(info.permissions, ["storage", "cookies", "<all_urls>"]);
+    }
+
+    #[test]
+    fn crx3_id_comes_from_the_signed_header_data() {
+        let crx_id: Vec<u8> = (0..16).collect();
+        let header = [key_proof(), field(10000, &field(1, &crx_id))].concat();
+        let info = parse_crx(&crx3(&header, &archive())).unwrap();
+
+        assert_eq!(info.id, "aaabacadaeafagahaiajakalamanaoap");
+        assert_eq!((info.name.as_str(), info.version.as_str(), info.crx_version), ("My Ext", "1.2.3", 3));
+
+        // Without signed data the first key proof gives the id
+        let info = parse_crx(&crx3(&key_proof(), &archive())).unwrap();
+        assert_eq!(info.id, id_from_public_key(b"key"));
+    }
+
+    #[test]
+    fn truncated_packages_are_rejected() {
+        let crx = crx2(b"public-key-der", &archive());
+        for len in [3, 12, 20, crx.len() - 30] {
+            let error = parse_crx(&crx[..len]).unwrap_err();
+            assert!(matches!(&error, AppError::Validation(m) if m.starts_with("Invalid CRX file")), "{}: {:?}", len, error);
+        }
+    }
+
+    #[test]
+    fn bad_magic_versions_and_archives_are_rejected() {
+        assert!(matches!(parse_crx(b"PK\x03\x04xxxxxxxxxxxx"), Err(AppError::Validation(_))));
+
+        let mut future = crx3(&key_proof(), &archive());
+        future[4] = 4;
+        assert!(parse_crx(&future).unwrap_err().to_string().contains("version 4"));
+
+        let no_manifest = crx3(&key_proof(), &zip(&[("a.txt", b"x", false)]));
+        assert!(parse_crx(&no_manifest).unwrap_err().to_string().contains("manifest.json"));
+
+        // Break the signature of the last central directory entry
+        let mut crx = crx3(&key_proof(), &archive());
+        let entry = crx.len() - 22 - (46 + "manifest.json".len());
+        crx[entry] ^= 0xff;
+        assert!(parse_crx(&crx).unwrap_err().to_string().contains("central directory"));
+    }
+}
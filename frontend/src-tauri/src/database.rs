@@ -1,7 +1,21 @@
-use rusqlite::{Connection, params, Result as SqlResult};
+use r2d2::{Pool, PooledConnection};
+use r2d2_sqlite::SqliteConnectionManager;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, OpenFlags, OptionalExtension, params, params_from_iter, Result as SqlResult, ToSql, Transaction, TransactionBehavior};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock, RwLockWriteGuard};
+use std::time::{Duration, Instant};
+
+use crate::error::AppError;
+use crate::geo;
+use crate::lint;
+use crate::workflow;
 
 // ============ Database Types ============
 
@@ -137,6 +151,15 @@ pub struct DbProfile {
     #[serde(rename = "lastIP")]
     pub last_ip: String,
 
+    /// Seed the fingerprint was generated from (decimal u64), empty if it wasn't seeded
+    #[serde(rename = "fingerprintSeed", default)]
+    pub fingerprint_seed: String,
+
+    /// `fingerprint_hash` of the stored fingerprint, recomputed on every write. Read only:
+    /// whatever is passed in is replaced.
+    #[serde(rename = "fingerprintHash", default)]
+    pub fingerprint_hash: String,
+
     // Metadata
     #[serde(rename = "createdAt")]
     pub created_at: String,
@@ -238,6 +261,22 @@ pub struct DbSchedule {
     pub updated_at: String,
 }
 
+/// A one-off run of a workflow at a set time, fired by the app's scheduled run timer
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbScheduledRun {
+    pub id: String,
+    #[serde(rename = "workflowId")]
+    pub workflow_id: String,
+    #[serde(rename = "runAt")]
+    pub run_at: String,
+    pub status: String,               // pending, triggered, cancelled
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    /// When the timer fired it, empty until then; later than `run_at` if the app was closed
+    #[serde(rename = "triggeredAt")]
+    pub triggered_at: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct DbExecutionHistory {
     pub id: String,
@@ -266,81 +305,359 @@ pub struct DbExecutionHistory {
 // ============ Database State ============
 
 pub struct Database {
-    conn: Arc<Mutex<Connection>>,
+    /// `relocate` swaps this and `vacuum` holds it while it rebuilds the file; everything
+    /// else only holds it while checking a connection out or reading the path
+    file: RwLock<DbFile>,
+}
+
+struct DbFile {
+    pool: Pool<SqliteConnectionManager>,
+    path: PathBuf,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbAuditEntry {
+    pub id: i64,
+    #[serde(rename = "entityType")]
+    pub entity_type: String,
+    #[serde(rename = "entityId")]
+    pub entity_id: String,
+    pub action: String,
+    pub timestamp: String,
+    #[serde(rename = "detailsJson")]
+    pub details_json: String,
+}
+
+/// One saved benchmark run; `result_json` is the sidecar's result as returned
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DbBenchmarkResult {
+    pub id: i64,
+    /// "quick" or "full"
+    pub kind: String,
+    pub engine: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "resultJson")]
+    pub result_json: String,
+}
+
+/// How one metric moved between two benchmark runs
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct BenchmarkDelta {
+    pub metric: String,
+    pub before: f64,
+    pub after: f64,
+    pub change: f64,
+    /// None when `before` is zero
+    pub percent: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VacuumResult {
+    #[serde(rename = "sizeBefore")]
+    pub size_before: u64,
+    #[serde(rename = "sizeAfter")]
+    pub size_after: u64,
+}
+
+/// How imports handle rows whose id already exists
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ImportStrategy {
+    /// Keep the existing row and drop the imported one
+    Skip,
+    /// Replace the existing row with the imported one
+    Overwrite,
+    /// Import under a new id, leaving the existing row alone
+    RegenerateId,
+}
+
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct ImportResult {
+    pub inserted: usize,
+    pub skipped: usize,
+    pub overwritten: usize,
+}
+
+impl std::ops::AddAssign for ImportResult {
+    fn add_assign(&mut self, other: Self) {
+        self.inserted += other.inserted;
+        self.skipped += other.skipped;
+        self.overwritten += other.overwritten;
+    }
+}
+
+/// Snapshot of the groups, proxies, workflows and profiles tables, written by `export_all` and read by `import_all`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Backup {
+    pub format: String,
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: i64,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    pub groups: Vec<DbGroup>,
+    pub proxies: Vec<DbProxy>,
+    pub workflows: Vec<DbWorkflow>,
+    pub profiles: Vec<DbProfile>,
+}
+
+/// Per-table outcome of `import_all`
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct BackupImportResult {
+    pub groups: ImportResult,
+    pub proxies: ImportResult,
+    pub workflows: ImportResult,
+    pub profiles: ImportResult,
+}
+
+/// One group with its profiles and the proxies they use, written by `export_group_bundle`
+/// and read by `import_group_bundle`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupBundle {
+    pub format: String,
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: i64,
+    #[serde(rename = "exportedAt")]
+    pub exported_at: String,
+    pub group: DbGroup,
+    pub proxies: Vec<DbProxy>,
+    pub profiles: Vec<DbProfile>,
+}
+
+/// Per-table outcome of `import_group_bundle`, plus the id the bundle's profiles ended up under
+#[derive(Debug, Serialize, Clone, Default, PartialEq, Eq)]
+pub struct GroupBundleImportResult {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    pub group: ImportResult,
+    pub proxies: ImportResult,
+    pub profiles: ImportResult,
+}
+
+/// Result of `relocate`
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct DbRelocation {
+    pub path: String,
+    #[serde(rename = "previousPath")]
+    pub previous_path: String,
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: u64,
+}
+
+/// Result of `check_integrity`. `guidance` says what to do when problems were found.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct IntegrityReport {
+    pub ok: bool,
+    pub issues: Vec<String>,
+    pub guidance: Option<String>,
+}
+
+/// Where the database lives and what's in it, for bug reports
+#[derive(Debug, Serialize, Clone)]
+pub struct DbInfo {
+    pub path: String,
+    /// Database file plus its write-ahead log
+    #[serde(rename = "sizeBytes")]
+    pub size_bytes: u64,
+    #[serde(rename = "profileCount")]
+    pub profile_count: i64,
+    #[serde(rename = "proxyCount")]
+    pub proxy_count: i64,
+    #[serde(rename = "workflowCount")]
+    pub workflow_count: i64,
+    #[serde(rename = "groupCount")]
+    pub group_count: i64,
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: i64,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct PurgeResult {
+    #[serde(rename = "dryRun")]
+    pub dry_run: bool,
+    /// Profiles deleted, or that would be deleted in a dry run
+    pub ids: Vec<String>,
+    /// 0 in a dry run
+    pub deleted: usize,
+}
+
+/// Which profile of a duplicate set `dedupe_profiles` keeps
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum DedupeKeep {
+    /// The earliest created
+    Oldest,
+    /// The latest created
+    Newest,
+    /// The most recently used; never-used profiles rank last
+    RecentlyUsed,
+}
+
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct DedupeResult {
+    /// One profile per duplicate set
+    pub kept: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+/// A profile pointing at a proxy or group that no longer exists
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct OrphanedReference {
+    #[serde(rename = "profileId")]
+    pub profile_id: String,
+    #[serde(rename = "profileName")]
+    pub profile_name: String,
+    /// `proxyId` or `groupId`
+    pub field: String,
+    #[serde(rename = "missingId")]
+    pub missing_id: String,
+}
+
+/// Result of `duplicate_group`
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct GroupDuplicate {
+    #[serde(rename = "groupId")]
+    pub group_id: String,
+    #[serde(rename = "clonedProfiles")]
+    pub cloned_profiles: usize,
+}
+
+/// A profile with its proxy and group resolved; either is None when unset or dangling
+#[derive(Debug, Serialize, Clone)]
+pub struct ProfileWithRelations {
+    pub profile: DbProfile,
+    pub proxy: Option<DbProxy>,
+    pub group: Option<DbGroup>,
+}
+
+/// Latency over a proxy's recent tests. The latency figures cover successful tests only
+/// and are None until one succeeds.
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct ProxyLatencyStats {
+    pub avg: Option<f64>,
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    pub p95: Option<i64>,
+    /// Share of tests that succeeded, 0.0 to 1.0
+    #[serde(rename = "successRate")]
+    pub success_rate: f64,
+    pub samples: usize,
+}
+
+/// A saved set of a profile's cookies, listed without the cookies themselves
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct CookieSnapshot {
+    #[serde(rename = "snapshotName")]
+    pub snapshot_name: String,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "cookieCount")]
+    pub cookie_count: i64,
+    pub encrypted: bool,
+}
+
+/// An extension assigned to a profile; enabled ones are loaded into its sessions
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct ProfileExtension {
+    #[serde(rename = "extensionId")]
+    pub extension_id: String,
+    pub enabled: bool,
+    #[serde(rename = "assignedAt")]
+    pub assigned_at: String,
+}
+
+/// Sidecar catalogs cached locally so browsing them doesn't need the sidecar
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Catalog {
+    Devices,
+    Engines,
+}
+
+impl Catalog {
+    fn table(&self) -> &'static str {
+        match self {
+            Catalog::Devices => "device_catalog",
+            Catalog::Engines => "engine_catalog",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CachedCatalog {
+    pub items: Vec<Value>,
+    #[serde(rename = "fetchedAt")]
+    pub fetched_at: String,
 }
 
 impl Database {
-    pub fn new() -> Result<Self, String> {
-        let db_path = get_database_path()?;
+    pub fn new() -> Result<Self, AppError> {
+        Self::open(get_database_path()?)
+    }
 
+    /// Open (creating if needed) the database at `db_path`
+    fn open(db_path: PathBuf) -> Result<Self, AppError> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
-            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // A damaged file would otherwise only show up as odd read errors later. Warn and carry
+        // on; if it is too damaged to open, the error below is the one that matters.
+        if db_path.exists() {
+            match Self::check_file_integrity(&db_path, true) {
+                Ok(report) if report.ok => {}
+                Ok(report) => log::warn!("Database quick check found problems: {}", report.issues.join("; ")),
+                Err(e) => log::warn!("Database quick check could not run: {}", e),
+            }
         }
 
-        let conn = Connection::open(&db_path).map_err(|e| e.to_string())?;
         let db = Self {
-            conn: Arc::new(Mutex::new(conn)),
+            file: RwLock::new(DbFile { pool: open_pool(&db_path)?, path: db_path }),
         };
 
         db.init_tables()?;
         Ok(db)
     }
 
-    fn init_tables(&self) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Check a connection out under the read lock, so `lock_exclusive` sees every connection
+    /// that was handed out before it took the lock
+    fn conn(&self) -> Result<PooledConnection<SqliteConnectionManager>, AppError> {
+        let file = self.file.read().unwrap_or_else(PoisonError::into_inner);
+        file.pool.get().map_err(AppError::from)
+    }
+
+    /// Take the file write lock and wait until every pooled connection has been returned, so
+    /// the caller is the only one touching the file. Gives up after `EXCLUSIVE_WAIT` rather
+    /// than hang behind a long command.
+    fn lock_exclusive(&self) -> Result<RwLockWriteGuard<'_, DbFile>, AppError> {
+        let file = self.file.write().unwrap_or_else(PoisonError::into_inner);
+        let deadline = Instant::now() + EXCLUSIVE_WAIT;
+        loop {
+            let state = file.pool.state();
+            if state.idle_connections == state.connections {
+                return Ok(file);
+            }
+            if Instant::now() >= deadline {
+                return Err(AppError::Timeout("Database is busy; try again when nothing is running".to_string()));
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    fn init_tables(&self) -> Result<(), AppError> {
+        let conn = self.conn()?;
 
         // Profiles table
+        let fingerprint = FINGERPRINT_DEFAULTS
+            .iter()
+            .map(|(column, sql_type, default)| format!("{} {} DEFAULT {}", column, sql_type, default))
+            .collect::<Vec<_>>()
+            .join(",\n                ");
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS profiles (
+            &format!("CREATE TABLE IF NOT EXISTS profiles (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
-                browser_type TEXT DEFAULT 'chrome',
-                browser_version TEXT DEFAULT '120',
-                user_agent TEXT,
-                os TEXT DEFAULT 'windows',
-                platform TEXT DEFAULT 'Win32',
-                viewport_width INTEGER DEFAULT 1920,
-                viewport_height INTEGER DEFAULT 1080,
-                screen_width INTEGER DEFAULT 1920,
-                screen_height INTEGER DEFAULT 1080,
-                color_depth INTEGER DEFAULT 24,
-                pixel_ratio REAL DEFAULT 1.0,
-                timezone_mode TEXT DEFAULT 'auto',
-                timezone TEXT DEFAULT 'America/New_York',
-                locale_mode TEXT DEFAULT 'auto',
-                locale TEXT DEFAULT 'en-US',
-                language TEXT DEFAULT 'en-US,en',
-                country TEXT DEFAULT 'US',
-                cpu_cores INTEGER DEFAULT 8,
-                device_memory INTEGER DEFAULT 8,
-                max_touch_points INTEGER DEFAULT 0,
-                webgl_image_mode TEXT DEFAULT 'noise',
-                webgl_metadata_mode TEXT DEFAULT 'custom',
-                webgl_vendor TEXT,
-                webgl_renderer TEXT,
-                canvas_noise REAL DEFAULT 0.02,
-                audio_noise REAL DEFAULT 0.0001,
-                client_rects_noise REAL DEFAULT 0.1,
-                webrtc_mode TEXT DEFAULT 'replace',
-                webrtc_public_ip TEXT DEFAULT '',
-                geo_mode TEXT DEFAULT 'query',
-                geo_latitude REAL DEFAULT 0,
-                geo_longitude REAL DEFAULT 0,
-                geo_accuracy REAL DEFAULT 100,
-                media_devices_mode TEXT DEFAULT 'real',
-                fake_cameras INTEGER DEFAULT 1,
-                fake_microphones INTEGER DEFAULT 1,
-                fake_speakers INTEGER DEFAULT 1,
-                do_not_track INTEGER DEFAULT 0,
-                block_webrtc INTEGER DEFAULT 0,
-                block_canvas INTEGER DEFAULT 0,
-                block_audio_context INTEGER DEFAULT 0,
-                block_images INTEGER DEFAULT 0,
-                block_media INTEGER DEFAULT 0,
-                fonts TEXT DEFAULT '[]',
-                plugins TEXT DEFAULT '[]',
-                speech_voices TEXT DEFAULT '[]',
+                {fingerprint},
                 proxy_id TEXT DEFAULT '',
                 group_id TEXT DEFAULT '',
                 platform_tags TEXT DEFAULT '[]',
@@ -349,11 +666,37 @@ impl Database {
                 status TEXT DEFAULT 'active',
                 last_used_at TEXT DEFAULT '',
                 last_ip TEXT DEFAULT '',
+                fingerprint_seed TEXT DEFAULT '',
+                fingerprint_hash TEXT DEFAULT '',
                 created_at TEXT NOT NULL,
                 updated_at TEXT NOT NULL
-            )",
+            )"),
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
+
+        // Columns added after a table was first released; CREATE TABLE above
+        // doesn't touch tables that already exist
+        let has_column = |table: &str, column: &str| -> SqlResult<bool> {
+            conn.query_row(
+                &format!("SELECT EXISTS(SELECT 1 FROM pragma_table_info('{}') WHERE name = ?1)", table),
+                params![column],
+                |row| row.get(0),
+            )
+        };
+        if !has_column("profiles", "fingerprint_seed")? {
+            conn.execute("ALTER TABLE profiles ADD COLUMN fingerprint_seed TEXT DEFAULT ''", [])?;
+        }
+        if !has_column("profiles", "fingerprint_hash")? {
+            conn.execute("ALTER TABLE profiles ADD COLUMN fingerprint_hash TEXT DEFAULT ''", [])?;
+            let ids = {
+                let mut stmt = conn.prepare("SELECT id FROM profiles")?;
+                let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+                rows.collect::<SqlResult<Vec<_>>>()?
+            };
+            for id in ids {
+                rehash_profile(&conn, &id)?;
+            }
+        }
 
         // Proxies table
         conn.execute(
@@ -374,7 +717,7 @@ impl Database {
                 updated_at TEXT NOT NULL
             )",
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         // Workflows table
         conn.execute(
@@ -392,7 +735,7 @@ impl Database {
                 updated_at TEXT NOT NULL
             )",
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         // Groups table
         conn.execute(
@@ -405,7 +748,7 @@ impl Database {
                 updated_at TEXT NOT NULL
             )",
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
         // Schedules table
         conn.execute(
@@ -434,7 +777,26 @@ impl Database {
                 FOREIGN KEY (workflow_id) REFERENCES workflows(id) ON DELETE CASCADE
             )",
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
+
+        // One-off workflow runs (recurring ones are `schedules`, run by the sidecar)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scheduled_runs (
+                id TEXT PRIMARY KEY,
+                workflow_id TEXT NOT NULL,
+                run_at TEXT NOT NULL,
+                status TEXT NOT NULL DEFAULT 'pending',
+                created_at TEXT NOT NULL,
+                triggered_at TEXT DEFAULT ''
+            )",
+            [],
+        )?;
+        // Foreign keys aren't enforced, so drop runs of a deleted workflow here
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS scheduled_runs_cleanup AFTER DELETE ON workflows
+             BEGIN DELETE FROM scheduled_runs WHERE workflow_id = OLD.id; END",
+            [],
+        )?;
 
         // Execution history table
         conn.execute(
@@ -454,181 +816,301 @@ impl Database {
                 logs TEXT DEFAULT '[]'
             )",
             [],
-        ).map_err(|e| e.to_string())?;
+        )?;
+
+        // Audit log table
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                entity_type TEXT NOT NULL,
+                entity_id TEXT NOT NULL,
+                action TEXT NOT NULL,
+                timestamp TEXT NOT NULL,
+                details_json TEXT DEFAULT '{}'
+            )",
+            [],
+        )?;
+
+        // Benchmark runs, kept so timings can be compared across versions
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS benchmark_results (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                kind TEXT NOT NULL,
+                engine TEXT NOT NULL DEFAULT '',
+                created_at TEXT NOT NULL,
+                result_json TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Proxy latency history (trimmed to PROXY_LATENCY_SAMPLES rows per proxy)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS proxy_latency_history (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                proxy_id TEXT NOT NULL,
+                measured_at TEXT NOT NULL,
+                latency_ms INTEGER NOT NULL,
+                success INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        // Cookie snapshots (one per profile and name; the blob may be an encrypted envelope)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS profile_cookies (
+                profile_id TEXT NOT NULL,
+                snapshot_name TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                cookie_count INTEGER NOT NULL DEFAULT 0,
+                encrypted INTEGER NOT NULL DEFAULT 0,
+                cookies_json TEXT NOT NULL,
+                PRIMARY KEY (profile_id, snapshot_name)
+            )",
+            [],
+        )?;
+        // Profiles are deleted from several places (single, batch, purge, dedupe)
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS profile_cookies_cleanup AFTER DELETE ON profiles
+             BEGIN DELETE FROM profile_cookies WHERE profile_id = OLD.id; END",
+            [],
+        )?;
+
+        // Extensions assigned to profiles (ids as the sidecar's extension manager knows them)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS profile_extensions (
+                profile_id TEXT NOT NULL,
+                extension_id TEXT NOT NULL,
+                enabled INTEGER NOT NULL DEFAULT 1,
+                assigned_at TEXT NOT NULL,
+                PRIMARY KEY (profile_id, extension_id)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TRIGGER IF NOT EXISTS profile_extensions_cleanup AFTER DELETE ON profiles
+             BEGIN DELETE FROM profile_extensions WHERE profile_id = OLD.id; END",
+            [],
+        )?;
+
+        // Catalog caches (one row per device/engine, in sidecar order)
+        for catalog in [Catalog::Devices, Catalog::Engines] {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {} (
+                        id TEXT PRIMARY KEY,
+                        position INTEGER NOT NULL,
+                        data_json TEXT NOT NULL,
+                        fetched_at TEXT NOT NULL
+                    )",
+                    catalog.table()
+                ),
+                [],
+            )?;
+        }
+
+        // Settings table (values are JSON so typed settings round-trip)
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL
+            )",
+            [],
+        )?;
 
         // Create indexes
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_group ON profiles(group_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_proxy ON profiles(proxy_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_status ON profiles(status)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_schedules_workflow ON schedules(workflow_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_schedules_enabled ON schedules(enabled)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_schedule ON execution_history(schedule_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_workflow ON execution_history(workflow_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_profile ON execution_history(profile_id)", [])
-            .map_err(|e| e.to_string())?;
-        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_started ON execution_history(started_at)", [])
-            .map_err(|e| e.to_string())?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_group ON profiles(group_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_proxy ON profiles(proxy_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_status ON profiles(status)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_updated ON profiles(updated_at)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_profiles_fingerprint_hash ON profiles(fingerprint_hash)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_workflows_status ON workflows(status)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_schedules_workflow ON schedules(workflow_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_schedules_enabled ON schedules(enabled)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_scheduled_runs_due ON scheduled_runs(status, run_at)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_schedule ON execution_history(schedule_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_workflow ON execution_history(workflow_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_profile ON execution_history(profile_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_history_started ON execution_history(started_at)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_entity ON audit_log(entity_id)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_audit_timestamp ON audit_log(timestamp)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS idx_latency_proxy ON proxy_latency_history(proxy_id, id)", [])?;
+
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
 
         Ok(())
     }
 
+    // ============ Transactions ============
+
+    /// Run several mutations atomically. `f` gets a write transaction that is committed
+    /// when it returns Ok; on Err (or a panic) it is rolled back and nothing is kept.
+    pub fn with_transaction<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&Transaction) -> Result<T, AppError>,
+    {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
     // ============ Profile CRUD ============
 
-    pub fn create_profile(&self, profile: &DbProfile) -> Result<DbProfile, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    pub fn create_profile(&self, profile: &DbProfile) -> Result<DbProfile, AppError> {
+        let mut profile = profile.clone();
+        fill_identity(&mut profile.id, &mut profile.created_at, &mut profile.updated_at);
+        warn_screen_geometry(&profile);
 
-        conn.execute(
-            "INSERT INTO profiles (
-                id, name, browser_type, browser_version, user_agent, os, platform,
-                viewport_width, viewport_height, screen_width, screen_height, color_depth, pixel_ratio,
-                timezone_mode, timezone, locale_mode, locale, language, country,
-                cpu_cores, device_memory, max_touch_points,
-                webgl_image_mode, webgl_metadata_mode, webgl_vendor, webgl_renderer,
-                canvas_noise, audio_noise, client_rects_noise,
-                webrtc_mode, webrtc_public_ip,
-                geo_mode, geo_latitude, geo_longitude, geo_accuracy,
-                media_devices_mode, fake_cameras, fake_microphones, fake_speakers,
-                do_not_track, block_webrtc, block_canvas, block_audio_context, block_images, block_media,
-                fonts, plugins, speech_voices,
-                proxy_id, group_id, platform_tags,
-                notes, bookmarks, status, last_used_at, last_ip,
-                created_at, updated_at
-            ) VALUES (
-                ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10,
-                ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20,
-                ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30,
-                ?31, ?32, ?33, ?34, ?35, ?36, ?37, ?38, ?39, ?40,
-                ?41, ?42, ?43, ?44, ?45, ?46, ?47, ?48, ?49, ?50,
-                ?51, ?52, ?53, ?54, ?55, ?56, ?57
-            )",
-            params![
-                profile.id, profile.name, profile.browser_type, profile.browser_version, profile.user_agent,
-                profile.os, profile.platform,
-                profile.viewport_width, profile.viewport_height, profile.screen_width, profile.screen_height,
-                profile.color_depth, profile.pixel_ratio,
-                profile.timezone_mode, profile.timezone, profile.locale_mode, profile.locale, profile.language, profile.country,
-                profile.cpu_cores, profile.device_memory, profile.max_touch_points,
-                profile.webgl_image_mode, profile.webgl_metadata_mode, profile.webgl_vendor, profile.webgl_renderer,
-                profile.canvas_noise, profile.audio_noise, profile.client_rects_noise,
-                profile.webrtc_mode, profile.webrtc_public_ip,
-                profile.geo_mode, profile.geo_latitude, profile.geo_longitude, profile.geo_accuracy,
-                profile.media_devices_mode, profile.fake_cameras, profile.fake_microphones, profile.fake_speakers,
-                profile.do_not_track, profile.block_webrtc, profile.block_canvas, profile.block_audio_context,
-                profile.block_images, profile.block_media,
-                profile.fonts, profile.plugins, profile.speech_voices,
-                profile.proxy_id, profile.group_id, profile.platform_tags,
-                profile.notes, profile.bookmarks, profile.status, profile.last_used_at, profile.last_ip,
-                profile.created_at, profile.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        profile.fingerprint_hash = insert_profile(&tx, &profile, "")?;
+        append_audit(&tx, "profile", &profile.id, "create", json!({ "name": profile.name }))?;
+        tx.commit()?;
+
+        Ok(profile)
+    }
 
-        Ok(profile.clone())
+    /// Insert a batch of profiles in one transaction, resolving id collisions (with
+    /// existing rows or earlier profiles in the batch) according to `strategy`
+    pub fn import_profiles(&self, profiles: &[DbProfile], strategy: ImportStrategy) -> Result<ImportResult, AppError> {
+        self.with_transaction(|tx| Ok(import_rows(tx, profiles, strategy)?.0))
     }
 
-    pub fn get_profiles(&self) -> Result<Vec<DbProfile>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    pub fn get_profiles(&self) -> Result<Vec<DbProfile>, AppError> {
+        self.query_profiles("ORDER BY created_at DESC", [])
+    }
 
-        let mut stmt = conn.prepare(
-            "SELECT
-                id, name, browser_type, browser_version, user_agent, os, platform,
-                viewport_width, viewport_height, screen_width, screen_height, color_depth, pixel_ratio,
-                timezone_mode, timezone, locale_mode, locale, language, country,
-                cpu_cores, device_memory, max_touch_points,
-                webgl_image_mode, webgl_metadata_mode, webgl_vendor, webgl_renderer,
-                canvas_noise, audio_noise, client_rects_noise,
-                webrtc_mode, webrtc_public_ip,
-                geo_mode, geo_latitude, geo_longitude, geo_accuracy,
-                media_devices_mode, fake_cameras, fake_microphones, fake_speakers,
-                do_not_track, block_webrtc, block_canvas, block_audio_context, block_images, block_media,
-                fonts, plugins, speech_voices,
-                proxy_id, group_id, platform_tags,
-                notes, bookmarks, status, last_used_at, last_ip,
-                created_at, updated_at
-            FROM profiles ORDER BY created_at DESC"
-        ).map_err(|e| e.to_string())?;
-
-        let profiles = stmt.query_map([], |row| {
-            Ok(DbProfile {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                browser_type: row.get(2)?,
-                browser_version: row.get(3)?,
-                user_agent: row.get(4)?,
-                os: row.get(5)?,
-                platform: row.get(6)?,
-                viewport_width: row.get(7)?,
-                viewport_height: row.get(8)?,
-                screen_width: row.get(9)?,
-                screen_height: row.get(10)?,
-                color_depth: row.get(11)?,
-                pixel_ratio: row.get(12)?,
-                timezone_mode: row.get(13)?,
-                timezone: row.get(14)?,
-                locale_mode: row.get(15)?,
-                locale: row.get(16)?,
-                language: row.get(17)?,
-                country: row.get(18)?,
-                cpu_cores: row.get(19)?,
-                device_memory: row.get(20)?,
-                max_touch_points: row.get(21)?,
-                webgl_image_mode: row.get(22)?,
-                webgl_metadata_mode: row.get(23)?,
-                webgl_vendor: row.get(24)?,
-                webgl_renderer: row.get(25)?,
-                canvas_noise: row.get(26)?,
-                audio_noise: row.get(27)?,
-                client_rects_noise: row.get(28)?,
-                webrtc_mode: row.get(29)?,
-                webrtc_public_ip: row.get(30)?,
-                geo_mode: row.get(31)?,
-                geo_latitude: row.get(32)?,
-                geo_longitude: row.get(33)?,
-                geo_accuracy: row.get(34)?,
-                media_devices_mode: row.get(35)?,
-                fake_cameras: row.get(36)?,
-                fake_microphones: row.get(37)?,
-                fake_speakers: row.get(38)?,
-                do_not_track: row.get(39)?,
-                block_webrtc: row.get(40)?,
-                block_canvas: row.get(41)?,
-                block_audio_context: row.get(42)?,
-                block_images: row.get(43)?,
-                block_media: row.get(44)?,
-                fonts: row.get(45)?,
-                plugins: row.get(46)?,
-                speech_voices: row.get(47)?,
-                proxy_id: row.get(48)?,
-                group_id: row.get(49)?,
-                platform_tags: row.get(50)?,
-                notes: row.get(51)?,
-                bookmarks: row.get(52)?,
-                status: row.get(53)?,
-                last_used_at: row.get(54)?,
-                last_ip: row.get(55)?,
-                created_at: row.get(56)?,
-                updated_at: row.get(57)?,
-            })
-        }).map_err(|e| e.to_string())?;
+    pub fn get_profiles_by_status(&self, status: &str) -> Result<Vec<DbProfile>, AppError> {
+        // Filter is served by idx_profiles_status
+        self.query_profiles("WHERE status = ?1 ORDER BY last_used_at DESC", params![status])
+    }
+
+    pub fn get_profiles_by_group(&self, group_id: &str) -> Result<Vec<DbProfile>, AppError> {
+        let exists: bool = self.conn()?.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)", params![group_id], |row| row.get(0)
+        )?;
+        if !exists {
+            return Err(AppError::NotFound(format!("Group not found: {}", group_id)));
+        }
+
+        self.query_profiles("WHERE group_id = ?1 ORDER BY name ASC", params![group_id])
+    }
+
+    /// Most recently used first; never-used profiles (empty `last_used_at`) go last.
+    /// julianday() compares the instants, so offsets and missing millis sort correctly.
+    pub fn get_recent_profiles(&self, limit: i64) -> Result<Vec<DbProfile>, AppError> {
+        self.query_profiles(
+            "ORDER BY COALESCE(last_used_at, '') = '', julianday(last_used_at) DESC, created_at DESC LIMIT ?1",
+            params![limit],
+        )
+    }
+
+    /// Profiles changed after `iso_timestamp`, oldest change first, for incremental sync.
+    /// `updated_at` is TEXT compared as a string, which orders correctly because every write
+    /// stores the same fixed-width UTC form (`2024-01-02T03:04:05.678Z`). The input is parsed
+    /// as RFC 3339 and converted to that form first, so offsets and missing millis still work.
+    pub fn get_profiles_updated_since(&self, iso_timestamp: &str) -> Result<Vec<DbProfile>, AppError> {
+        let since = chrono::DateTime::parse_from_rfc3339(iso_timestamp.trim())
+            .map_err(|e| AppError::Validation(format!(
+                "Invalid timestamp '{}' ({}); expected ISO-8601 like 2024-01-02T03:04:05.000Z", iso_timestamp, e
+            )))?
+            .with_timezone(&chrono::Utc)
+            .to_rfc3339_opts(chrono::SecondsFormat::Millis, true);
+
+        // Filter is served by idx_profiles_updated
+        self.query_profiles("WHERE updated_at > ?1 ORDER BY updated_at ASC", params![since])
+    }
 
-        profiles.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+    /// Profiles whose fingerprint hashes to `hash` (see `fingerprint_hash`), oldest first
+    pub fn get_profiles_by_fingerprint_hash(&self, hash: &str) -> Result<Vec<DbProfile>, AppError> {
+        // Filter is served by idx_profiles_fingerprint_hash
+        self.query_profiles(
+            "WHERE fingerprint_hash = ?1 ORDER BY julianday(created_at) ASC, id ASC",
+            params![hash.trim().to_ascii_lowercase()],
+        )
     }
 
-    pub fn get_profile(&self, id: &str) -> Result<Option<DbProfile>, String> {
+    pub fn get_profiles_by_proxy(&self, proxy_id: &str) -> Result<Vec<DbProfile>, AppError> {
+        // Filter is served by idx_profiles_proxy
+        self.query_profiles("WHERE proxy_id = ?1 ORDER BY created_at DESC", params![proxy_id])
+    }
+
+    /// Run a profile SELECT with the given `WHERE`/`ORDER BY` tail
+    fn query_profiles<P: rusqlite::Params>(&self, clause: &str, params: P) -> Result<Vec<DbProfile>, AppError> {
+        let conn = self.conn()?;
+
+        let sql = format!("SELECT {} FROM profiles {}", PROFILE_COLUMNS, clause);
+        let mut stmt = conn.prepare(&sql)?;
+        let profiles = stmt.query_map(params, row_to_profile)?;
+
+        profiles.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_profile(&self, id: &str) -> Result<Option<DbProfile>, AppError> {
         let profiles = self.get_profiles()?;
         Ok(profiles.into_iter().find(|p| p.id == id))
     }
 
-    pub fn update_profile(&self, profile: &DbProfile) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// The profile with its proxy and group in one query, for detail views
+    pub fn get_profile_with_relations(&self, id: &str) -> Result<Option<ProfileWithRelations>, AppError> {
+        Ok(self.get_profiles_with_relations(&[id.to_string()])?.remove(id))
+    }
 
-        conn.execute(
+    /// `get_profile_with_relations` for several profiles in one query, keyed by profile id.
+    /// Ids with no saved profile are left out.
+    pub fn get_profiles_with_relations(&self, ids: &[String]) -> Result<HashMap<String, ProfileWithRelations>, AppError> {
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let conn = self.conn()?;
+
+        let profile_width = PROFILE_COLUMNS.split(',').count();
+        let proxy_width = PROXY_COLUMNS.split(',').count();
+        let sql = format!(
+            "SELECT {}, {}, {} FROM profiles p
+             LEFT JOIN proxies x ON x.id = p.proxy_id AND p.proxy_id != ''
+             LEFT JOIN groups g ON g.id = p.group_id AND p.group_id != ''
+             WHERE p.id IN ({})",
+            qualified("p", PROFILE_COLUMNS),
+            qualified("x", PROXY_COLUMNS),
+            qualified("g", GROUP_COLUMNS),
+            placeholders(1, ids.len()),
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(ids), |row| {
+            let proxy = match row.get::<_, Option<String>>(profile_width)? {
+                Some(_) => Some(row_to_proxy_at(row, profile_width)?),
+                None => None,
+            };
+            let group = match row.get::<_, Option<String>>(profile_width + proxy_width)? {
+                Some(_) => Some(row_to_group_at(row, profile_width + proxy_width)?),
+                None => None,
+            };
+            Ok(ProfileWithRelations { profile: row_to_profile(row)?, proxy, group })
+        })?;
+
+        rows.map(|row| row.map(|relations| (relations.profile.id.clone(), relations)))
+            .collect::<SqlResult<HashMap<_, _>>>()
+            .map_err(AppError::from)
+    }
+
+    /// Save every field of `profile`; `updated_at` is stamped now, whatever the caller sent
+    pub fn update_profile(&self, profile: &DbProfile) -> Result<(), AppError> {
+        warn_screen_geometry(profile);
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let before = tx.query_row(
+            &format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS),
+            params![profile.id],
+            row_to_profile,
+        ).optional()?;
+
+        tx.execute(
             "UPDATE profiles SET
                 name = ?2, browser_type = ?3, browser_version = ?4, user_agent = ?5,
                 os = ?6, platform = ?7,
@@ -665,481 +1147,4584 @@ impl Database {
                 profile.fonts, profile.plugins, profile.speech_voices,
                 profile.proxy_id, profile.group_id, profile.platform_tags,
                 profile.notes, profile.bookmarks, profile.status, profile.last_used_at, profile.last_ip,
-                profile.updated_at
+                now_iso()
             ],
-        ).map_err(|e| e.to_string())?;
+        )?;
+        rehash_profile(&tx, &profile.id)?;
 
-        Ok(())
-    }
+        if let Some(before) = before {
+            let changes = diff_fields(&json!(before), &json!(profile));
+            append_audit(&tx, "profile", &profile.id, "update", json!({ "changes": changes }))?;
+        }
+        tx.commit()?;
 
-    pub fn delete_profile(&self, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM profiles WHERE id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
         Ok(())
     }
 
-    // ============ Proxy CRUD ============
+    /// Update only the given columns of a profile, plus `updated_at`, so concurrent edits to
+    /// other fields aren't overwritten. Keys are column names from `PROFILE_COLUMNS` (id and
+    /// timestamps excluded); each value must match its column's type. Returns the updated profile.
+    pub fn patch_profile(&self, id: &str, patch: &serde_json::Map<String, Value>) -> Result<DbProfile, AppError> {
+        if patch.is_empty() {
+            return Err(AppError::Validation("Patch has no fields".to_string()));
+        }
 
-    pub fn create_proxy(&self, proxy: &DbProxy) -> Result<DbProxy, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        self.with_transaction(|tx| {
+            let select = format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS);
+            let before = tx.query_row(&select, params![id], row_to_profile)
+                .optional()?
+                .ok_or_else(|| AppError::NotFound(format!("Profile not found: {}", id)))?;
 
-        conn.execute(
-            "INSERT INTO proxies (
-                id, name, proxy_type, host, port, username, password,
-                country, city, status, last_tested_at, last_ip, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-            params![
-                proxy.id, proxy.name, proxy.proxy_type, proxy.host, proxy.port,
-                proxy.username, proxy.password, proxy.country, proxy.city,
-                proxy.status, proxy.last_tested_at, proxy.last_ip,
-                proxy.created_at, proxy.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+            // Declared column types, so values are checked against the schema itself
+            let column_types: HashMap<String, String> = {
+                let mut stmt = tx.prepare("SELECT name, type FROM pragma_table_info('profiles')")?;
+                let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                rows.collect::<SqlResult<_>>()?
+            };
 
-        Ok(proxy.clone())
-    }
+            let mut assignments = Vec::with_capacity(patch.len() + 1);
+            let mut values = Vec::with_capacity(patch.len() + 2);
+            for (column, value) in patch {
+                let patchable = !matches!(column.as_str(), "id" | "created_at" | "updated_at" | "fingerprint_hash")
+                    && PROFILE_COLUMNS.split(',').any(|c| c.trim() == column);
+                let column_type = column_types.get(column).filter(|_| patchable)
+                    .ok_or_else(|| AppError::Validation(format!("Unknown or read-only profile column: {}", column)))?;
 
-    pub fn get_proxies(&self) -> Result<Vec<DbProxy>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+                let sql_value = match (column_type.as_str(), value) {
+                    ("TEXT", Value::String(text)) => SqlValue::Text(text.clone()),
+                    ("INTEGER", Value::Bool(flag)) => SqlValue::Integer(i64::from(*flag)),
+                    ("INTEGER", Value::Number(n)) if n.as_i64().is_some_and(|n| i32::try_from(n).is_ok()) => {
+                        SqlValue::Integer(n.as_i64().unwrap_or_default())
+                    }
+                    ("REAL", Value::Number(n)) => SqlValue::Real(n.as_f64().unwrap_or_default()),
+                    _ => {
+                        return Err(AppError::Validation(format!(
+                            "Column {} expects {}, got {}", column, column_type.to_ascii_lowercase(), value
+                        )));
+                    }
+                };
+                values.push(sql_value);
+                assignments.push(format!("{} = ?{}", column, values.len()));
+            }
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, proxy_type, host, port, username, password,
-                    country, city, status, last_tested_at, last_ip, created_at, updated_at
-             FROM proxies ORDER BY created_at DESC"
-        ).map_err(|e| e.to_string())?;
+            values.push(SqlValue::Text(now_iso()));
+            assignments.push(format!("updated_at = ?{}", values.len()));
+            values.push(SqlValue::Text(id.to_string()));
+            let sql = format!("UPDATE profiles SET {} WHERE id = ?{}", assignments.join(", "), values.len());
+            tx.execute(&sql, params_from_iter(values))?;
+            rehash_profile(tx, id)?;
 
-        let proxies = stmt.query_map([], |row| {
-            Ok(DbProxy {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                proxy_type: row.get(2)?,
-                host: row.get(3)?,
-                port: row.get(4)?,
-                username: row.get(5)?,
-                password: row.get(6)?,
-                country: row.get(7)?,
-                city: row.get(8)?,
-                status: row.get(9)?,
-                last_tested_at: row.get(10)?,
-                last_ip: row.get(11)?,
-                created_at: row.get(12)?,
-                updated_at: row.get(13)?,
-            })
-        }).map_err(|e| e.to_string())?;
+            let after = tx.query_row(&select, params![id], row_to_profile)?;
+            let changes = diff_fields(&json!(before), &json!(after));
+            append_audit(tx, "profile", id, "update", json!({ "changes": changes }))?;
 
-        proxies.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+            Ok(after)
+        })
     }
 
-    pub fn get_proxy(&self, id: &str) -> Result<Option<DbProxy>, String> {
-        let proxies = self.get_proxies()?;
-        Ok(proxies.into_iter().find(|p| p.id == id))
+    /// Record a launch: stamps `last_used_at`/`last_ip` (and `updated_at`) and marks the
+    /// profile active. An empty `ip` keeps the previously recorded one.
+    pub fn touch_profile_usage(&self, profile_id: &str, ip: &str, when: &str) -> Result<(), AppError> {
+        self.with_transaction(|tx| {
+            let updated = audited_profile_update(tx, &[profile_id.to_string()], || Ok(tx.execute(
+                "UPDATE profiles SET
+                    last_used_at = ?2, last_ip = COALESCE(NULLIF(?3, ''), last_ip), status = 'active',
+                    updated_at = ?4
+                WHERE id = ?1",
+                params![profile_id, when, ip, now_iso()],
+            )?))?;
+
+            if updated == 0 {
+                return Err(AppError::NotFound(format!("Profile not found: {}", profile_id)));
+            }
+            Ok(())
+        })
     }
 
-    pub fn update_proxy(&self, proxy: &DbProxy) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Change only `status` (and `updated_at`), so concurrent edits to other fields survive
+    pub fn set_profile_status(&self, id: &str, status: &str) -> Result<(), AppError> {
+        if !PROFILE_STATUSES.contains(&status) {
+            return Err(AppError::Validation(format!(
+                "Invalid profile status '{}'. Valid statuses: {}", status, PROFILE_STATUSES.join(", ")
+            )));
+        }
 
-        conn.execute(
-            "UPDATE proxies SET
-                name = ?2, proxy_type = ?3, host = ?4, port = ?5,
-                username = ?6, password = ?7, country = ?8, city = ?9,
-                status = ?10, last_tested_at = ?11, last_ip = ?12, updated_at = ?13
-            WHERE id = ?1",
-            params![
-                proxy.id, proxy.name, proxy.proxy_type, proxy.host, proxy.port,
-                proxy.username, proxy.password, proxy.country, proxy.city,
-                proxy.status, proxy.last_tested_at, proxy.last_ip, proxy.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-        Ok(())
-    }
+        let previous: String = tx
+            .query_row("SELECT status FROM profiles WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Profile not found: {}", id)))?;
+        if previous == status {
+            return Ok(());
+        }
+
+        tx.execute(
+            "UPDATE profiles SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, status, now_iso()],
+        )?;
+        append_audit(&tx, "profile", id, "update", json!({ "status": { "from": previous, "to": status } }))?;
+        tx.commit()?;
 
-    pub fn delete_proxy(&self, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM proxies WHERE id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
         Ok(())
     }
 
-    // ============ Workflow CRUD ============
+    /// Set `timezone`, `locale`, `language` and `country` together in one statement, so a
+    /// profile is never seen with a timezone from one country and a locale from another.
+    /// The timezone must be a canonical IANA zone and the country an ISO 3166-1 alpha-2 code.
+    pub fn set_profile_locale_bundle(
+        &self,
+        id: &str,
+        tz: &str,
+        locale: &str,
+        language: &str,
+        country: &str,
+    ) -> Result<(), AppError> {
+        let tz = tz.trim();
+        if !geo::is_known_timezone(tz) {
+            return Err(AppError::Validation(format!(
+                "Unknown timezone '{}'. Use an IANA zone name such as 'Europe/London'", tz
+            )));
+        }
+        let country = country.trim().to_ascii_uppercase();
+        if !geo::is_country_code(&country) {
+            return Err(AppError::Validation(format!(
+                "Unknown country code '{}'. Use an ISO 3166-1 alpha-2 code such as 'GB'", country
+            )));
+        }
+        let (locale, language) = (locale.trim(), language.trim());
+        if locale.is_empty() || language.is_empty() {
+            return Err(AppError::Validation("Locale and language are required".to_string()));
+        }
 
-    pub fn create_workflow(&self, workflow: &DbWorkflow) -> Result<DbWorkflow, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        self.with_transaction(|tx| {
+            let previous = tx.query_row(
+                "SELECT timezone, locale, language, country FROM profiles WHERE id = ?1",
+                params![id],
+                |row| Ok(json!({
+                    "timezone": row.get::<_, String>(0)?,
+                    "locale": row.get::<_, String>(1)?,
+                    "language": row.get::<_, String>(2)?,
+                    "country": row.get::<_, String>(3)?,
+                })),
+            ).optional()?
+                .ok_or_else(|| AppError::NotFound(format!("Profile not found: {}", id)))?;
 
-        conn.execute(
-            "INSERT INTO workflows (
-                id, name, description, blocks, variables, settings,
-                status, last_run_at, run_count, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-            params![
-                workflow.id, workflow.name, workflow.description,
-                workflow.blocks, workflow.variables, workflow.settings,
-                workflow.status, workflow.last_run_at, workflow.run_count,
-                workflow.created_at, workflow.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+            tx.execute(
+                "UPDATE profiles SET timezone = ?2, locale = ?3, language = ?4, country = ?5, updated_at = ?6
+                 WHERE id = ?1",
+                params![id, tz, locale, language, country, now_iso()],
+            )?;
+            rehash_profile(tx, id)?;
 
-        Ok(workflow.clone())
+            let current = json!({ "timezone": tz, "locale": locale, "language": language, "country": country });
+            if previous != current {
+                append_audit(tx, "profile", id, "update", json!({ "changes": diff_fields(&previous, &current) }))?;
+            }
+            Ok(())
+        })
     }
 
-    pub fn get_workflows(&self) -> Result<Vec<DbWorkflow>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Put every fingerprint column back to its schema default and clear the fingerprint seed.
+    /// Name, group, proxy, tags, notes, bookmarks, status and usage are kept; `updated_at` is
+    /// bumped. Returns the reset profile.
+    pub fn reset_profile_fingerprint(&self, id: &str) -> Result<DbProfile, AppError> {
+        self.with_transaction(|tx| {
+            let select = format!("SELECT {} FROM profiles WHERE id = ?1", PROFILE_COLUMNS);
+            let before = tx.query_row(&select, params![id], row_to_profile)
+                .optional()?
+                .ok_or_else(|| AppError::NotFound(format!("Profile not found: {}", id)))?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, blocks, variables, settings,
-                    status, last_run_at, run_count, created_at, updated_at
-             FROM workflows ORDER BY created_at DESC"
-        ).map_err(|e| e.to_string())?;
+            let defaults = FINGERPRINT_DEFAULTS
+                .iter()
+                .map(|(column, _, default)| format!("{} = {}", column, default))
+                .collect::<Vec<_>>()
+                .join(", ");
+            tx.execute(
+                &format!("UPDATE profiles SET {}, fingerprint_seed = '', updated_at = ?2 WHERE id = ?1", defaults),
+                params![id, now_iso()],
+            )?;
+            rehash_profile(tx, id)?;
 
-        let workflows = stmt.query_map([], |row| {
-            Ok(DbWorkflow {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                blocks: row.get(3)?,
-                variables: row.get(4)?,
-                settings: row.get(5)?,
-                status: row.get(6)?,
-                last_run_at: row.get(7)?,
-                run_count: row.get(8)?,
-                created_at: row.get(9)?,
-                updated_at: row.get(10)?,
-            })
-        }).map_err(|e| e.to_string())?;
+            let after = tx.query_row(&select, params![id], row_to_profile)?;
+            let changes = diff_fields(&json!(before), &json!(after));
+            append_audit(tx, "profile", id, "update", json!({ "changes": changes }))?;
 
-        workflows.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+            Ok(after)
+        })
     }
 
-    pub fn get_workflow(&self, id: &str) -> Result<Option<DbWorkflow>, String> {
-        let workflows = self.get_workflows()?;
-        Ok(workflows.into_iter().find(|w| w.id == id))
-    }
+    pub fn delete_profile(&self, id: &str) -> Result<(), AppError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-    pub fn update_workflow(&self, workflow: &DbWorkflow) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let name: Option<String> = tx.query_row(
+            "DELETE FROM profiles WHERE id = ?1 RETURNING name", params![id], |row| row.get(0)
+        ).optional()?;
 
-        conn.execute(
-            "UPDATE workflows SET
-                name = ?2, description = ?3, blocks = ?4, variables = ?5, settings = ?6,
-                status = ?7, last_run_at = ?8, run_count = ?9, updated_at = ?10
-            WHERE id = ?1",
-            params![
-                workflow.id, workflow.name, workflow.description,
-                workflow.blocks, workflow.variables, workflow.settings,
-                workflow.status, workflow.last_run_at, workflow.run_count, workflow.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+        if let Some(name) = name {
+            append_audit(&tx, "profile", id, "delete", json!({ "name": name }))?;
+        }
+        tx.commit()?;
 
         Ok(())
     }
 
-    pub fn delete_workflow(&self, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM workflows WHERE id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
-        Ok(())
+    pub fn delete_profiles(&self, ids: &[String]) -> Result<i32, AppError> {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        // Unknown ids simply don't match, so they never abort the batch
+        let sql = format!("DELETE FROM profiles WHERE id IN ({}) RETURNING id, name", placeholders(1, ids.len()));
+        let deleted = {
+            let mut stmt = tx.prepare(&sql)?;
+            let rows = stmt.query_map(params_from_iter(ids), |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+            rows.collect::<SqlResult<Vec<_>>>()?
+        };
+
+        for (id, name) in &deleted {
+            append_audit(&tx, "profile", id, "delete", json!({ "name": name }))?;
+        }
+        tx.commit()?;
+
+        Ok(deleted.len() as i32)
     }
 
-    // ============ Group CRUD ============
+    /// Delete every profile with `status` in one transaction. A dry run only lists them.
+    pub fn purge_profiles_by_status(&self, status: &str, dry_run: bool) -> Result<PurgeResult, AppError> {
+        if !PURGEABLE_STATUSES.contains(&status) {
+            return Err(AppError::Validation(format!(
+                "Profiles with status '{}' can't be purged. Purgeable statuses: {}", status, PURGEABLE_STATUSES.join(", ")
+            )));
+        }
 
-    pub fn create_group(&self, group: &DbGroup) -> Result<DbGroup, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-        conn.execute(
-            "INSERT INTO groups (id, name, color, description, created_at, updated_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![
-                group.id, group.name, group.color, group.description,
-                group.created_at, group.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+        let sql = if dry_run {
+            "SELECT id, name FROM profiles WHERE status = ?1"
+        } else {
+            "DELETE FROM profiles WHERE status = ?1 RETURNING id, name"
+        };
+        let profiles = {
+            let mut stmt = tx.prepare(sql)?;
+            let rows = stmt.query_map(params![status], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+            rows.collect::<SqlResult<Vec<_>>>()?
+        };
 
-        Ok(group.clone())
+        if !dry_run {
+            for (id, name) in &profiles {
+                append_audit(&tx, "profile", id, "delete", json!({ "name": name, "purgedStatus": status }))?;
+            }
+        }
+        tx.commit()?;
+
+        let ids: Vec<String> = profiles.into_iter().map(|(id, _)| id).collect();
+        Ok(PurgeResult {
+            dry_run,
+            deleted: if dry_run { 0 } else { ids.len() },
+            ids,
+        })
     }
 
-    pub fn get_groups(&self) -> Result<Vec<DbGroup>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Sets of profiles with identical fingerprints, as ids ordered oldest first. Profiles match
+    /// when their `fingerprint_hash` does, i.e. on every column in `FINGERPRINT_DEFAULTS`; id,
+    /// name, proxy, group, tags, notes, bookmarks, status, usage, seed and timestamps don't count.
+    pub fn find_duplicate_profiles(&self) -> Result<Vec<Vec<String>>, AppError> {
+        let conn = self.conn()?;
+        duplicate_profile_sets(&conn, "julianday(created_at) ASC, id ASC")
+    }
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, color, description, created_at, updated_at
-             FROM groups ORDER BY name ASC"
-        ).map_err(|e| e.to_string())?;
+    /// Delete all but one profile of each duplicate set from `find_duplicate_profiles`,
+    /// in one transaction, choosing the survivor by `keep`.
+    pub fn dedupe_profiles(&self, keep: DedupeKeep) -> Result<DedupeResult, AppError> {
+        let order = match keep {
+            DedupeKeep::Oldest => "julianday(created_at) ASC, id ASC",
+            DedupeKeep::Newest => "julianday(created_at) DESC, id ASC",
+            DedupeKeep::RecentlyUsed => "last_used_at = '' ASC, julianday(last_used_at) DESC, id ASC",
+        };
 
-        let groups = stmt.query_map([], |row| {
-            Ok(DbGroup {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                color: row.get(2)?,
-                description: row.get(3)?,
-                created_at: row.get(4)?,
-                updated_at: row.get(5)?,
-            })
-        }).map_err(|e| e.to_string())?;
+        self.with_transaction(|tx| {
+            let mut result = DedupeResult { kept: Vec::new(), deleted: Vec::new() };
+            for set in duplicate_profile_sets(tx, order)? {
+                let Some((keeper, rest)) = set.split_first() else { continue };
+                for id in rest {
+                    let name: String = tx.query_row(
+                        "DELETE FROM profiles WHERE id = ?1 RETURNING name", params![id], |row| row.get(0)
+                    )?;
+                    append_audit(tx, "profile", id, "delete", json!({ "name": name, "duplicateOf": keeper }))?;
+                }
+                result.kept.push(keeper.clone());
+                result.deleted.extend_from_slice(rest);
+            }
+            Ok(result)
+        })
+    }
+
+    /// Profiles whose `proxy_id` or `group_id` is set but matches no row. Nothing enforces
+    /// these relations, so deletes made outside `delete_proxy`/`delete_group` leave them behind.
+    pub fn find_orphaned_references(&self) -> Result<Vec<OrphanedReference>, AppError> {
+        let conn = self.conn()?;
+
+        let mut orphans = Vec::new();
+        for (field, column, table) in PROFILE_RELATIONS {
+            let mut stmt = conn.prepare(&format!(
+                "SELECT id, name, {0} FROM profiles
+                 WHERE {0} != '' AND {0} NOT IN (SELECT id FROM {1})
+                 ORDER BY name ASC",
+                column, table
+            ))?;
+            let rows = stmt.query_map([], |row| {
+                Ok(OrphanedReference {
+                    profile_id: row.get(0)?,
+                    profile_name: row.get(1)?,
+                    field: field.to_string(),
+                    missing_id: row.get(2)?,
+                })
+            })?;
+            orphans.extend(rows.collect::<SqlResult<Vec<_>>>()?);
+        }
 
-        groups.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+        Ok(orphans)
     }
 
-    pub fn update_group(&self, group: &DbGroup) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Clear every dangling `proxy_id`/`group_id` in one transaction.
+    /// Returns the number of references cleared (a profile can have two).
+    pub fn fix_orphaned_references(&self) -> Result<i32, AppError> {
+        self.with_transaction(|tx| {
+            let now = now_iso();
+            let mut fixed = 0;
+            for (field, column, table) in PROFILE_RELATIONS {
+                let cleared = {
+                    let mut stmt = tx.prepare(&format!(
+                        "UPDATE profiles SET {0} = '', updated_at = ?1
+                         WHERE {0} != '' AND {0} NOT IN (SELECT id FROM {1})
+                         RETURNING id",
+                        column, table
+                    ))?;
+                    let rows = stmt.query_map(params![now], |row| row.get::<_, String>(0))?;
+                    rows.collect::<SqlResult<Vec<_>>>()?
+                };
 
-        conn.execute(
-            "UPDATE groups SET name = ?2, color = ?3, description = ?4, updated_at = ?5
-             WHERE id = ?1",
-            params![group.id, group.name, group.color, group.description, group.updated_at],
-        ).map_err(|e| e.to_string())?;
+                for id in &cleared {
+                    append_audit(tx, "profile", id, "update", json!({ "orphanCleared": field }))?;
+                }
+                fixed += cleared.len() as i32;
+            }
+            Ok(fixed)
+        })
+    }
 
-        Ok(())
+    pub fn assign_proxy_to_profiles(&self, proxy_id: &str, profile_ids: &[String]) -> Result<i32, AppError> {
+        if profile_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        // An empty proxy_id unassigns, anything else must reference a real proxy
+        if !proxy_id.is_empty() {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM proxies WHERE id = ?1)", params![proxy_id], |row| row.get(0)
+            )?;
+            if !exists {
+                return Err(AppError::NotFound(format!("Proxy not found: {}", proxy_id)));
+            }
+        }
+
+        let now = now_iso();
+        let sql = format!(
+            "UPDATE profiles SET proxy_id = ?1, updated_at = ?2 WHERE id IN ({})",
+            placeholders(3, profile_ids.len())
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&proxy_id, &now];
+        values.extend(profile_ids.iter().map(|id| id as &dyn ToSql));
+
+        let updated = audited_profile_update(&tx, profile_ids, || Ok(tx.execute(&sql, values.as_slice())?))?;
+        tx.commit()?;
+
+        Ok(updated as i32)
     }
 
-    pub fn delete_group(&self, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-        // Also clear group_id from profiles
-        conn.execute("UPDATE profiles SET group_id = '' WHERE group_id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM groups WHERE id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
-        Ok(())
+    /// Assign `proxy_ids` round-robin across `profile_ids` (wrapping when there are
+    /// more profiles than proxies). Returns profile id -> assigned proxy id for
+    /// the profiles that exist.
+    pub fn rotate_proxies_over_profiles(&self, proxy_ids: &[String], profile_ids: &[String]) -> Result<HashMap<String, String>, AppError> {
+        if proxy_ids.is_empty() {
+            return Err(AppError::Validation("At least one proxy is required for rotation".to_string()));
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        let sql = format!("SELECT id FROM proxies WHERE id IN ({})", placeholders(1, proxy_ids.len()));
+        let found: Vec<String> = tx.prepare(&sql)?
+            .query_map(params_from_iter(proxy_ids), |row| row.get(0))?
+            .collect::<SqlResult<_>>()?;
+        let missing: Vec<&str> = proxy_ids.iter()
+            .filter(|id| !found.contains(id))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            return Err(AppError::NotFound(format!("Proxy not found: {}", missing.join(", "))));
+        }
+
+        let now = now_iso();
+        let assigned = audited_profile_update(&tx, profile_ids, || {
+            let mut assigned = HashMap::new();
+            let mut stmt = tx.prepare("UPDATE profiles SET proxy_id = ?1, updated_at = ?2 WHERE id = ?3")?;
+            for (profile_id, proxy_id) in profile_ids.iter().zip(proxy_ids.iter().cycle()) {
+                if stmt.execute(params![proxy_id, now, profile_id])? > 0 {
+                    assigned.insert(profile_id.clone(), proxy_id.clone());
+                }
+            }
+            Ok(assigned)
+        })?;
+        tx.commit()?;
+
+        Ok(assigned)
     }
 
-    // ============ Schedule CRUD ============
+    // ============ Proxy CRUD ============
 
-    pub fn create_schedule(&self, schedule: &DbSchedule) -> Result<DbSchedule, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    pub fn create_proxy(&self, proxy: &DbProxy) -> Result<DbProxy, AppError> {
+        check_proxy(proxy)?;
+        let mut proxy = proxy.clone();
+        fill_identity(&mut proxy.id, &mut proxy.created_at, &mut proxy.updated_at);
 
-        conn.execute(
-            "INSERT INTO schedules (
-                id, name, description, workflow_id, cron, cron_description,
-                enabled, run_on_start, max_retries, timeout, profile_ids, parallel_config,
-                last_run, last_status, last_error, next_run,
-                run_count, success_count, failure_count, created_at, updated_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
-            params![
-                schedule.id, schedule.name, schedule.description, schedule.workflow_id,
-                schedule.cron, schedule.cron_description,
-                schedule.enabled, schedule.run_on_start, schedule.max_retries, schedule.timeout,
-                schedule.profile_ids, schedule.parallel_config,
-                schedule.last_run, schedule.last_status, schedule.last_error, schedule.next_run,
-                schedule.run_count, schedule.success_count, schedule.failure_count,
-                schedule.created_at, schedule.updated_at
-            ],
-        ).map_err(|e| e.to_string())?;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-        Ok(schedule.clone())
+        insert_proxy(&tx, &proxy, "")?;
+        append_audit(&tx, "proxy", &proxy.id, "create", json!({ "name": proxy.name, "host": proxy.host }))?;
+        tx.commit()?;
+
+        Ok(proxy)
     }
 
-    pub fn get_schedules(&self) -> Result<Vec<DbSchedule>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    pub fn get_proxies(&self) -> Result<Vec<DbProxy>, AppError> {
+        let conn = self.conn()?;
 
-        let mut stmt = conn.prepare(
-            "SELECT id, name, description, workflow_id, cron, cron_description,
-                    enabled, run_on_start, max_retries, timeout, profile_ids, parallel_config,
-                    last_run, last_status, last_error, next_run,
-                    run_count, success_count, failure_count, created_at, updated_at
-             FROM schedules ORDER BY created_at DESC"
-        ).map_err(|e| e.to_string())?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM proxies ORDER BY created_at DESC", PROXY_COLUMNS))?;
+        let proxies = stmt.query_map([], row_to_proxy)?;
 
-        let schedules = stmt.query_map([], |row| {
-            Ok(DbSchedule {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                description: row.get(2)?,
-                workflow_id: row.get(3)?,
-                cron: row.get(4)?,
-                cron_description: row.get(5)?,
-                enabled: row.get(6)?,
-                run_on_start: row.get(7)?,
-                max_retries: row.get(8)?,
-                timeout: row.get(9)?,
-                profile_ids: row.get(10)?,
-                parallel_config: row.get(11)?,
-                last_run: row.get(12)?,
-                last_status: row.get(13)?,
-                last_error: row.get(14)?,
-                next_run: row.get(15)?,
-                run_count: row.get(16)?,
-                success_count: row.get(17)?,
-                failure_count: row.get(18)?,
-                created_at: row.get(19)?,
-                updated_at: row.get(20)?,
-            })
-        }).map_err(|e| e.to_string())?;
+        proxies.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    /// Filters combine with AND; `None` (or blank) filters are ignored, so no filters returns every proxy
+    pub fn search_proxies(
+        &self,
+        country: Option<String>,
+        status: Option<String>,
+        host_contains: Option<String>,
+    ) -> Result<Vec<DbProxy>, AppError> {
+        let given = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let mut clauses = Vec::new();
+        let mut values = Vec::new();
+        if let Some(country) = given(country) {
+            values.push(country);
+            clauses.push(format!("country = ?{} COLLATE NOCASE", values.len()));
+        }
+        if let Some(status) = given(status) {
+            values.push(status);
+            clauses.push(format!("status = ?{}", values.len()));
+        }
+        if let Some(host) = given(host_contains) {
+            // Escape LIKE wildcards so the text matches literally
+            let escaped = host.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            values.push(format!("%{}%", escaped));
+            clauses.push(format!("host LIKE ?{} ESCAPE '\\'", values.len()));
+        }
 
-        schedules.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+        let filter = if clauses.is_empty() { String::new() } else { format!("WHERE {}", clauses.join(" AND ")) };
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM proxies {} ORDER BY created_at DESC", PROXY_COLUMNS, filter))?;
+        let proxies = stmt.query_map(params_from_iter(&values), row_to_proxy)?;
+
+        proxies.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
     }
 
-    pub fn get_schedule(&self, id: &str) -> Result<Option<DbSchedule>, String> {
-        let schedules = self.get_schedules()?;
-        Ok(schedules.into_iter().find(|s| s.id == id))
+    pub fn get_proxy(&self, id: &str) -> Result<Option<DbProxy>, AppError> {
+        let proxies = self.get_proxies()?;
+        Ok(proxies.into_iter().find(|p| p.id == id))
+    }
+
+    /// The proxy stored on a profile, or None when the profile has none or isn't saved.
+    /// A `proxy_id` naming a deleted proxy is an error rather than None, so a launch never
+    /// silently goes out without the proxy the profile was meant to use.
+    pub fn get_profile_proxy(&self, profile_id: &str) -> Result<Option<DbProxy>, AppError> {
+        let Some(profile) = self.get_profile(profile_id)? else {
+            return Ok(None);
+        };
+        if profile.proxy_id.is_empty() {
+            return Ok(None);
+        }
+        self.get_proxy(&profile.proxy_id)?
+            .map(Some)
+            .ok_or_else(|| AppError::NotFound(format!("Proxy not found: {}", profile.proxy_id)))
     }
 
-    pub fn update_schedule(&self, schedule: &DbSchedule) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    pub fn update_proxy(&self, proxy: &DbProxy) -> Result<(), AppError> {
+        check_proxy(proxy)?;
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-        conn.execute(
-            "UPDATE schedules SET
-                name = ?2, description = ?3, workflow_id = ?4, cron = ?5, cron_description = ?6,
-                enabled = ?7, run_on_start = ?8, max_retries = ?9, timeout = ?10,
-                profile_ids = ?11, parallel_config = ?12,
-                last_run = ?13, last_status = ?14, last_error = ?15, next_run = ?16,
-                run_count = ?17, success_count = ?18, failure_count = ?19, updated_at = ?20
+        let before = tx.query_row(
+            &format!("SELECT {} FROM proxies WHERE id = ?1", PROXY_COLUMNS),
+            params![proxy.id],
+            row_to_proxy,
+        ).optional()?;
+
+        tx.execute(
+            "UPDATE proxies SET
+                name = ?2, proxy_type = ?3, host = ?4, port = ?5,
+                username = ?6, password = ?7, country = ?8, city = ?9,
+                status = ?10, last_tested_at = ?11, last_ip = ?12, updated_at = ?13
             WHERE id = ?1",
             params![
-                schedule.id, schedule.name, schedule.description, schedule.workflow_id,
-                schedule.cron, schedule.cron_description,
-                schedule.enabled, schedule.run_on_start, schedule.max_retries, schedule.timeout,
-                schedule.profile_ids, schedule.parallel_config,
-                schedule.last_run, schedule.last_status, schedule.last_error, schedule.next_run,
-                schedule.run_count, schedule.success_count, schedule.failure_count, schedule.updated_at
+                proxy.id, proxy.name, proxy.proxy_type, proxy.host, proxy.port,
+                proxy.username, proxy.password, proxy.country, proxy.city,
+                proxy.status, proxy.last_tested_at, proxy.last_ip, proxy.updated_at
             ],
-        ).map_err(|e| e.to_string())?;
+        )?;
 
-        Ok(())
-    }
+        if let Some(before) = before {
+            let changes = diff_fields(&json!(before), &json!(proxy));
+            append_audit(&tx, "proxy", &proxy.id, "update", json!({ "changes": changes }))?;
+        }
+        tx.commit()?;
 
-    pub fn delete_schedule(&self, id: &str) -> Result<(), String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-        conn.execute("DELETE FROM schedules WHERE id = ?1", params![id])
-            .map_err(|e| e.to_string())?;
         Ok(())
     }
 
-    // ============ Execution History CRUD ============
+    /// Profiles still using the proxy block the delete unless `force` is set, in which
+    /// case their `proxy_id` is cleared in the same transaction (like `delete_group`).
+    /// Returns the number of profiles that referenced the proxy.
+    pub fn delete_proxy(&self, id: &str, force: bool) -> Result<i32, AppError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-    pub fn create_execution(&self, execution: &DbExecutionHistory) -> Result<DbExecutionHistory, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let in_use: i32 = tx.query_row(
+            "SELECT COUNT(*) FROM profiles WHERE proxy_id = ?1", params![id], |row| row.get(0)
+        )?;
 
-        conn.execute(
-            "INSERT INTO execution_history (
-                id, schedule_id, workflow_id, profile_id, profile_name,
-                status, error, started_at, finished_at, duration,
-                steps_completed, total_steps, logs
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-            params![
-                execution.id, execution.schedule_id, execution.workflow_id,
-                execution.profile_id, execution.profile_name,
-                execution.status, execution.error,
-                execution.started_at, execution.finished_at, execution.duration,
-                execution.steps_completed, execution.total_steps, execution.logs
-            ],
-        ).map_err(|e| e.to_string())?;
+        if in_use > 0 {
+            if !force {
+                return Err(AppError::Validation(format!(
+                    "Proxy is used by {} profile(s); delete with force to unassign it from them",
+                    in_use
+                )));
+            }
+            let users: Vec<String> = tx.prepare("SELECT id FROM profiles WHERE proxy_id = ?1")?
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<SqlResult<_>>()?;
+            audited_profile_update(&tx, &users, || Ok(tx.execute(
+                "UPDATE profiles SET proxy_id = '', updated_at = ?2 WHERE proxy_id = ?1",
+                params![id, now_iso()],
+            )?))?;
+        }
 
-        Ok(execution.clone())
+        let name: Option<String> = tx.query_row(
+            "DELETE FROM proxies WHERE id = ?1 RETURNING name", params![id], |row| row.get(0)
+        ).optional()?;
+
+        if let Some(name) = name {
+            append_audit(&tx, "proxy", id, "delete", json!({ "name": name, "unassignedProfiles": in_use }))?;
+        }
+        tx.execute("DELETE FROM proxy_latency_history WHERE proxy_id = ?1", params![id])?;
+        tx.commit()?;
+
+        Ok(in_use)
     }
 
-    pub fn get_executions(&self, limit: i32, offset: i32) -> Result<Vec<DbExecutionHistory>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+    /// Record a proxy test: append it to the latency history, dropping samples beyond the
+    /// most recent `PROXY_LATENCY_SAMPLES`, and stamp the proxy's `last_tested_at` and
+    /// status (`active` or `failed`). A test succeeded if it got the proxy's egress IP,
+    /// which becomes `last_ip`; a failed test keeps the last IP seen.
+    pub fn record_proxy_test(&self, proxy_id: &str, latency_ms: i64, egress_ip: Option<&str>) -> Result<(), AppError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let now = now_iso();
+        let success = egress_ip.is_some();
 
-        let mut stmt = conn.prepare(
-            "SELECT id, schedule_id, workflow_id, profile_id, profile_name,
-                    status, error, started_at, finished_at, duration,
-                    steps_completed, total_steps, logs
-             FROM execution_history
-             ORDER BY started_at DESC
-             LIMIT ?1 OFFSET ?2"
-        ).map_err(|e| e.to_string())?;
+        let updated = tx.execute(
+            "UPDATE proxies SET last_tested_at = ?2, status = ?3, last_ip = COALESCE(?4, last_ip) WHERE id = ?1",
+            params![proxy_id, now, if success { "active" } else { "failed" }, egress_ip],
+        )?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Proxy not found: {}", proxy_id)));
+        }
 
-        let executions = stmt.query_map(params![limit, offset], |row| {
-            Ok(DbExecutionHistory {
-                id: row.get(0)?,
-                schedule_id: row.get(1)?,
-                workflow_id: row.get(2)?,
-                profile_id: row.get(3)?,
-                profile_name: row.get(4)?,
-                status: row.get(5)?,
-                error: row.get(6)?,
-                started_at: row.get(7)?,
-                finished_at: row.get(8)?,
-                duration: row.get(9)?,
-                steps_completed: row.get(10)?,
-                total_steps: row.get(11)?,
-                logs: row.get(12)?,
-            })
-        }).map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO proxy_latency_history (proxy_id, measured_at, latency_ms, success)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![proxy_id, now, latency_ms, success],
+        )?;
+        tx.execute(
+            "DELETE FROM proxy_latency_history WHERE proxy_id = ?1 AND id NOT IN (
+                SELECT id FROM proxy_latency_history WHERE proxy_id = ?1 ORDER BY id DESC LIMIT ?2
+            )",
+            params![proxy_id, PROXY_LATENCY_SAMPLES],
+        )?;
+        tx.commit()?;
 
-        executions.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+        Ok(())
     }
 
-    pub fn get_executions_by_schedule(&self, schedule_id: &str, limit: i32) -> Result<Vec<DbExecutionHistory>, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-
+    /// Latency stats over the proxy's last `PROXY_LATENCY_SAMPLES` tests; p95 is nearest-rank
+    pub fn get_proxy_latency_stats(&self, proxy_id: &str) -> Result<ProxyLatencyStats, AppError> {
+        let conn = self.conn()?;
         let mut stmt = conn.prepare(
-            "SELECT id, schedule_id, workflow_id, profile_id, profile_name,
-                    status, error, started_at, finished_at, duration,
-                    steps_completed, total_steps, logs
-             FROM execution_history
-             WHERE schedule_id = ?1
-             ORDER BY started_at DESC
-             LIMIT ?2"
-        ).map_err(|e| e.to_string())?;
+            "SELECT latency_ms, success FROM proxy_latency_history
+             WHERE proxy_id = ?1 ORDER BY id DESC LIMIT ?2",
+        )?;
+        let samples = stmt
+            .query_map(params![proxy_id, PROXY_LATENCY_SAMPLES], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?)))?
+            .collect::<SqlResult<Vec<_>>>()?;
 
-        let executions = stmt.query_map(params![schedule_id, limit], |row| {
-            Ok(DbExecutionHistory {
-                id: row.get(0)?,
-                schedule_id: row.get(1)?,
-                workflow_id: row.get(2)?,
-                profile_id: row.get(3)?,
-                profile_name: row.get(4)?,
-                status: row.get(5)?,
-                error: row.get(6)?,
-                started_at: row.get(7)?,
-                finished_at: row.get(8)?,
-                duration: row.get(9)?,
-                steps_completed: row.get(10)?,
-                total_steps: row.get(11)?,
-                logs: row.get(12)?,
-            })
-        }).map_err(|e| e.to_string())?;
+        let mut latencies: Vec<i64> = samples.iter().filter(|(_, ok)| *ok).map(|(ms, _)| *ms).collect();
+        latencies.sort_unstable();
 
-        executions.collect::<SqlResult<Vec<_>>>().map_err(|e| e.to_string())
+        let succeeded = latencies.len();
+        Ok(ProxyLatencyStats {
+            avg: (succeeded > 0).then(|| latencies.iter().sum::<i64>() as f64 / succeeded as f64),
+            min: latencies.first().copied(),
+            max: latencies.last().copied(),
+            p95: (succeeded > 0).then(|| latencies[(succeeded * 95).div_ceil(100) - 1]),
+            success_rate: if samples.is_empty() { 0.0 } else { succeeded as f64 / samples.len() as f64 },
+            samples: samples.len(),
+        })
     }
 
-    pub fn get_execution_stats(&self) -> Result<serde_json::Value, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
-
-        let total: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM execution_history", [], |row| row.get(0)
-        ).unwrap_or(0);
+    /// Proxies whose last test reached the internet through the same IP, as `(ip, proxy ids)`
+    /// ordered by IP. They aren't isolated from each other whatever their hosts say. Untested
+    /// proxies (empty `last_ip`) are ignored.
+    pub fn find_proxies_sharing_ip(&self) -> Result<Vec<(String, Vec<String>)>, AppError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT TRIM(last_ip) AS ip, id FROM proxies
+             WHERE ip IN (
+                 SELECT TRIM(last_ip) FROM proxies WHERE TRIM(last_ip) != ''
+                 GROUP BY TRIM(last_ip) HAVING COUNT(*) > 1
+             )
+             ORDER BY ip, created_at, id",
+        )?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<SqlResult<Vec<_>>>()?;
 
-        let success: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM execution_history WHERE status = 'success'", [], |row| row.get(0)
-        ).unwrap_or(0);
+        let mut groups: Vec<(String, Vec<String>)> = Vec::new();
+        for (ip, id) in rows {
+            match groups.last_mut() {
+                Some((last, ids)) if *last == ip => ids.push(id),
+                _ => groups.push((ip, vec![id])),
+            }
+        }
+        Ok(groups)
+    }
 
-        let failure: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM execution_history WHERE status = 'failure'", [], |row| row.get(0)
-        ).unwrap_or(0);
+    // ============ Cookie Snapshots ============
 
-        let avg_duration: f64 = conn.query_row(
-            "SELECT AVG(duration) FROM execution_history WHERE status = 'success'", [], |row| row.get(0)
-        ).unwrap_or(0.0);
+    /// Store `cookies_json` as the profile's snapshot `name`, replacing an existing snapshot
+    /// of that name. The blob is stored as given; `encrypted` only records what it is.
+    pub fn save_cookie_snapshot(
+        &self,
+        profile_id: &str,
+        name: &str,
+        cookies_json: &str,
+        cookie_count: usize,
+        encrypted: bool,
+    ) -> Result<CookieSnapshot, AppError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AppError::Validation("Snapshot name cannot be empty".to_string()));
+        }
 
-        Ok(serde_json::json!({
-            "total": total,
-            "success": success,
-            "failure": failure,
-            "successRate": if total > 0 { (success as f64 / total as f64 * 100.0).round() } else { 0.0 },
-            "avgDuration": avg_duration.round()
-        }))
-    }
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
 
-    pub fn delete_old_executions(&self, days: i32) -> Result<i32, String> {
-        let conn = self.conn.lock().map_err(|e| e.to_string())?;
+        let exists = tx.query_row("SELECT 1 FROM profiles WHERE id = ?1", params![profile_id], |_| Ok(()))
+            .optional()?
+            .is_some();
+        if !exists {
+            return Err(AppError::NotFound(format!("Profile not found: {}", profile_id)));
+        }
 
-        let deleted = conn.execute(
-            "DELETE FROM execution_history WHERE datetime(started_at) < datetime('now', ?1)",
-            params![format!("-{} days", days)],
-        ).map_err(|e| e.to_string())?;
+        let snapshot = CookieSnapshot {
+            snapshot_name: name.to_string(),
+            created_at: now_iso(),
+            cookie_count: cookie_count as i64,
+            encrypted,
+        };
+        tx.execute(
+            "INSERT INTO profile_cookies (profile_id, snapshot_name, created_at, cookie_count, encrypted, cookies_json)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+             ON CONFLICT(profile_id, snapshot_name) DO UPDATE SET
+                created_at = excluded.created_at,
+                cookie_count = excluded.cookie_count,
+                encrypted = excluded.encrypted,
+                cookies_json = excluded.cookies_json",
+            params![profile_id, snapshot.snapshot_name, snapshot.created_at, snapshot.cookie_count, encrypted, cookies_json],
+        )?;
+        tx.commit()?;
 
-        Ok(deleted as i32)
+        Ok(snapshot)
     }
-}
 
-// ============ Helpers ============
+    /// The stored blob of a profile's snapshot, exactly as saved
+    pub fn get_cookie_snapshot(&self, profile_id: &str, name: &str) -> Result<String, AppError> {
+        let conn = self.conn()?;
+        conn.query_row(
+            "SELECT cookies_json FROM profile_cookies WHERE profile_id = ?1 AND snapshot_name = ?2",
+            params![profile_id, name.trim()],
+            |row| row.get(0),
+        )
+        .optional()?
+        .ok_or_else(|| AppError::NotFound(format!("Cookie snapshot not found: {}", name)))
+    }
 
-fn get_database_path() -> Result<PathBuf, String> {
+    /// A profile's snapshots, newest first
+    pub fn list_cookie_snapshots(&self, profile_id: &str) -> Result<Vec<CookieSnapshot>, AppError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT snapshot_name, created_at, cookie_count, encrypted FROM profile_cookies
+             WHERE profile_id = ?1 ORDER BY created_at DESC, snapshot_name",
+        )?;
+        let snapshots = stmt.query_map(params![profile_id], |row| {
+            Ok(CookieSnapshot {
+                snapshot_name: row.get(0)?,
+                created_at: row.get(1)?,
+                cookie_count: row.get(2)?,
+                encrypted: row.get(3)?,
+            })
+        })?;
+
+        snapshots.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    // ============ Profile Extensions ============
+
+    /// Assign an extension to profiles, enabled. Profiles that already have it are re-enabled;
+    /// ids that aren't profiles are skipped. Returns how many profiles it was assigned to.
+    pub fn assign_extension_to_profiles(&self, extension_id: &str, profile_ids: &[String]) -> Result<i32, AppError> {
+        let extension_id = extension_id.trim();
+        if extension_id.is_empty() {
+            return Err(AppError::Validation("Extension id is required".to_string()));
+        }
+        if profile_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.conn()?;
+        let sql = format!(
+            "INSERT INTO profile_extensions (profile_id, extension_id, enabled, assigned_at)
+             SELECT id, ?1, 1, ?2 FROM profiles WHERE id IN ({})
+             ON CONFLICT (profile_id, extension_id) DO UPDATE SET enabled = 1",
+            placeholders(3, profile_ids.len())
+        );
+        let now = now_iso();
+        let mut values: Vec<&dyn ToSql> = vec![&extension_id, &now];
+        values.extend(profile_ids.iter().map(|id| id as &dyn ToSql));
+
+        Ok(conn.execute(&sql, values.as_slice())? as i32)
+    }
+
+    /// Remove an extension from profiles. Returns how many profiles had it.
+    pub fn unassign_extension_from_profiles(&self, extension_id: &str, profile_ids: &[String]) -> Result<i32, AppError> {
+        let extension_id = extension_id.trim();
+        if extension_id.is_empty() {
+            return Err(AppError::Validation("Extension id is required".to_string()));
+        }
+        if profile_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let conn = self.conn()?;
+        let sql = format!(
+            "DELETE FROM profile_extensions WHERE extension_id = ?1 AND profile_id IN ({})",
+            placeholders(2, profile_ids.len())
+        );
+        let mut values: Vec<&dyn ToSql> = vec![&extension_id];
+        values.extend(profile_ids.iter().map(|id| id as &dyn ToSql));
+
+        Ok(conn.execute(&sql, values.as_slice())? as i32)
+    }
+
+    /// Keep an extension assigned to a profile but stop (or resume) loading it
+    pub fn set_profile_extension_enabled(&self, profile_id: &str, extension_id: &str, enabled: bool) -> Result<(), AppError> {
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE profile_extensions SET enabled = ?3 WHERE profile_id = ?1 AND extension_id = ?2",
+            params![profile_id, extension_id, enabled],
+        )?;
+        if updated == 0 {
+            return Err(AppError::NotFound(format!(
+                "Extension {} is not assigned to profile {}", extension_id, profile_id
+            )));
+        }
+        Ok(())
+    }
+
+    /// Extensions assigned to a profile, oldest assignment first
+    pub fn get_profile_extensions(&self, profile_id: &str) -> Result<Vec<ProfileExtension>, AppError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT extension_id, enabled, assigned_at FROM profile_extensions
+             WHERE profile_id = ?1 ORDER BY assigned_at, extension_id",
+        )?;
+        let extensions = stmt.query_map(params![profile_id], |row| {
+            Ok(ProfileExtension {
+                extension_id: row.get(0)?,
+                enabled: row.get(1)?,
+                assigned_at: row.get(2)?,
+            })
+        })?;
+
+        extensions.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    // ============ Workflow CRUD ============
+
+    pub fn create_workflow(&self, workflow: &DbWorkflow) -> Result<DbWorkflow, AppError> {
+        check_workflow(workflow)?;
+        let mut workflow = workflow.clone();
+        fill_identity(&mut workflow.id, &mut workflow.created_at, &mut workflow.updated_at);
+
+        let conn = self.conn()?;
+        insert_workflow(&conn, &workflow, "")?;
+
+        Ok(workflow)
+    }
+
+    pub fn get_workflows(&self) -> Result<Vec<DbWorkflow>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM workflows ORDER BY created_at DESC", WORKFLOW_COLUMNS))?;
+        let workflows = stmt.query_map([], row_to_workflow)?;
+
+        workflows.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_workflows_by_status(&self, status: &str) -> Result<Vec<DbWorkflow>, AppError> {
+        let conn = self.conn()?;
+
+        // Filter is served by idx_workflows_status
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM workflows WHERE status = ?1 ORDER BY created_at DESC", WORKFLOW_COLUMNS
+        ))?;
+        let workflows = stmt.query_map(params![status], row_to_workflow)?;
+
+        workflows.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_workflow(&self, id: &str) -> Result<Option<DbWorkflow>, AppError> {
+        let workflows = self.get_workflows()?;
+        Ok(workflows.into_iter().find(|w| w.id == id))
+    }
+
+    pub fn update_workflow(&self, workflow: &DbWorkflow) -> Result<(), AppError> {
+        check_workflow(workflow)?;
+        let conn = self.conn()?;
+
+        conn.execute(
+            "UPDATE workflows SET
+                name = ?2, description = ?3, blocks = ?4, variables = ?5, settings = ?6,
+                status = ?7, last_run_at = ?8, run_count = ?9, updated_at = ?10
+            WHERE id = ?1",
+            params![
+                workflow.id, workflow.name, workflow.description,
+                workflow.blocks, workflow.variables, workflow.settings,
+                workflow.status, workflow.last_run_at, workflow.run_count, workflow.updated_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    /// Change only `status` (and `updated_at`), without round-tripping the `blocks` blob
+    pub fn set_workflow_status(&self, id: &str, status: &str) -> Result<(), AppError> {
+        if !WORKFLOW_STATUSES.contains(&status) {
+            return Err(AppError::Validation(format!(
+                "Invalid workflow status '{}'. Valid statuses: {}", status, WORKFLOW_STATUSES.join(", ")
+            )));
+        }
+
+        let conn = self.conn()?;
+        let updated = conn.execute(
+            "UPDATE workflows SET status = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, status, now_iso()],
+        )?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("Workflow not found: {}", id)));
+        }
+        Ok(())
+    }
+
+    pub fn record_workflow_run(&self, id: &str, ran_at: &str) -> Result<i32, AppError> {
+        let conn = self.conn()?;
+
+        // Increment in SQL so concurrent runs can't lose an update
+        conn.query_row(
+            "UPDATE workflows SET run_count = run_count + 1, last_run_at = ?2, updated_at = ?2
+             WHERE id = ?1
+             RETURNING run_count",
+            params![id, ran_at],
+            |row| row.get(0),
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound(format!("Workflow not found: {}", id)),
+            e => e.into(),
+        })
+    }
+
+    /// Copy a workflow under a new id and name. The JSON blobs are copied in SQL so
+    /// they stay byte-identical; run stats start fresh.
+    pub fn clone_workflow(&self, id: &str, new_name: &str) -> Result<DbWorkflow, AppError> {
+        let conn = self.conn()?;
+        let new_id = uuid::Uuid::new_v4().to_string();
+
+        conn.query_row(
+            &format!(
+                "INSERT INTO workflows (
+                    id, name, description, blocks, variables, settings,
+                    status, last_run_at, run_count, created_at, updated_at
+                )
+                SELECT ?2, ?3, description, blocks, variables, settings, status, '', 0, ?4, ?4
+                FROM workflows WHERE id = ?1
+                RETURNING {}",
+                WORKFLOW_COLUMNS
+            ),
+            params![id, new_id, new_name, now_iso()],
+            row_to_workflow,
+        ).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound(format!("Workflow not found: {}", id)),
+            e => e.into(),
+        })
+    }
+
+    pub fn delete_workflow(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM workflows WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // ============ Group CRUD ============
+
+    pub fn create_group(&self, group: &DbGroup) -> Result<DbGroup, AppError> {
+        let mut group = group.clone();
+        fill_identity(&mut group.id, &mut group.created_at, &mut group.updated_at);
+
+        let conn = self.conn()?;
+        insert_group(&conn, &group, "")?;
+
+        Ok(group)
+    }
+
+    /// Create a group and move profiles into it in one go. Every profile must exist,
+    /// otherwise neither the group nor any move is kept.
+    pub fn create_group_with_profiles(&self, group: &DbGroup, profile_ids: &[String]) -> Result<DbGroup, AppError> {
+        let mut group = group.clone();
+        fill_identity(&mut group.id, &mut group.created_at, &mut group.updated_at);
+
+        self.with_transaction(|tx| {
+            insert_group(tx, &group, "")?;
+
+            if profile_ids.is_empty() {
+                return Ok(group.clone());
+            }
+
+            let unique: HashSet<&String> = profile_ids.iter().collect();
+            let moved = move_profiles(tx, &group.id, profile_ids)?;
+            if moved < unique.len() {
+                return Err(AppError::NotFound(format!(
+                    "{} of {} profiles not found", unique.len() - moved, unique.len()
+                )));
+            }
+
+            Ok(group.clone())
+        })
+    }
+
+    /// Copy a group's color and description into a new group named `new_group_name` and,
+    /// with `clone_profiles`, copy its profiles into it under fresh ids, all in one transaction.
+    /// Cloned profiles keep their proxy only with `keep_proxies`; otherwise they are left
+    /// unassigned, since profiles sharing a proxy can be linked to each other. Usage
+    /// (`last_used_at`, `last_ip`) is not copied, and neither is browser data on disk.
+    pub fn duplicate_group(
+        &self,
+        group_id: &str,
+        new_group_name: &str,
+        clone_profiles: bool,
+        keep_proxies: bool,
+    ) -> Result<GroupDuplicate, AppError> {
+        let new_group_name = new_group_name.trim();
+        if new_group_name.is_empty() {
+            return Err(AppError::Validation("Group name is required".to_string()));
+        }
+
+        self.with_transaction(|tx| {
+            let source = tx.query_row(
+                &format!("SELECT {} FROM groups WHERE id = ?1", GROUP_COLUMNS),
+                params![group_id],
+                |row| row_to_group_at(row, 0),
+            ).optional()?
+                .ok_or_else(|| AppError::NotFound(format!("Group not found: {}", group_id)))?;
+
+            let now = now_iso();
+            let group = DbGroup {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: new_group_name.to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                ..source
+            };
+            insert_group(tx, &group, "")?;
+
+            if !clone_profiles {
+                return Ok(GroupDuplicate { group_id: group.id, cloned_profiles: 0 });
+            }
+
+            let members = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {} FROM profiles WHERE group_id = ?1 ORDER BY name ASC", PROFILE_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![group_id], row_to_profile)?;
+                rows.collect::<SqlResult<Vec<_>>>()?
+            };
+
+            for member in &members {
+                let clone = DbProfile {
+                    id: uuid::Uuid::new_v4().to_string(),
+                    group_id: group.id.clone(),
+                    proxy_id: if keep_proxies { member.proxy_id.clone() } else { String::new() },
+                    last_used_at: String::new(),
+                    last_ip: String::new(),
+                    created_at: now.clone(),
+                    updated_at: now.clone(),
+                    ..member.clone()
+                };
+                insert_profile(tx, &clone, "")?;
+                append_audit(tx, "profile", &clone.id, "create", json!({ "name": clone.name, "clonedFrom": member.id }))?;
+            }
+
+            Ok(GroupDuplicate { group_id: group.id, cloned_profiles: members.len() })
+        })
+    }
+
+    pub fn get_groups(&self) -> Result<Vec<DbGroup>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, color, description, created_at, updated_at
+             FROM groups ORDER BY name ASC"
+        )?;
+
+        let groups = stmt.query_map([], |row| {
+            Ok(DbGroup {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            })
+        })?;
+
+        groups.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    /// Every group with its profile count (empty groups included), ordered like `get_groups`
+    pub fn get_groups_with_counts(&self) -> Result<Vec<(DbGroup, i64)>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT g.id, g.name, g.color, g.description, g.created_at, g.updated_at, COUNT(p.id)
+             FROM groups g
+             LEFT JOIN profiles p ON p.group_id = g.id
+             GROUP BY g.id
+             ORDER BY g.name ASC"
+        )?;
+
+        let groups = stmt.query_map([], |row| {
+            let group = DbGroup {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                color: row.get(2)?,
+                description: row.get(3)?,
+                created_at: row.get(4)?,
+                updated_at: row.get(5)?,
+            };
+            Ok((group, row.get(6)?))
+        })?;
+
+        groups.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn update_group(&self, group: &DbGroup) -> Result<(), AppError> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "UPDATE groups SET name = ?2, color = ?3, description = ?4, updated_at = ?5
+             WHERE id = ?1",
+            params![group.id, group.name, group.color, group.description, group.updated_at],
+        )?;
+
+        Ok(())
+    }
+
+    /// Rename a group, rejecting names another group already uses (case-insensitive).
+    /// Renaming a group to its current name changes nothing.
+    pub fn rename_group(&self, id: &str, new_name: &str) -> Result<(), AppError> {
+        let new_name = new_name.trim();
+        if new_name.is_empty() {
+            return Err(AppError::Validation("Group name cannot be empty".to_string()));
+        }
+
+        let conn = self.conn()?;
+
+        let current: String = conn
+            .query_row("SELECT name FROM groups WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Group {} not found", id)))?;
+        if current == new_name {
+            return Ok(());
+        }
+
+        let taken: bool = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM groups WHERE name = ?1 COLLATE NOCASE AND id != ?2)",
+            params![new_name, id],
+            |row| row.get(0),
+        )?;
+        if taken {
+            return Err(AppError::Validation(format!("A group named '{}' already exists", new_name)));
+        }
+
+        conn.execute(
+            "UPDATE groups SET name = ?2, updated_at = ?3 WHERE id = ?1",
+            params![id, new_name, now_iso()],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn delete_group(&self, id: &str) -> Result<(), AppError> {
+        self.with_transaction(|tx| {
+            // Also clear group_id from profiles
+            let members: Vec<String> = tx.prepare("SELECT id FROM profiles WHERE group_id = ?1")?
+                .query_map(params![id], |row| row.get(0))?
+                .collect::<SqlResult<_>>()?;
+            if !members.is_empty() {
+                move_profiles(tx, "", &members)?;
+            }
+            tx.execute("DELETE FROM groups WHERE id = ?1", params![id])?;
+            Ok(())
+        })
+    }
+
+    pub fn move_profiles_to_group(&self, group_id: &str, profile_ids: &[String]) -> Result<i32, AppError> {
+        if profile_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+
+        // An empty group_id means ungrouped, anything else must reference a real group
+        if !group_id.is_empty() {
+            let exists: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)", params![group_id], |row| row.get(0)
+            )?;
+            if !exists {
+                return Err(AppError::NotFound(format!("Group not found: {}", group_id)));
+            }
+        }
+
+        let moved = move_profiles(&tx, group_id, profile_ids)?;
+        tx.commit()?;
+
+        Ok(moved as i32)
+    }
+
+    /// Move every profile whose `platform_tags` array contains `tag` (a whole, case-sensitive
+    /// element, so "fb" doesn't match "fbads") into `group_id`. Profiles already in the group
+    /// and malformed tag lists are skipped. Returns how many were moved.
+    pub fn group_profiles_by_tag(&self, tag: &str, group_id: &str) -> Result<i32, AppError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(AppError::Validation("Tag cannot be empty".to_string()));
+        }
+
+        self.with_transaction(|tx| {
+            if !group_id.is_empty() {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS(SELECT 1 FROM groups WHERE id = ?1)", params![group_id], |row| row.get(0)
+                )?;
+                if !exists {
+                    return Err(AppError::NotFound(format!("Group not found: {}", group_id)));
+                }
+            }
+
+            let ids = {
+                let mut stmt = tx.prepare(
+                    "SELECT id FROM profiles
+                     WHERE group_id != ?2 AND EXISTS (
+                        SELECT 1 FROM json_each(CASE WHEN json_valid(platform_tags) THEN platform_tags ELSE '[]' END)
+                        WHERE type = 'text' AND value = ?1
+                     )",
+                )?;
+                let rows = stmt.query_map(params![tag, group_id], |row| row.get::<_, String>(0))?;
+                rows.collect::<SqlResult<Vec<_>>>()?
+            };
+            if ids.is_empty() {
+                return Ok(0);
+            }
+
+            Ok(move_profiles(tx, group_id, &ids)? as i32)
+        })
+    }
+
+    // ============ Schedule CRUD ============
+
+    pub fn create_schedule(&self, schedule: &DbSchedule) -> Result<DbSchedule, AppError> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "INSERT INTO schedules (
+                id, name, description, workflow_id, cron, cron_description,
+                enabled, run_on_start, max_retries, timeout, profile_ids, parallel_config,
+                last_run, last_status, last_error, next_run,
+                run_count, success_count, failure_count, created_at, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+            params![
+                schedule.id, schedule.name, schedule.description, schedule.workflow_id,
+                schedule.cron, schedule.cron_description,
+                schedule.enabled, schedule.run_on_start, schedule.max_retries, schedule.timeout,
+                schedule.profile_ids, schedule.parallel_config,
+                schedule.last_run, schedule.last_status, schedule.last_error, schedule.next_run,
+                schedule.run_count, schedule.success_count, schedule.failure_count,
+                schedule.created_at, schedule.updated_at
+            ],
+        )?;
+
+        Ok(schedule.clone())
+    }
+
+    pub fn get_schedules(&self) -> Result<Vec<DbSchedule>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, name, description, workflow_id, cron, cron_description,
+                    enabled, run_on_start, max_retries, timeout, profile_ids, parallel_config,
+                    last_run, last_status, last_error, next_run,
+                    run_count, success_count, failure_count, created_at, updated_at
+             FROM schedules ORDER BY created_at DESC"
+        )?;
+
+        let schedules = stmt.query_map([], |row| {
+            Ok(DbSchedule {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                description: row.get(2)?,
+                workflow_id: row.get(3)?,
+                cron: row.get(4)?,
+                cron_description: row.get(5)?,
+                enabled: row.get(6)?,
+                run_on_start: row.get(7)?,
+                max_retries: row.get(8)?,
+                timeout: row.get(9)?,
+                profile_ids: row.get(10)?,
+                parallel_config: row.get(11)?,
+                last_run: row.get(12)?,
+                last_status: row.get(13)?,
+                last_error: row.get(14)?,
+                next_run: row.get(15)?,
+                run_count: row.get(16)?,
+                success_count: row.get(17)?,
+                failure_count: row.get(18)?,
+                created_at: row.get(19)?,
+                updated_at: row.get(20)?,
+            })
+        })?;
+
+        schedules.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_schedule(&self, id: &str) -> Result<Option<DbSchedule>, AppError> {
+        let schedules = self.get_schedules()?;
+        Ok(schedules.into_iter().find(|s| s.id == id))
+    }
+
+    pub fn update_schedule(&self, schedule: &DbSchedule) -> Result<(), AppError> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "UPDATE schedules SET
+                name = ?2, description = ?3, workflow_id = ?4, cron = ?5, cron_description = ?6,
+                enabled = ?7, run_on_start = ?8, max_retries = ?9, timeout = ?10,
+                profile_ids = ?11, parallel_config = ?12,
+                last_run = ?13, last_status = ?14, last_error = ?15, next_run = ?16,
+                run_count = ?17, success_count = ?18, failure_count = ?19, updated_at = ?20
+            WHERE id = ?1",
+            params![
+                schedule.id, schedule.name, schedule.description, schedule.workflow_id,
+                schedule.cron, schedule.cron_description,
+                schedule.enabled, schedule.run_on_start, schedule.max_retries, schedule.timeout,
+                schedule.profile_ids, schedule.parallel_config,
+                schedule.last_run, schedule.last_status, schedule.last_error, schedule.next_run,
+                schedule.run_count, schedule.success_count, schedule.failure_count, schedule.updated_at
+            ],
+        )?;
+
+        Ok(())
+    }
+
+    pub fn delete_schedule(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn()?;
+        conn.execute("DELETE FROM schedules WHERE id = ?1", params![id])?;
+        Ok(())
+    }
+
+    // ============ Scheduled Run CRUD ============
+
+    /// Schedule `workflow_id` to run once at `run_at` (RFC 3339, in the future). Stored in the
+    /// same fixed-width UTC form as every other timestamp, so due runs are found by string order.
+    pub fn schedule_workflow_run(&self, workflow_id: &str, run_at: &str) -> Result<DbScheduledRun, AppError> {
+        let run_at = chrono::DateTime::parse_from_rfc3339(run_at.trim())
+            .map_err(|e| AppError::Validation(format!(
+                "Invalid run time '{}' ({}); expected ISO-8601 like 2024-01-02T03:00:00.000Z", run_at, e
+            )))?
+            .with_timezone(&chrono::Utc);
+        if run_at <= chrono::Utc::now() {
+            return Err(AppError::Validation("Run time must be in the future".to_string()));
+        }
+        if self.get_workflow(workflow_id)?.is_none() {
+            return Err(AppError::NotFound(format!("Workflow not found: {}", workflow_id)));
+        }
+
+        let run = DbScheduledRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            workflow_id: workflow_id.to_string(),
+            run_at: run_at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+            status: "pending".to_string(),
+            created_at: now_iso(),
+            triggered_at: String::new(),
+        };
+        let conn = self.conn()?;
+        conn.execute(
+            &format!("INSERT INTO scheduled_runs ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6)", SCHEDULED_RUN_COLUMNS),
+            params![run.id, run.workflow_id, run.run_at, run.status, run.created_at, run.triggered_at],
+        )?;
+
+        Ok(run)
+    }
+
+    /// Cancel a run that hasn't fired yet
+    pub fn cancel_scheduled_run(&self, id: &str) -> Result<(), AppError> {
+        let conn = self.conn()?;
+        let status: Option<String> = conn
+            .query_row("SELECT status FROM scheduled_runs WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?;
+        match status.as_deref() {
+            None => Err(AppError::NotFound(format!("Scheduled run not found: {}", id))),
+            Some("pending") => {
+                conn.execute("UPDATE scheduled_runs SET status = 'cancelled' WHERE id = ?1 AND status = 'pending'", params![id])?;
+                Ok(())
+            }
+            Some(status) => Err(AppError::Validation(format!("Scheduled run {} is already {}", id, status))),
+        }
+    }
+
+    /// Every scheduled run, soonest first
+    pub fn list_scheduled_runs(&self) -> Result<Vec<DbScheduledRun>, AppError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM scheduled_runs ORDER BY run_at ASC, created_at ASC", SCHEDULED_RUN_COLUMNS))?;
+        let runs = stmt.query_map([], row_to_scheduled_run)?;
+        Ok(runs.collect::<SqlResult<Vec<_>>>()?)
+    }
+
+    /// Move pending runs that fell due before `run_at` (while the app was closed) to `run_at`,
+    /// so they fire once the frontend is listening rather than during startup. Returns how many moved.
+    pub fn reschedule_overdue_runs(&self, run_at: &str) -> Result<usize, AppError> {
+        let conn = self.conn()?;
+        let moved = conn.execute(
+            "UPDATE scheduled_runs SET run_at = ?1 WHERE status = 'pending' AND run_at < ?1",
+            params![run_at],
+        )?;
+        Ok(moved)
+    }
+
+    /// Mark pending runs due by now as triggered and return them, soonest first. Runs that fell
+    /// due while the app was closed are returned on the first call after it starts. A run is
+    /// only ever returned once, even with several callers.
+    pub fn take_due_scheduled_runs(&self) -> Result<Vec<DbScheduledRun>, AppError> {
+        let now = now_iso();
+        self.with_transaction(|tx| {
+            let mut runs = {
+                let mut stmt = tx.prepare(&format!(
+                    "SELECT {} FROM scheduled_runs WHERE status = 'pending' AND run_at <= ?1 ORDER BY run_at ASC",
+                    SCHEDULED_RUN_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![now], row_to_scheduled_run)?;
+                rows.collect::<SqlResult<Vec<_>>>()?
+            };
+            for run in &mut runs {
+                tx.execute(
+                    "UPDATE scheduled_runs SET status = 'triggered', triggered_at = ?2 WHERE id = ?1",
+                    params![run.id, now],
+                )?;
+                run.status = "triggered".to_string();
+                run.triggered_at = now.clone();
+            }
+            Ok(runs)
+        })
+    }
+
+    // ============ Execution History CRUD ============
+
+    pub fn create_execution(&self, execution: &DbExecutionHistory) -> Result<DbExecutionHistory, AppError> {
+        let conn = self.conn()?;
+
+        conn.execute(
+            "INSERT INTO execution_history (
+                id, schedule_id, workflow_id, profile_id, profile_name,
+                status, error, started_at, finished_at, duration,
+                steps_completed, total_steps, logs
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                execution.id, execution.schedule_id, execution.workflow_id,
+                execution.profile_id, execution.profile_name,
+                execution.status, execution.error,
+                execution.started_at, execution.finished_at, execution.duration,
+                execution.steps_completed, execution.total_steps, execution.logs
+            ],
+        )?;
+
+        Ok(execution.clone())
+    }
+
+    pub fn get_executions(&self, limit: i32, offset: i32) -> Result<Vec<DbExecutionHistory>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, schedule_id, workflow_id, profile_id, profile_name,
+                    status, error, started_at, finished_at, duration,
+                    steps_completed, total_steps, logs
+             FROM execution_history
+             ORDER BY started_at DESC
+             LIMIT ?1 OFFSET ?2"
+        )?;
+
+        let executions = stmt.query_map(params![limit, offset], |row| {
+            Ok(DbExecutionHistory {
+                id: row.get(0)?,
+                schedule_id: row.get(1)?,
+                workflow_id: row.get(2)?,
+                profile_id: row.get(3)?,
+                profile_name: row.get(4)?,
+                status: row.get(5)?,
+                error: row.get(6)?,
+                started_at: row.get(7)?,
+                finished_at: row.get(8)?,
+                duration: row.get(9)?,
+                steps_completed: row.get(10)?,
+                total_steps: row.get(11)?,
+                logs: row.get(12)?,
+            })
+        })?;
+
+        executions.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_executions_by_schedule(&self, schedule_id: &str, limit: i32) -> Result<Vec<DbExecutionHistory>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, schedule_id, workflow_id, profile_id, profile_name,
+                    status, error, started_at, finished_at, duration,
+                    steps_completed, total_steps, logs
+             FROM execution_history
+             WHERE schedule_id = ?1
+             ORDER BY started_at DESC
+             LIMIT ?2"
+        )?;
+
+        let executions = stmt.query_map(params![schedule_id, limit], |row| {
+            Ok(DbExecutionHistory {
+                id: row.get(0)?,
+                schedule_id: row.get(1)?,
+                workflow_id: row.get(2)?,
+                profile_id: row.get(3)?,
+                profile_name: row.get(4)?,
+                status: row.get(5)?,
+                error: row.get(6)?,
+                started_at: row.get(7)?,
+                finished_at: row.get(8)?,
+                duration: row.get(9)?,
+                steps_completed: row.get(10)?,
+                total_steps: row.get(11)?,
+                logs: row.get(12)?,
+            })
+        })?;
+
+        executions.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    pub fn get_execution_stats(&self) -> Result<serde_json::Value, AppError> {
+        let conn = self.conn()?;
+
+        let total: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM execution_history", [], |row| row.get(0)
+        ).unwrap_or(0);
+
+        let success: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM execution_history WHERE status = 'success'", [], |row| row.get(0)
+        ).unwrap_or(0);
+
+        let failure: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM execution_history WHERE status = 'failure'", [], |row| row.get(0)
+        ).unwrap_or(0);
+
+        let avg_duration: f64 = conn.query_row(
+            "SELECT AVG(duration) FROM execution_history WHERE status = 'success'", [], |row| row.get(0)
+        ).unwrap_or(0.0);
+
+        Ok(serde_json::json!({
+            "total": total,
+            "success": success,
+            "failure": failure,
+            "successRate": if total > 0 { (success as f64 / total as f64 * 100.0).round() } else { 0.0 },
+            "avgDuration": avg_duration.round()
+        }))
+    }
+
+    pub fn delete_old_executions(&self, days: i32) -> Result<i32, AppError> {
+        let conn = self.conn()?;
+
+        let deleted = conn.execute(
+            "DELETE FROM execution_history WHERE datetime(started_at) < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?;
+
+        Ok(deleted as i32)
+    }
+
+    // ============ Audit Log ============
+
+    /// Newest entries first, optionally limited to one entity
+    pub fn get_audit_log(&self, entity_id: Option<&str>, limit: i64) -> Result<Vec<DbAuditEntry>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, entity_type, entity_id, action, timestamp, details_json
+             FROM audit_log
+             WHERE ?1 IS NULL OR entity_id = ?1
+             ORDER BY id DESC
+             LIMIT ?2"
+        )?;
+
+        let entries = stmt.query_map(params![entity_id, limit], |row| {
+            Ok(DbAuditEntry {
+                id: row.get(0)?,
+                entity_type: row.get(1)?,
+                entity_id: row.get(2)?,
+                action: row.get(3)?,
+                timestamp: row.get(4)?,
+                details_json: row.get(5)?,
+            })
+        })?;
+
+        entries.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    /// Retention: drop audit entries older than `days`
+    pub fn trim_audit_log(&self, days: i32) -> Result<i32, AppError> {
+        let conn = self.conn()?;
+
+        let deleted = conn.execute(
+            "DELETE FROM audit_log WHERE datetime(timestamp) < datetime('now', ?1)",
+            params![format!("-{} days", days)],
+        )?;
+
+        Ok(deleted as i32)
+    }
+
+    // ============ Benchmark Results ============
+
+    pub fn record_benchmark(&self, kind: &str, engine: &str, result: &Value) -> Result<DbBenchmarkResult, AppError> {
+        let conn = self.conn()?;
+        let created_at = now_iso();
+        let result_json = result.to_string();
+        conn.execute(
+            "INSERT INTO benchmark_results (kind, engine, created_at, result_json) VALUES (?1, ?2, ?3, ?4)",
+            params![kind, engine, created_at, result_json],
+        )?;
+
+        Ok(DbBenchmarkResult {
+            id: conn.last_insert_rowid(),
+            kind: kind.to_string(),
+            engine: engine.to_string(),
+            created_at,
+            result_json,
+        })
+    }
+
+    /// Newest runs first
+    pub fn get_benchmark_history(&self, limit: i64) -> Result<Vec<DbBenchmarkResult>, AppError> {
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, kind, engine, created_at, result_json FROM benchmark_results ORDER BY id DESC LIMIT ?1",
+        )?;
+        let runs = stmt.query_map(params![limit], row_to_benchmark)?;
+
+        runs.collect::<SqlResult<Vec<_>>>().map_err(AppError::from)
+    }
+
+    /// Metrics present in both runs, in `before`'s order; `after - before` is the change
+    pub fn compare_benchmark_runs(&self, before_id: i64, after_id: i64) -> Result<Vec<BenchmarkDelta>, AppError> {
+        let before = benchmark_metrics(&self.benchmark_result(before_id)?);
+        let after = benchmark_metrics(&self.benchmark_result(after_id)?);
+
+        Ok(before.into_iter()
+            .filter_map(|(metric, before)| {
+                let (_, after) = after.iter().find(|(name, _)| *name == metric)?;
+                let change = after - before;
+                let percent = (before != 0.0).then(|| change / before * 100.0);
+                Some(BenchmarkDelta { metric, before, after: *after, change, percent })
+            })
+            .collect())
+    }
+
+    /// One row per run, oldest first: id, kind, engine and time, then a column per metric
+    /// seen in any run (blank where a run lacks it). Returns how many runs were written.
+    pub fn export_benchmark_csv(&self, dest: &Path) -> Result<usize, AppError> {
+        if dest.exists() && dest.canonicalize()? == self.path().canonicalize()? {
+            return Err(AppError::Validation("Refusing to overwrite the database file".to_string()));
+        }
+
+        let runs = {
+            let conn = self.conn()?;
+            let mut stmt = conn.prepare(
+                "SELECT id, kind, engine, created_at, result_json FROM benchmark_results ORDER BY id",
+            )?;
+            let runs = stmt.query_map([], row_to_benchmark)?;
+            runs.collect::<SqlResult<Vec<_>>>()?
+        };
+        let metrics: Vec<Vec<(String, f64)>> = runs.iter()
+            .map(|run| benchmark_metrics(&serde_json::from_str(&run.result_json).unwrap_or(Value::Null)))
+            .collect();
+        let mut columns: Vec<&str> = Vec::new();
+        for (metric, _) in metrics.iter().flatten() {
+            if !columns.contains(&metric.as_str()) {
+                columns.push(metric);
+            }
+        }
+
+        let mut writer = BufWriter::new(File::create(dest)?);
+        let header = ["id", "kind", "engine", "createdAt"].into_iter().chain(columns.iter().copied());
+        write_csv_record(&mut writer, header.map(str::to_string))?;
+        for (run, metrics) in runs.iter().zip(&metrics) {
+            let values = columns.iter().map(|column| {
+                metrics.iter()
+                    .find(|(metric, _)| metric == column)
+                    .map(|(_, value)| value.to_string())
+                    .unwrap_or_default()
+            });
+            let fields = [run.id.to_string(), run.kind.clone(), run.engine.clone(), run.created_at.clone()];
+            write_csv_record(&mut writer, fields.into_iter().chain(values))?;
+        }
+        writer.flush()?;
+
+        Ok(runs.len())
+    }
+
+    fn benchmark_result(&self, id: i64) -> Result<Value, AppError> {
+        let conn = self.conn()?;
+        let raw: String = conn
+            .query_row("SELECT result_json FROM benchmark_results WHERE id = ?1", params![id], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| AppError::NotFound(format!("Benchmark run not found: {}", id)))?;
+        serde_json::from_str(&raw).map_err(|e| AppError::Validation(format!("Benchmark run {} is not valid JSON: {}", id, e)))
+    }
+
+    // ============ Settings ============
+
+    /// Stored value for `key`, else its default, else null
+    pub fn get_setting(&self, key: &str) -> Result<Value, AppError> {
+        let conn = self.conn()?;
+
+        let stored: Option<String> = conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", params![key], |row| row.get(0))
+            .optional()?;
+
+        Ok(match stored {
+            Some(raw) => parse_setting(raw),
+            None => default_setting(key).unwrap_or(Value::Null),
+        })
+    }
+
+    pub fn set_setting(&self, key: &str, value: &Value) -> Result<(), AppError> {
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(AppError::Validation("Setting key cannot be empty".to_string()));
+        }
+
+        let conn = self.conn()?;
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![key, value.to_string()],
+        )?;
+
+        Ok(())
+    }
+
+    /// Every default overlaid with whatever has been stored
+    pub fn get_all_settings(&self) -> Result<Value, AppError> {
+        let conn = self.conn()?;
+
+        let mut settings: serde_json::Map<String, Value> = DEFAULT_SETTINGS
+            .iter()
+            .filter_map(|key| Some((key.to_string(), default_setting(key)?)))
+            .collect();
+
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+        for row in rows {
+            let (key, raw) = row?;
+            settings.insert(key, parse_setting(raw));
+        }
+
+        Ok(Value::Object(settings))
+    }
+
+    // ============ Catalog Cache ============
+
+    /// Cached catalog, or None if it has never been fetched
+    pub fn get_catalog(&self, catalog: Catalog) -> Result<Option<CachedCatalog>, AppError> {
+        let conn = self.conn()?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT data_json, fetched_at FROM {} ORDER BY position",
+            catalog.table()
+        ))?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?;
+
+        let mut items = Vec::new();
+        let mut fetched_at = String::new();
+        for row in rows {
+            let (data, row_fetched_at) = row?;
+            items.push(serde_json::from_str(&data)?);
+            // Rows are written together, but report the oldest to be safe
+            if fetched_at.is_empty() || row_fetched_at < fetched_at {
+                fetched_at = row_fetched_at;
+            }
+        }
+
+        Ok((!items.is_empty()).then_some(CachedCatalog { items, fetched_at }))
+    }
+
+    /// Replace the cached catalog with a fresh copy from the sidecar
+    pub fn replace_catalog(&self, catalog: Catalog, items: &[Value]) -> Result<CachedCatalog, AppError> {
+        let mut conn = self.conn()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let fetched_at = now_iso();
+
+        tx.execute(&format!("DELETE FROM {}", catalog.table()), [])?;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT OR REPLACE INTO {} (id, position, data_json, fetched_at) VALUES (?1, ?2, ?3, ?4)",
+                catalog.table()
+            ))?;
+            for (position, item) in items.iter().enumerate() {
+                let id = item.get("id")
+                    .and_then(Value::as_str)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| position.to_string());
+                stmt.execute(params![id, position as i64, item.to_string(), fetched_at])?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(CachedCatalog { items: items.to_vec(), fetched_at })
+    }
+
+    // ============ Maintenance ============
+
+    /// Rebuild the database file to reclaim space left by deletes. Waits for running commands
+    /// to hand back their connections and holds everything else off until the rebuild is done
+    /// (usually well under a second).
+    pub fn vacuum(&self) -> Result<VacuumResult, AppError> {
+        let file = self.lock_exclusive()?;
+        let conn = file.pool.get()?;
+        let file_size = || -> Result<u64, AppError> { Ok(std::fs::metadata(&file.path)?.len()) };
+
+        // Sizes are of the main file, so fold the WAL into it before measuring
+        conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE)")?;
+        let size_before = file_size()?;
+        conn.execute_batch("VACUUM; PRAGMA wal_checkpoint(TRUNCATE);")?;
+        let size_after = file_size()?;
+
+        Ok(VacuumResult { size_before, size_after })
+    }
+
+    /// Full `PRAGMA integrity_check` plus `PRAGMA foreign_key_check` on this database
+    pub fn check_integrity(&self) -> Result<IntegrityReport, AppError> {
+        let conn = self.conn()?;
+        Ok(integrity_report(&conn, &self.path(), "integrity_check"))
+    }
+
+    /// `check_integrity` for a file that may be too damaged to open as a `Database`. `quick`
+    /// runs `PRAGMA quick_check`, which skips matching indexes against their tables.
+    pub fn check_file_integrity(path: &Path, quick: bool) -> Result<IntegrityReport, AppError> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        Ok(integrity_report(&conn, path, if quick { "quick_check" } else { "integrity_check" }))
+    }
+
+    pub fn info(&self) -> Result<DbInfo, AppError> {
+        let conn = self.conn()?;
+        let count = |table: &str| -> Result<i64, AppError> {
+            Ok(conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))?)
+        };
+
+        let path = self.path();
+        let mut wal = path.clone().into_os_string();
+        wal.push("-wal");
+        let wal_size = std::fs::metadata(wal).map(|m| m.len()).unwrap_or(0);
+
+        Ok(DbInfo {
+            path: path.to_string_lossy().to_string(),
+            size_bytes: self.file_size()? + wal_size,
+            profile_count: count("profiles")?,
+            proxy_count: count("proxies")?,
+            workflow_count: count("workflows")?,
+            group_count: count("groups")?,
+            schema_version: conn.pragma_query_value(None, "user_version", |row| row.get(0))?,
+        })
+    }
+
+    /// Write groups, proxies, workflows and profiles to `dest` as one JSON document
+    pub fn export_all(&self, dest: &Path) -> Result<Backup, AppError> {
+        if dest.exists() && dest.canonicalize()? == self.path().canonicalize()? {
+            return Err(AppError::Validation("Refusing to overwrite the database file".to_string()));
+        }
+
+        let backup = Backup {
+            format: BACKUP_FORMAT.to_string(),
+            schema_version: SCHEMA_VERSION,
+            exported_at: now_iso(),
+            groups: self.get_groups()?,
+            proxies: self.get_proxies()?,
+            workflows: self.get_workflows()?,
+            profiles: self.get_profiles()?,
+        };
+        std::fs::write(dest, serde_json::to_string_pretty(&backup)?)?;
+
+        Ok(backup)
+    }
+
+    /// Restore a file written by `export_all` in one transaction. Groups and proxies go in
+    /// first, and profiles follow any ids `RegenerateId` gave them.
+    pub fn import_all(&self, src: &Path, strategy: ImportStrategy) -> Result<BackupImportResult, AppError> {
+        let backup: Backup = read_export(src, BACKUP_FORMAT, "Backup")?;
+
+        self.with_transaction(|tx| {
+            let (groups, group_ids) = import_rows(tx, &backup.groups, strategy)?;
+            let (proxies, proxy_ids) = import_rows(tx, &backup.proxies, strategy)?;
+            let (workflows, _) = import_rows(tx, &backup.workflows, strategy)?;
+
+            let profiles: Vec<DbProfile> = backup.profiles.iter()
+                .map(|profile| {
+                    let mut profile = profile.clone();
+                    if let Some(id) = proxy_ids.get(&profile.proxy_id) {
+                        profile.proxy_id = id.clone();
+                    }
+                    if let Some(id) = group_ids.get(&profile.group_id) {
+                        profile.group_id = id.clone();
+                    }
+                    profile
+                })
+                .collect();
+            let (profiles, _) = import_rows(tx, &profiles, strategy)?;
+
+            Ok(BackupImportResult { groups, proxies, workflows, profiles })
+        })
+    }
+
+    /// Write a group, its profiles and the proxies they use to `dest` as one JSON document,
+    /// for handing a ready-to-use group to someone else. References to proxies that no longer
+    /// exist are cleared in the bundle rather than shipped dangling.
+    pub fn export_group_bundle(&self, group_id: &str, dest: &Path) -> Result<GroupBundle, AppError> {
+        if dest.exists() && dest.canonicalize()? == self.path().canonicalize()? {
+            return Err(AppError::Validation("Refusing to overwrite the database file".to_string()));
+        }
+
+        let group = self.get_groups()?
+            .into_iter()
+            .find(|group| group.id == group_id)
+            .ok_or_else(|| AppError::NotFound(format!("Group not found: {}", group_id)))?;
+        let mut profiles = self.get_profiles_by_group(group_id)?;
+
+        let mut proxies: Vec<DbProxy> = Vec::new();
+        for profile in &mut profiles {
+            if profile.proxy_id.is_empty() || proxies.iter().any(|proxy| proxy.id == profile.proxy_id) {
+                continue;
+            }
+            match self.get_proxy(&profile.proxy_id)? {
+                Some(proxy) => proxies.push(proxy),
+                None => profile.proxy_id.clear(),
+            }
+        }
+
+        let bundle = GroupBundle {
+            format: GROUP_BUNDLE_FORMAT.to_string(),
+            schema_version: SCHEMA_VERSION,
+            exported_at: now_iso(),
+            group,
+            proxies,
+            profiles,
+        };
+        std::fs::write(dest, serde_json::to_string_pretty(&bundle)?)?;
+
+        Ok(bundle)
+    }
+
+    /// Recreate a group bundle in one transaction: proxies, then the group, then profiles.
+    /// Every profile lands in the bundle's group and keeps its proxy, following any ids
+    /// `strategy` changed; proxy references the bundle can't satisfy are cleared.
+    pub fn import_group_bundle(&self, src: &Path, strategy: ImportStrategy) -> Result<GroupBundleImportResult, AppError> {
+        let bundle: GroupBundle = read_export(src, GROUP_BUNDLE_FORMAT, "Group bundle")?;
+
+        self.with_transaction(|tx| {
+            let (proxies, proxy_ids) = import_rows(tx, &bundle.proxies, strategy)?;
+            let (group, group_ids) = import_rows(tx, std::slice::from_ref(&bundle.group), strategy)?;
+            let group_id = group_ids.get(&bundle.group.id).unwrap_or(&bundle.group.id);
+
+            let profiles: Vec<DbProfile> = bundle.profiles.iter()
+                .map(|profile| {
+                    let mut profile = profile.clone();
+                    profile.group_id = group_id.clone();
+                    if let Some(id) = proxy_ids.get(&profile.proxy_id) {
+                        profile.proxy_id = id.clone();
+                    } else if !bundle.proxies.iter().any(|proxy| proxy.id == profile.proxy_id) {
+                        profile.proxy_id.clear();
+                    }
+                    profile
+                })
+                .collect();
+            let (profiles, _) = import_rows(tx, &profiles, strategy)?;
+
+            Ok(GroupBundleImportResult { group_id: group_id.clone(), group, proxies, profiles })
+        })
+    }
+
+    /// Write every profile to `dest` as JSON Lines, one profile per line. Rows are streamed
+    /// from the query straight to the file, so memory use doesn't grow with the table.
+    /// Returns the number of profiles written.
+    pub fn export_profiles_jsonl(&self, dest: &Path) -> Result<usize, AppError> {
+        if dest.exists() && dest.canonicalize()? == self.path().canonicalize()? {
+            return Err(AppError::Validation("Refusing to overwrite the database file".to_string()));
+        }
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY created_at", PROFILE_COLUMNS))?;
+        let mut writer = BufWriter::new(File::create(dest)?);
+
+        let mut written = 0;
+        for profile in stmt.query_map([], row_to_profile)? {
+            serde_json::to_writer(&mut writer, &profile?)?;
+            writer.write_all(b"\n")?;
+            written += 1;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+
+    /// Write profiles to `dest` as CSV with a header row, one row per profile in creation order.
+    /// `columns` picks and orders the columns (names from `PROFILE_COLUMNS`); all of them when
+    /// not given. Values are written as stored, so JSON columns come out as JSON text. Streamed
+    /// like `export_profiles_jsonl`. Returns the number of profiles written.
+    pub fn export_profiles_csv(&self, dest: &Path, columns: Option<&[String]>) -> Result<usize, AppError> {
+        let known: Vec<&str> = PROFILE_COLUMNS.split(',').map(str::trim).collect();
+        let columns: Vec<&str> = match columns {
+            Some(columns) => columns.iter().map(|c| c.trim()).collect(),
+            None => known.clone(),
+        };
+        if columns.is_empty() {
+            return Err(AppError::Validation("Select at least one column to export".to_string()));
+        }
+        let unknown: Vec<&str> = columns.iter().copied().filter(|c| !known.contains(c)).collect();
+        if !unknown.is_empty() {
+            return Err(AppError::Validation(format!("Unknown profile column(s): {}", unknown.join(", "))));
+        }
+        if dest.exists() && dest.canonicalize()? == self.path().canonicalize()? {
+            return Err(AppError::Validation("Refusing to overwrite the database file".to_string()));
+        }
+
+        let conn = self.conn()?;
+        let mut stmt = conn.prepare(&format!("SELECT {} FROM profiles ORDER BY created_at", columns.join(", ")))?;
+        let mut writer = BufWriter::new(File::create(dest)?);
+        write_csv_record(&mut writer, columns.iter().map(|c| c.to_string()))?;
+
+        let mut written = 0;
+        let mut rows = stmt.query([])?;
+        while let Some(row) = rows.next()? {
+            let fields = (0..columns.len())
+                .map(|index| row.get_ref(index).map(value_text))
+                .collect::<SqlResult<Vec<_>>>()?;
+            write_csv_record(&mut writer, fields)?;
+            written += 1;
+        }
+        writer.flush()?;
+
+        Ok(written)
+    }
+
+    /// Import a file written by `export_profiles_jsonl`, reading it line by line and inserting
+    /// in batches of `JSONL_IMPORT_BATCH`. All or nothing, like `import_profiles`.
+    pub fn import_profiles_jsonl(&self, src: &Path, strategy: ImportStrategy) -> Result<ImportResult, AppError> {
+        let reader = BufReader::new(File::open(src)?);
+
+        self.with_transaction(|tx| {
+            let mut result = ImportResult::default();
+            let mut batch = Vec::with_capacity(JSONL_IMPORT_BATCH);
+
+            for (index, line) in reader.lines().enumerate() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let profile: DbProfile = serde_json::from_str(&line)
+                    .map_err(|e| AppError::Validation(format!("Line {}: invalid profile: {}", index + 1, e)))?;
+                batch.push(profile);
+
+                if batch.len() == JSONL_IMPORT_BATCH {
+                    result += import_rows(tx, &batch, strategy)?.0;
+                    batch.clear();
+                }
+            }
+            result += import_rows(tx, &batch, strategy)?.0;
+
+            Ok(result)
+        })
+    }
+
+    /// Move the database to `new_path`, which must not exist yet, and remember the location
+    /// for later launches. Running commands are waited for and new ones held off while the
+    /// WAL is checkpointed and the file copied next to its destination, so a full disk or
+    /// unwritable folder fails before anything changes. The copy is checked, renamed into
+    /// place and opened, and only then does the app switch to it and delete the original.
+    /// Any failure before the switch leaves the database where it was.
+    pub fn relocate(&self, new_path: &Path) -> Result<DbRelocation, AppError> {
+        self.relocate_with(new_path, save_database_location)
+    }
+
+    /// `relocate`, remembering locations through `remember` instead of the config file
+    fn relocate_with(&self, new_path: &Path, remember: impl Fn(&Path) -> Result<(), AppError>) -> Result<DbRelocation, AppError> {
+        if !new_path.is_absolute() {
+            return Err(AppError::Validation("New database path must be absolute".to_string()));
+        }
+        if new_path.exists() {
+            return Err(AppError::Validation(format!("{} already exists; refusing to overwrite it", new_path.display())));
+        }
+        let file_name = new_path.file_name()
+            .ok_or_else(|| AppError::Validation("New database path must name a file".to_string()))?;
+        if let Some(parent) = new_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut partial = file_name.to_os_string();
+        partial.push(".partial");
+        let partial = new_path.with_file_name(partial);
+
+        // Every connection to the old file is back in the pool and none is handed out until
+        // the switch is done, so nothing commits to the old file that the copy would miss
+        let mut file = self.lock_exclusive()?;
+        let old_path = file.path.clone();
+
+        let moved = (|| -> Result<Pool<SqliteConnectionManager>, AppError> {
+            let conn = file.pool.get()?;
+            let (busy, wal_frames, checkpointed): (i64, i64, i64) = conn
+                .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            if busy != 0 || checkpointed < wal_frames {
+                return Err(AppError::Db("Database is busy; try again when nothing is running".to_string()));
+            }
+
+            // Copying next to the destination is the writability and free space check
+            std::fs::copy(&old_path, &partial)?;
+            let report = Self::check_file_integrity(&partial, true)?;
+            if !report.ok {
+                return Err(AppError::Db(format!("Copied database failed its check: {}", report.issues.join("; "))));
+            }
+            std::fs::rename(&partial, new_path)?;
+            let pool = open_pool(new_path)?;
+            remember(new_path)?;
+            Ok(pool)
+        })();
+
+        let pool = match moved {
+            Ok(pool) => pool,
+            Err(e) => {
+                let _ = std::fs::remove_file(&partial);
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut path = new_path.as_os_str().to_os_string();
+                    path.push(suffix);
+                    let _ = std::fs::remove_file(path);
+                }
+                if let Err(e) = remember(&old_path) {
+                    log::warn!("Could not restore database location: {}", e);
+                }
+                return Err(e);
+            }
+        };
+
+        let size_bytes = std::fs::metadata(new_path)?.len();
+        *file = DbFile { pool, path: new_path.to_path_buf() };
+        drop(file);
+
+        // Every old connection was idle, so dropping the old pool closed them all
+        for suffix in ["", "-wal", "-shm"] {
+            let mut path = old_path.as_os_str().to_os_string();
+            path.push(suffix);
+            if let Err(e) = std::fs::remove_file(&path) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    log::warn!("Could not delete old database file {}: {}", Path::new(&path).display(), e);
+                }
+            }
+        }
+
+        Ok(DbRelocation {
+            path: new_path.to_string_lossy().to_string(),
+            previous_path: old_path.to_string_lossy().to_string(),
+            size_bytes,
+        })
+    }
+
+    /// Location of the database file on disk
+    pub fn path(&self) -> PathBuf {
+        self.file.read().unwrap_or_else(PoisonError::into_inner).path.clone()
+    }
+
+    fn file_size(&self) -> Result<u64, AppError> {
+        std::fs::metadata(self.path())
+            .map(|m| m.len())
+            .map_err(AppError::from)
+    }
+}
+
+// ============ Helpers ============
+
+/// Statuses a profile can be put in with `set_profile_status`
+pub const PROFILE_STATUSES: &[&str] = &["active", "idle", "banned", "archived"];
+
+/// Statuses a workflow can be put in with `set_workflow_status`
+pub const WORKFLOW_STATUSES: &[&str] = &["active", "inactive"];
+
+const SCHEDULED_RUN_COLUMNS: &str = "id, workflow_id, run_at, status, created_at, triggered_at";
+
+fn row_to_scheduled_run(row: &rusqlite::Row) -> SqlResult<DbScheduledRun> {
+    Ok(DbScheduledRun {
+        id: row.get(0)?,
+        workflow_id: row.get(1)?,
+        run_at: row.get(2)?,
+        status: row.get(3)?,
+        created_at: row.get(4)?,
+        triggered_at: row.get(5)?,
+    })
+}
+
+fn row_to_benchmark(row: &rusqlite::Row) -> SqlResult<DbBenchmarkResult> {
+    Ok(DbBenchmarkResult {
+        id: row.get(0)?,
+        kind: row.get(1)?,
+        engine: row.get(2)?,
+        created_at: row.get(3)?,
+        result_json: row.get(4)?,
+    })
+}
+
+/// The numbers in a benchmark result as `(dotted path, value)`: everything under `benchmarks`
+/// (full runs) or `tests` (quick runs). Array items are keyed by their `name`; unnamed arrays,
+/// such as raw per-iteration times, are skipped.
+pub fn benchmark_metrics(result: &Value) -> Vec<(String, f64)> {
+    fn collect(path: String, value: &Value, metrics: &mut Vec<(String, f64)>) {
+        let child = |key: &str| if path.is_empty() { key.to_string() } else { format!("{}.{}", path, key) };
+        match value {
+            Value::Number(number) => metrics.extend(number.as_f64().map(|n| (path, n))),
+            Value::Object(fields) => {
+                for (key, field) in fields {
+                    collect(child(key), field, metrics);
+                }
+            }
+            Value::Array(items) => {
+                for item in items {
+                    if let Some(name) = item.get("name").and_then(Value::as_str) {
+                        collect(child(name), item, metrics);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut metrics = Vec::new();
+    for section in ["benchmarks", "tests"] {
+        if let Some(value) = result.get(section) {
+            collect(String::new(), value, &mut metrics);
+        }
+    }
+    metrics
+}
+
+/// The profile columns that make up the fingerprint, in table order, with their SQL types
+/// and schema defaults. The profiles table is created from this list, so resetting to these
+/// values always matches a fresh row. `find_duplicate_profiles` compares these columns:
+/// everything a site can observe about the browser, and nothing that only organizes it.
+/// `fingerprint_hash` covers the same columns in this order.
+const FINGERPRINT_DEFAULTS: &[(&str, &str, &str)] = &[
+    ("browser_type", "TEXT", "'chrome'"),
+    ("browser_version", "TEXT", "'120'"),
+    ("user_agent", "TEXT", "''"),
+    ("os", "TEXT", "'windows'"),
+    ("platform", "TEXT", "'Win32'"),
+    ("viewport_width", "INTEGER", "1920"),
+    ("viewport_height", "INTEGER", "1080"),
+    ("screen_width", "INTEGER", "1920"),
+    ("screen_height", "INTEGER", "1080"),
+    ("color_depth", "INTEGER", "24"),
+    ("pixel_ratio", "REAL", "1.0"),
+    ("timezone_mode", "TEXT", "'auto'"),
+    ("timezone", "TEXT", "'America/New_York'"),
+    ("locale_mode", "TEXT", "'auto'"),
+    ("locale", "TEXT", "'en-US'"),
+    ("language", "TEXT", "'en-US,en'"),
+    ("country", "TEXT", "'US'"),
+    ("cpu_cores", "INTEGER", "8"),
+    ("device_memory", "INTEGER", "8"),
+    ("max_touch_points", "INTEGER", "0"),
+    ("webgl_image_mode", "TEXT", "'noise'"),
+    ("webgl_metadata_mode", "TEXT", "'custom'"),
+    ("webgl_vendor", "TEXT", "''"),
+    ("webgl_renderer", "TEXT", "''"),
+    ("canvas_noise", "REAL", "0.02"),
+    ("audio_noise", "REAL", "0.0001"),
+    ("client_rects_noise", "REAL", "0.1"),
+    ("webrtc_mode", "TEXT", "'replace'"),
+    ("webrtc_public_ip", "TEXT", "''"),
+    ("geo_mode", "TEXT", "'query'"),
+    ("geo_latitude", "REAL", "0"),
+    ("geo_longitude", "REAL", "0"),
+    ("geo_accuracy", "REAL", "100"),
+    ("media_devices_mode", "TEXT", "'real'"),
+    ("fake_cameras", "INTEGER", "1"),
+    ("fake_microphones", "INTEGER", "1"),
+    ("fake_speakers", "INTEGER", "1"),
+    ("do_not_track", "INTEGER", "0"),
+    ("block_webrtc", "INTEGER", "0"),
+    ("block_canvas", "INTEGER", "0"),
+    ("block_audio_context", "INTEGER", "0"),
+    ("block_images", "INTEGER", "0"),
+    ("block_media", "INTEGER", "0"),
+    ("fonts", "TEXT", "'[]'"),
+    ("plugins", "TEXT", "'[]'"),
+    ("speech_voices", "TEXT", "'[]'"),
+];
+
+/// Loose profile relations as (serialized field, profile column, referenced table)
+const PROFILE_RELATIONS: &[(&str, &str, &str)] = &[
+    ("proxyId", "proxy_id", "proxies"),
+    ("groupId", "group_id", "groups"),
+];
+
+/// Statuses `purge_profiles_by_status` may delete; profiles still in use can't be purged
+pub const PURGEABLE_STATUSES: &[&str] = &["banned", "archived"];
+
+/// Connections kept open; only one of them can write at a time
+const POOL_SIZE: u32 = 8;
+
+/// How long vacuum and relocate wait for running commands to return their connections
+const EXCLUSIVE_WAIT: Duration = Duration::from_secs(10);
+
+/// Database file name in the OS data directory, unless `relocate` moved it
+const DEFAULT_DATABASE_FILE: &str = "mmo-express.db";
+
+/// Config file in the OS data directory holding a relocated database's path
+const DATABASE_LOCATION_FILE: &str = "database-location.json";
+
+/// Latency samples kept per proxy; older ones are dropped as new tests are recorded
+const PROXY_LATENCY_SAMPLES: i64 = 100;
+
+/// Stored in `PRAGMA user_version`; bump when the schema changes
+const SCHEMA_VERSION: i64 = 3;
+
+/// Profiles parsed before each insert in `import_profiles_jsonl`, bounding its memory use
+const JSONL_IMPORT_BATCH: usize = 1000;
+
+/// `format` field identifying files written by `export_all`
+const BACKUP_FORMAT: &str = "mmo-express-backup";
+
+/// `format` field identifying files written by `export_group_bundle`
+const GROUP_BUNDLE_FORMAT: &str = "mmo-express-group-bundle";
+
+/// Keys that have a default, in the order `get_all_settings` lists them
+const DEFAULT_SETTINGS: &[&str] = &["defaultHeadless", "defaultTimeout", "benchmarkEngine", "screenshotMaxBytes", "prewarmOnStartup", "sidecarRateLimits"];
+
+fn default_setting(key: &str) -> Option<Value> {
+    match key {
+        "defaultHeadless" => Some(json!(false)),
+        "defaultTimeout" => Some(json!(30000)),
+        "benchmarkEngine" => Some(json!("chromium")),
+        // Base64 screenshots travel over IPC in a single message
+        "screenshotMaxBytes" => Some(json!(8 * 1024 * 1024)),
+        // Start the shared browser in the background at launch
+        "prewarmOnStartup" => Some(json!(false)),
+        // Per-command overrides of the sidecar rate limit, `{ command: perSecond }`
+        "sidecarRateLimits" => Some(json!({})),
+        _ => None,
+    }
+}
+
+/// Values written outside the app may not be JSON; keep them as plain strings
+fn parse_setting(raw: String) -> Value {
+    serde_json::from_str(&raw).unwrap_or(Value::String(raw))
+}
+
+/// Connection pool for the database file at `path`, creating the file if needed
+fn open_pool(path: &Path) -> Result<Pool<SqliteConnectionManager>, AppError> {
+    // WAL lets readers run alongside the single writer; busy_timeout makes
+    // a second writer wait for the lock instead of failing immediately
+    let manager = SqliteConnectionManager::file(path).with_init(|conn| {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA synchronous = NORMAL;
+             PRAGMA busy_timeout = 5000;",
+        )
+    });
+    Ok(Pool::builder().max_size(POOL_SIZE).build(manager)?)
+}
+
+fn data_dir() -> Result<PathBuf, AppError> {
     let project_dirs = directories::ProjectDirs::from("com", "mmo", "express")
-        .ok_or("Could not determine data directory")?;
+        .ok_or_else(|| AppError::Io("Could not determine data directory".to_string()))?;
+    Ok(project_dirs.data_dir().to_path_buf())
+}
+
+/// Where the database was moved to by `relocate`, kept in the data directory
+#[derive(Debug, Serialize, Deserialize)]
+struct DatabaseLocation {
+    path: PathBuf,
+}
+
+/// The location saved by `relocate`, or `mmo-express.db` in the OS data directory. A saved
+/// location whose file is gone is an error rather than a silently created empty database.
+fn get_database_path() -> Result<PathBuf, AppError> {
+    let data_dir = data_dir()?;
+    let config = data_dir.join(DATABASE_LOCATION_FILE);
+
+    let contents = match std::fs::read_to_string(&config) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(data_dir.join(DEFAULT_DATABASE_FILE)),
+        Err(e) => return Err(e.into()),
+    };
+    let location: DatabaseLocation = serde_json::from_str(&contents)
+        .map_err(|e| AppError::Validation(format!("Invalid database location in {}: {}", config.display(), e)))?;
+    if !location.path.exists() {
+        return Err(AppError::NotFound(format!(
+            "Database {} (set in {}) is missing. Reconnect its drive, or delete {} to start over in the default location.",
+            location.path.display(),
+            config.display(),
+            config.display()
+        )));
+    }
+    Ok(location.path)
+}
+
+/// Persist `path` for `get_database_path`; the default location needs no config file
+fn save_database_location(path: &Path) -> Result<(), AppError> {
+    let data_dir = data_dir()?;
+    let config = data_dir.join(DATABASE_LOCATION_FILE);
+    if path == data_dir.join(DEFAULT_DATABASE_FILE) {
+        return match std::fs::remove_file(&config) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+
+    std::fs::create_dir_all(&data_dir)?;
+    let partial = config.with_extension("json.partial");
+    std::fs::write(&partial, serde_json::to_string_pretty(&DatabaseLocation { path: path.to_path_buf() })?)?;
+    std::fs::rename(&partial, &config)?;
+    Ok(())
+}
+
+/// Current UTC time in the same ISO-8601 format the frontend uses (`Date.toISOString()`)
+pub(crate) fn now_iso() -> String {
+    chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+/// Run `PRAGMA {check}` (`integrity_check` or `quick_check`) and `PRAGMA foreign_key_check`.
+/// Corruption bad enough that a check can't run at all is reported as an issue, not an error.
+fn integrity_report(conn: &Connection, path: &Path, check: &str) -> IntegrityReport {
+    let mut issues = Vec::new();
+
+    let messages = conn.prepare(&format!("PRAGMA {}", check)).and_then(|mut stmt| {
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<SqlResult<Vec<_>>>()
+    });
+    match messages {
+        Ok(messages) => issues.extend(messages.into_iter().filter(|message| message != "ok")),
+        Err(e) => issues.push(format!("Integrity check failed: {}", e)),
+    }
+
+    let foreign_keys = conn.prepare("PRAGMA foreign_key_check").and_then(|mut stmt| {
+        let rows = stmt.query_map([], |row| {
+            Ok(format!(
+                "{} row {} references a missing {} row",
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<i64>>(1)?.map_or("?".to_string(), |rowid| rowid.to_string()),
+                row.get::<_, String>(2)?
+            ))
+        })?;
+        rows.collect::<SqlResult<Vec<_>>>()
+    });
+    match foreign_keys {
+        Ok(rows) => issues.extend(rows),
+        Err(e) => issues.push(format!("Foreign key check failed: {}", e)),
+    }
+
+    let ok = issues.is_empty();
+    IntegrityReport {
+        ok,
+        issues,
+        guidance: (!ok).then(|| format!(
+            "The database at {} is damaged. Export what is still readable, then restore from a \
+             backup, or close the app and move the file aside so a fresh one is created.",
+            path.display()
+        )),
+    }
+}
+
+/// Read a JSON export written with `format`, checking its format and schema version first.
+/// `label` names the kind of file in errors, e.g. "Backup".
+fn read_export<T: serde::de::DeserializeOwned>(src: &Path, format: &str, label: &str) -> Result<T, AppError> {
+    let contents = std::fs::read_to_string(src)?;
+    let header: Value = serde_json::from_str(&contents)
+        .map_err(|e| AppError::Validation(format!("{} is not valid JSON: {}", label, e)))?;
+    if header.get("format").and_then(Value::as_str) != Some(format) {
+        return Err(AppError::Validation(format!("Not an MMO Express {} file", label.to_lowercase())));
+    }
+    // Older exports lack only columns that have defaults, so they still import
+    match header.get("schemaVersion").and_then(Value::as_i64) {
+        Some(version) if (1..=SCHEMA_VERSION).contains(&version) => {}
+        version => {
+            return Err(AppError::Validation(format!(
+                "{} schema version {} is not supported (expected {} or older)",
+                label,
+                version.map_or("missing".to_string(), |v| v.to_string()),
+                SCHEMA_VERSION
+            )));
+        }
+    }
+    serde_json::from_value(header)
+        .map_err(|e| AppError::Validation(format!("Malformed {}: {}", label.to_lowercase(), e)))
+}
+
+/// Log `lint::check_screen_geometry` findings without blocking the save: an odd size is a
+/// fingerprint risk rather than bad data, and `lint_profile` reports it to the UI.
+fn warn_screen_geometry(profile: &DbProfile) {
+    for finding in lint::check_screen_geometry(profile) {
+        log::warn!("Profile '{}' ({}): {}", profile.name, profile.id, finding.message);
+    }
+}
+
+/// Fill in what callers may leave empty on create: a UUIDv4 id and now for both
+/// timestamps. Values the caller provided are kept as they are.
+fn fill_identity(id: &mut String, created_at: &mut String, updated_at: &mut String) {
+    if id.is_empty() {
+        *id = uuid::Uuid::new_v4().to_string();
+    }
+    let now = now_iso();
+    if created_at.is_empty() {
+        *created_at = now.clone();
+    }
+    if updated_at.is_empty() {
+        *updated_at = now;
+    }
+}
+
+/// Ids of profiles sharing a fingerprint, one set per signature with two or more members.
+/// Members of each set are ordered by `order_by`.
+fn duplicate_profile_sets(conn: &Connection, order_by: &str) -> Result<Vec<Vec<String>>, AppError> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT id, signature FROM (
+            SELECT id, created_at, last_used_at,
+                DENSE_RANK() OVER (ORDER BY fingerprint_hash) AS signature,
+                COUNT(*) OVER (PARTITION BY fingerprint_hash) AS copies
+            FROM profiles
+        )
+        WHERE copies > 1
+        ORDER BY signature, {order}",
+        order = order_by
+    ))?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?;
+
+    let mut sets: Vec<(i64, Vec<String>)> = Vec::new();
+    for row in rows {
+        let (id, signature) = row?;
+        match sets.last_mut() {
+            Some((current, ids)) if *current == signature => ids.push(id),
+            _ => sets.push((signature, vec![id])),
+        }
+    }
+    Ok(sets.into_iter().map(|(_, ids)| ids).collect())
+}
+
+/// `validate_workflow_json` as an `AppError` listing every problem
+fn check_workflow(workflow: &DbWorkflow) -> Result<(), AppError> {
+    workflow::validate_workflow_json(workflow).map_err(|errors| AppError::Validation(errors.join("; ")))
+}
+
+/// Checks on a proxy's connection fields before it is saved. Collects every problem rather
+/// than stopping at the first so the editor can show them all at once.
+pub fn validate_proxy_config(proxy: &DbProxy) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    if proxy.host.trim().is_empty() {
+        errors.push("host is required".to_string());
+    }
+    if !(1..=65535).contains(&proxy.port) {
+        errors.push(format!("port must be between 1 and 65535 (got {})", proxy.port));
+    }
+    if let Err(e) = crate::ProxyType::try_from(proxy.proxy_type.as_str()) {
+        errors.push(e.message().to_string());
+    }
+    if proxy.username.is_empty() != proxy.password.is_empty() {
+        errors.push("username and password must be given together".to_string());
+    }
+
+    if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+/// `validate_proxy_config` as an `AppError` listing every problem
+fn check_proxy(proxy: &DbProxy) -> Result<(), AppError> {
+    validate_proxy_config(proxy).map_err(|errors| AppError::Validation(errors.join("; ")))
+}
+
+fn insert_group(conn: &Connection, group: &DbGroup, on_conflict: &str) -> Result<(), AppError> {
+    conn.execute(
+        &format!(
+            "INSERT INTO groups (id, name, color, description, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6) {}",
+            on_conflict
+        ),
+        params![
+            group.id, group.name, group.color, group.description,
+            group.created_at, group.updated_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_proxy(conn: &Connection, proxy: &DbProxy, on_conflict: &str) -> Result<(), AppError> {
+    conn.execute(
+        &format!(
+            "INSERT INTO proxies ({}) VALUES ({}) {}",
+            PROXY_COLUMNS,
+            placeholders(1, PROXY_COLUMNS.split(',').count()),
+            on_conflict
+        ),
+        params![
+            proxy.id, proxy.name, proxy.proxy_type, proxy.host, proxy.port,
+            proxy.username, proxy.password, proxy.country, proxy.city,
+            proxy.status, proxy.last_tested_at, proxy.last_ip,
+            proxy.created_at, proxy.updated_at
+        ],
+    )?;
+    Ok(())
+}
+
+fn insert_workflow(conn: &Connection, workflow: &DbWorkflow, on_conflict: &str) -> Result<(), AppError> {
+    conn.execute(
+        &format!(
+            "INSERT INTO workflows ({}) VALUES ({}) {}",
+            WORKFLOW_COLUMNS,
+            placeholders(1, WORKFLOW_COLUMNS.split(',').count()),
+            on_conflict
+        ),
+        params![
+            workflow.id, workflow.name, workflow.description,
+            workflow.blocks, workflow.variables, workflow.settings,
+            workflow.status, workflow.last_run_at, workflow.run_count,
+            workflow.created_at, workflow.updated_at
+        ],
+    )?;
+    Ok(())
+}
+
+/// A row type `import_rows` can insert
+trait ImportRow: Clone {
+    const TABLE: &'static str;
+    /// Column list in the order `insert` binds them
+    const COLUMNS: &'static str;
+    /// Entity type in the audit log, for tables whose changes are audited
+    const AUDIT_ENTITY: Option<&'static str>;
+
+    fn id(&self) -> &str;
+    fn set_id(&mut self, id: String);
+    fn name(&self) -> &str;
+    fn insert(&self, conn: &Connection, on_conflict: &str) -> Result<(), AppError>;
+}
+
+impl ImportRow for DbProfile {
+    const TABLE: &'static str = "profiles";
+    const COLUMNS: &'static str = PROFILE_COLUMNS;
+    const AUDIT_ENTITY: Option<&'static str> = Some("profile");
+
+    fn id(&self) -> &str { &self.id }
+    fn set_id(&mut self, id: String) { self.id = id; }
+    fn name(&self) -> &str { &self.name }
+    fn insert(&self, conn: &Connection, on_conflict: &str) -> Result<(), AppError> {
+        insert_profile(conn, self, on_conflict).map(|_| ())
+    }
+}
+
+impl ImportRow for DbProxy {
+    const TABLE: &'static str = "proxies";
+    const COLUMNS: &'static str = PROXY_COLUMNS;
+    const AUDIT_ENTITY: Option<&'static str> = Some("proxy");
+
+    fn id(&self) -> &str { &self.id }
+    fn set_id(&mut self, id: String) { self.id = id; }
+    fn name(&self) -> &str { &self.name }
+    fn insert(&self, conn: &Connection, on_conflict: &str) -> Result<(), AppError> {
+        insert_proxy(conn, self, on_conflict)
+    }
+}
+
+impl ImportRow for DbGroup {
+    const TABLE: &'static str = "groups";
+    const COLUMNS: &'static str = GROUP_COLUMNS;
+    const AUDIT_ENTITY: Option<&'static str> = None;
+
+    fn id(&self) -> &str { &self.id }
+    fn set_id(&mut self, id: String) { self.id = id; }
+    fn name(&self) -> &str { &self.name }
+    fn insert(&self, conn: &Connection, on_conflict: &str) -> Result<(), AppError> {
+        insert_group(conn, self, on_conflict)
+    }
+}
+
+impl ImportRow for DbWorkflow {
+    const TABLE: &'static str = "workflows";
+    const COLUMNS: &'static str = WORKFLOW_COLUMNS;
+    const AUDIT_ENTITY: Option<&'static str> = None;
+
+    fn id(&self) -> &str { &self.id }
+    fn set_id(&mut self, id: String) { self.id = id; }
+    fn name(&self) -> &str { &self.name }
+    fn insert(&self, conn: &Connection, on_conflict: &str) -> Result<(), AppError> {
+        insert_workflow(conn, self, on_conflict)
+    }
+}
+
+/// Insert `rows` resolving id collisions (with existing rows or earlier rows in the batch)
+/// according to `strategy`. Also returns the ids `RegenerateId` replaced, old -> new.
+fn import_rows<T: ImportRow>(
+    conn: &Connection,
+    rows: &[T],
+    strategy: ImportStrategy,
+) -> Result<(ImportResult, HashMap<String, String>), AppError> {
+    let mut result = ImportResult::default();
+    let mut regenerated = HashMap::new();
+
+    // Every column but the id takes the imported value
+    let overwrite = format!(
+        "ON CONFLICT(id) DO UPDATE SET {}",
+        T::COLUMNS.split(',')
+            .map(str::trim)
+            .filter(|column| *column != "id")
+            .map(|column| format!("{0} = excluded.{0}", column))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let audit = |row: &T, action: &str, details: Value| match T::AUDIT_ENTITY {
+        Some(entity) => append_audit(conn, entity, row.id(), action, details),
+        None => Ok(()),
+    };
+
+    for row in rows {
+        let exists = conn.query_row(&format!("SELECT 1 FROM {} WHERE id = ?1", T::TABLE), params![row.id()], |_| Ok(()))
+            .optional()?
+            .is_some();
+
+        match strategy {
+            ImportStrategy::Skip if exists => {
+                result.skipped += 1;
+            }
+            ImportStrategy::Overwrite if exists => {
+                row.insert(conn, &overwrite)?;
+                audit(row, "update", json!({ "name": row.name(), "import": "overwrite" }))?;
+                result.overwritten += 1;
+            }
+            ImportStrategy::RegenerateId if exists || row.id().is_empty() => {
+                let mut row = row.clone();
+                let id = uuid::Uuid::new_v4().to_string();
+                regenerated.insert(row.id().to_string(), id.clone());
+                row.set_id(id);
+                row.insert(conn, "")?;
+                audit(&row, "create", json!({ "name": row.name() }))?;
+                result.inserted += 1;
+            }
+            _ => {
+                row.insert(conn, "")?;
+                audit(row, "create", json!({ "name": row.name() }))?;
+                result.inserted += 1;
+            }
+        }
+    }
+
+    Ok((result, regenerated))
+}
+
+/// Set `group_id` on the given profiles, returning how many rows matched
+fn move_profiles(conn: &Connection, group_id: &str, profile_ids: &[String]) -> Result<usize, AppError> {
+    let now = now_iso();
+    let sql = format!(
+        "UPDATE profiles SET group_id = ?1, updated_at = ?2 WHERE id IN ({})",
+        placeholders(3, profile_ids.len())
+    );
+    let mut values: Vec<&dyn ToSql> = vec![&group_id, &now];
+    values.extend(profile_ids.iter().map(|id| id as &dyn ToSql));
+
+    audited_profile_update(conn, profile_ids, || Ok(conn.execute(&sql, values.as_slice())?))
+}
+
+/// Insert every profile column; `on_conflict` is an optional upsert clause
+/// Returns the stored fingerprint hash
+fn insert_profile(conn: &Connection, profile: &DbProfile, on_conflict: &str) -> Result<String, AppError> {
+    let column_count = PROFILE_COLUMNS.split(',').count();
+    conn.execute(
+        &format!(
+            "INSERT INTO profiles ({}) VALUES ({}) {}",
+            PROFILE_COLUMNS,
+            placeholders(1, column_count),
+            on_conflict
+        ),
+        params![
+            profile.id, profile.name, profile.browser_type, profile.browser_version, profile.user_agent,
+            profile.os, profile.platform,
+            profile.viewport_width, profile.viewport_height, profile.screen_width, profile.screen_height,
+            profile.color_depth, profile.pixel_ratio,
+            profile.timezone_mode, profile.timezone, profile.locale_mode, profile.locale, profile.language, profile.country,
+            profile.cpu_cores, profile.device_memory, profile.max_touch_points,
+            profile.webgl_image_mode, profile.webgl_metadata_mode, profile.webgl_vendor, profile.webgl_renderer,
+            profile.canvas_noise, profile.audio_noise, profile.client_rects_noise,
+            profile.webrtc_mode, profile.webrtc_public_ip,
+            profile.geo_mode, profile.geo_latitude, profile.geo_longitude, profile.geo_accuracy,
+            profile.media_devices_mode, profile.fake_cameras, profile.fake_microphones, profile.fake_speakers,
+            profile.do_not_track, profile.block_webrtc, profile.block_canvas, profile.block_audio_context,
+            profile.block_images, profile.block_media,
+            profile.fonts, profile.plugins, profile.speech_voices,
+            profile.proxy_id, profile.group_id, profile.platform_tags,
+            profile.notes, profile.bookmarks, profile.status, profile.last_used_at, profile.last_ip,
+            profile.fingerprint_seed, profile.fingerprint_hash, profile.created_at, profile.updated_at
+        ],
+    )?;
+    rehash_profile(conn, &profile.id)
+}
+
+/// Hex SHA-256 identifying a fingerprint. The input is one `column=value\n` line per
+/// `FINGERPRINT_DEFAULTS` column, in that order, with values as SQLite stores them:
+/// integers (booleans as 0/1) in decimal, reals in Rust's shortest round-trip form
+/// (`1`, `0.02`), text as is. So `sha256("browser_type=chrome\nbrowser_version=120\n...")`.
+fn fingerprint_hash(row: &rusqlite::Row) -> SqlResult<String> {
+    let mut hasher = Sha256::new();
+    for (index, (column, _, _)) in FINGERPRINT_DEFAULTS.iter().enumerate() {
+        hasher.update(format!("{}={}\n", column, value_text(row.get_ref(index)?)));
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// A column value as text: integers in decimal, reals in Rust's shortest round-trip form, text as is, NULL empty
+fn value_text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(n) => n.to_string(),
+        ValueRef::Real(n) => n.to_string(),
+        ValueRef::Text(text) | ValueRef::Blob(text) => String::from_utf8_lossy(text).into_owned(),
+    }
+}
+
+/// One CSV record per RFC 4180: fields joined by commas and ended by CRLF. A field containing
+/// a comma, quote or line break is wrapped in quotes, with its quotes doubled.
+fn write_csv_record(writer: &mut impl Write, fields: impl IntoIterator<Item = String>) -> std::io::Result<()> {
+    for (index, field) in fields.into_iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(b"\r\n")
+}
+
+/// Recompute and store `fingerprint_hash` for profile `id` from its saved columns, so it can't
+/// drift from what was written. Every write to a fingerprint column goes through this.
+/// Returns the new hash.
+fn rehash_profile(conn: &Connection, id: &str) -> Result<String, AppError> {
+    let columns = FINGERPRINT_DEFAULTS.iter().map(|(column, _, _)| *column).collect::<Vec<_>>().join(", ");
+    let hash = conn.query_row(&format!("SELECT {} FROM profiles WHERE id = ?1", columns), params![id], fingerprint_hash)?;
+    conn.execute("UPDATE profiles SET fingerprint_hash = ?2 WHERE id = ?1", params![id, hash])?;
+    Ok(hash)
+}
+
+/// Numbered placeholders for an `IN (...)` clause, e.g. `?3, ?4, ?5`
+fn placeholders(start: usize, count: usize) -> String {
+    (start..start + count)
+        .map(|i| format!("?{}", i))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Append an audit entry; callers pass their open transaction so the entry
+/// commits or rolls back together with the mutation it describes.
+fn append_audit(conn: &Connection, entity_type: &str, entity_id: &str, action: &str, details: Value) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT INTO audit_log (entity_type, entity_id, action, timestamp, details_json)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![entity_type, entity_id, action, now_iso(), details.to_string()],
+    )?;
+    Ok(())
+}
+
+/// Run `update` and append a profile `update` audit row for each of `ids` whose fields it
+/// changed, so bulk writes leave the same trail as single edits
+fn audited_profile_update<T>(conn: &Connection, ids: &[String], update: impl FnOnce() -> Result<T, AppError>) -> Result<T, AppError> {
+    let before = profile_snapshots(conn, ids)?;
+    let result = update()?;
+    let mut after = profile_snapshots(conn, ids)?;
+
+    let mut seen = HashSet::new();
+    for id in ids.iter().filter(|id| seen.insert(id.as_str())) {
+        if let (Some(before), Some(after)) = (before.get(id), after.remove(id)) {
+            let changes = diff_fields(before, &after);
+            if changes.as_object().is_some_and(|c| !c.is_empty()) {
+                append_audit(conn, "profile", id, "update", json!({ "changes": changes }))?;
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The profiles among `ids` that exist, serialized for `diff_fields`
+fn profile_snapshots(conn: &Connection, ids: &[String]) -> Result<HashMap<String, Value>, AppError> {
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let sql = format!("SELECT {} FROM profiles WHERE id IN ({})", PROFILE_COLUMNS, placeholders(1, ids.len()));
+    let mut stmt = conn.prepare(&sql)?;
+    let rows = stmt.query_map(params_from_iter(ids), row_to_profile)?;
+    Ok(rows.map(|profile| profile.map(|p| (p.id.clone(), json!(p)))).collect::<SqlResult<_>>()?)
+}
+
+/// Fields that differ between two serialized records, as `{ field: { from, to } }`.
+/// Secrets are masked; `updatedAt` and `fingerprintHash` are skipped since they follow
+/// from the other changes.
+fn diff_fields(before: &Value, after: &Value) -> Value {
+    let (Some(before), Some(after)) = (before.as_object(), after.as_object()) else {
+        return json!({});
+    };
+
+    let changes: serde_json::Map<String, Value> = after
+        .iter()
+        .filter(|(key, _)| !matches!(key.as_str(), "updatedAt" | "fingerprintHash"))
+        .filter(|(key, value)| before.get(key.as_str()) != Some(value))
+        .map(|(key, value)| {
+            let change = if key == "password" {
+                json!({ "from": "***", "to": "***" })
+            } else {
+                json!({ "from": before.get(key.as_str()), "to": value })
+            };
+            (key.clone(), change)
+        })
+        .collect();
+
+    Value::Object(changes)
+}
+
+/// Column list matching the field order read by `row_to_profile`
+const PROFILE_COLUMNS: &str = "
+    id, name, browser_type, browser_version, user_agent, os, platform,
+    viewport_width, viewport_height, screen_width, screen_height, color_depth, pixel_ratio,
+    timezone_mode, timezone, locale_mode, locale, language, country,
+    cpu_cores, device_memory, max_touch_points,
+    webgl_image_mode, webgl_metadata_mode, webgl_vendor, webgl_renderer,
+    canvas_noise, audio_noise, client_rects_noise,
+    webrtc_mode, webrtc_public_ip,
+    geo_mode, geo_latitude, geo_longitude, geo_accuracy,
+    media_devices_mode, fake_cameras, fake_microphones, fake_speakers,
+    do_not_track, block_webrtc, block_canvas, block_audio_context, block_images, block_media,
+    fonts, plugins, speech_voices,
+    proxy_id, group_id, platform_tags,
+    notes, bookmarks, status, last_used_at, last_ip, fingerprint_seed, fingerprint_hash,
+    created_at, updated_at";
+
+fn row_to_profile(row: &rusqlite::Row) -> SqlResult<DbProfile> {
+    Ok(DbProfile {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        browser_type: row.get(2)?,
+        browser_version: row.get(3)?,
+        user_agent: row.get(4)?,
+        os: row.get(5)?,
+        platform: row.get(6)?,
+        viewport_width: row.get(7)?,
+        viewport_height: row.get(8)?,
+        screen_width: row.get(9)?,
+        screen_height: row.get(10)?,
+        color_depth: row.get(11)?,
+        pixel_ratio: row.get(12)?,
+        timezone_mode: row.get(13)?,
+        timezone: row.get(14)?,
+        locale_mode: row.get(15)?,
+        locale: row.get(16)?,
+        language: row.get(17)?,
+        country: row.get(18)?,
+        cpu_cores: row.get(19)?,
+        device_memory: row.get(20)?,
+        max_touch_points: row.get(21)?,
+        webgl_image_mode: row.get(22)?,
+        webgl_metadata_mode: row.get(23)?,
+        webgl_vendor: row.get(24)?,
+        webgl_renderer: row.get(25)?,
+        canvas_noise: row.get(26)?,
+        audio_noise: row.get(27)?,
+        client_rects_noise: row.get(28)?,
+        webrtc_mode: row.get(29)?,
+        webrtc_public_ip: row.get(30)?,
+        geo_mode: row.get(31)?,
+        geo_latitude: row.get(32)?,
+        geo_longitude: row.get(33)?,
+        geo_accuracy: row.get(34)?,
+        media_devices_mode: row.get(35)?,
+        fake_cameras: row.get(36)?,
+        fake_microphones: row.get(37)?,
+        fake_speakers: row.get(38)?,
+        do_not_track: row.get(39)?,
+        block_webrtc: row.get(40)?,
+        block_canvas: row.get(41)?,
+        block_audio_context: row.get(42)?,
+        block_images: row.get(43)?,
+        block_media: row.get(44)?,
+        fonts: row.get(45)?,
+        plugins: row.get(46)?,
+        speech_voices: row.get(47)?,
+        proxy_id: row.get(48)?,
+        group_id: row.get(49)?,
+        platform_tags: row.get(50)?,
+        notes: row.get(51)?,
+        bookmarks: row.get(52)?,
+        status: row.get(53)?,
+        last_used_at: row.get(54)?,
+        last_ip: row.get(55)?,
+        fingerprint_seed: row.get(56)?,
+        fingerprint_hash: row.get(57)?,
+        created_at: row.get(58)?,
+        updated_at: row.get(59)?,
+    })
+}
+
+/// Column list matching the field order read by `row_to_workflow`
+const WORKFLOW_COLUMNS: &str = "id, name, description, blocks, variables, settings,
+    status, last_run_at, run_count, created_at, updated_at";
+
+fn row_to_workflow(row: &rusqlite::Row) -> SqlResult<DbWorkflow> {
+    Ok(DbWorkflow {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        description: row.get(2)?,
+        blocks: row.get(3)?,
+        variables: row.get(4)?,
+        settings: row.get(5)?,
+        status: row.get(6)?,
+        last_run_at: row.get(7)?,
+        run_count: row.get(8)?,
+        created_at: row.get(9)?,
+        updated_at: row.get(10)?,
+    })
+}
+
+/// Column list matching the field order read by `row_to_proxy`
+const PROXY_COLUMNS: &str = "id, name, proxy_type, host, port, username, password,
+    country, city, status, last_tested_at, last_ip, created_at, updated_at";
+
+fn row_to_proxy(row: &rusqlite::Row) -> SqlResult<DbProxy> {
+    row_to_proxy_at(row, 0)
+}
+
+/// `row_to_proxy` for a row where the proxy columns start at `start`, e.g. after a join
+fn row_to_proxy_at(row: &rusqlite::Row, start: usize) -> SqlResult<DbProxy> {
+    Ok(DbProxy {
+        id: row.get(start)?,
+        name: row.get(start + 1)?,
+        proxy_type: row.get(start + 2)?,
+        host: row.get(start + 3)?,
+        port: row.get(start + 4)?,
+        username: row.get(start + 5)?,
+        password: row.get(start + 6)?,
+        country: row.get(start + 7)?,
+        city: row.get(start + 8)?,
+        status: row.get(start + 9)?,
+        last_tested_at: row.get(start + 10)?,
+        last_ip: row.get(start + 11)?,
+        created_at: row.get(start + 12)?,
+        updated_at: row.get(start + 13)?,
+    })
+}
+
+/// Column list matching the field order read by `row_to_group_at`
+const GROUP_COLUMNS: &str = "id, name, color, description, created_at, updated_at";
+
+fn row_to_group_at(row: &rusqlite::Row, start: usize) -> SqlResult<DbGroup> {
+    Ok(DbGroup {
+        id: row.get(start)?,
+        name: row.get(start + 1)?,
+        color: row.get(start + 2)?,
+        description: row.get(start + 3)?,
+        created_at: row.get(start + 4)?,
+        updated_at: row.get(start + 5)?,
+    })
+}
+
+/// Prefix every column in a column list with a table alias, for joins
+fn qualified(alias: &str, columns: &str) -> String {
+    columns.split(',')
+        .map(|column| format!("{}.{}", alias, column.trim()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::ops::Deref;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    /// A database in its own temporary directory, deleted on drop
+    pub(crate) struct TestDb {
+        db: Database,
+        pub(crate) dir: PathBuf,
+    }
+
+    impl Deref for TestDb {
+        type Target = Database;
+
+        fn deref(&self) -> &Database {
+            &self.db
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn temp_dir() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "mmo-test-{}-{}", std::process::id(), NEXT.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    pub(crate) fn test_db() -> TestDb {
+        let dir = temp_dir();
+        TestDb { db: Database::open(dir.join("test.db")).unwrap(), dir }
+    }
+
+    /// A valid profile named after its id, for tests across the crate
+    pub(crate) fn profile(id: &str) -> DbProfile {
+        let mut profile: DbProfile = serde_json::from_str(r#"{
+            "id": "", "name": "", "browserType": "chrome", "browserVersion": "120",
+            "userAgent": "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "os": "windows", "platform": "Win32", "viewportWidth": 1920, "viewportHeight": 1080,
+            "screenWidth": 1920, "screenHeight": 1080, "colorDepth": 24, "pixelRatio": 1.0,
+            "timezoneMode": "auto", "timezone": "America/New_York", "localeMode": "auto", "locale": "en-US",
+            "language": "en-US,en", "country": "US", "cpuCores": 8, "deviceMemory": 8, "maxTouchPoints": 0,
+            "webglImageMode": "noise", "webglMetadataMode": "custom", "webglVendor": "Google Inc. (NVIDIA)",
+            "webglRenderer": "ANGLE (NVIDIA, NVIDIA GeForce GTX 1060 6GB Direct3D11 vs_5_0 ps_5_0, D3D11)",
+            "canvasNoise": 0.02, "audioNoise": 0.0001, "clientRectsNoise": 0.1, "webrtcMode": "replace",
+            "webrtcPublicIP": "", "geoMode": "query", "geoLatitude": 0.0, "geoLongitude": 0.0, "geoAccuracy": 100.0,
+            "mediaDevicesMode": "real", "fakeCameras": 1, "fakeMicrophones": 1, "fakeSpeakers": 1,
+            "doNotTrack": false, "blockWebRTC": false, "blockCanvas": false, "blockAudioContext": false,
+            "blockImages": false, "blockMedia": false, "fonts": "[]", "plugins": "[]", "speechVoices": "[]",
+            "proxyId": "", "groupId": "", "platformTags": "[]", "notes": "", "bookmarks": "", "status": "active",
+            "lastUsedAt": "", "lastIP": "", "createdAt": "2024-01-01T00:00:00.000Z", "updatedAt": "2024-01-01T00:00:00.000Z"
+        }"#).unwrap();
+        profile.id = id.to_string();
+        profile.name = id.to_string();
+        profile
+    }
+
+    fn proxy(id: &str) -> DbProxy {
+        serde_json::from_value(json!({
+            "id": id, "name": id, "type": "http", "host": "1.2.3.4", "port": 8080, "username": "", "password": "",
+            "country": "US", "city": "", "status": "active", "lastTestedAt": "", "lastIP": "",
+            "createdAt": "2024-01-01T00:00:00.000Z", "updatedAt": "2024-01-01T00:00:00.000Z"
+        })).unwrap()
+    }
+
+    fn workflow(id: &str) -> DbWorkflow {
+        serde_json::from_value(json!({
+            "id": id, "name": id, "description": "", "blocks": "[]", "variables": "{}", "settings": "{}",
+            "status": "active", "lastRunAt": "", "runCount": 0,
+            "createdAt": "2024-01-01T00:00:00.000Z", "updatedAt": "2024-01-01T00:00:00.000Z"
+        })).unwrap()
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn pooled_connections_share_the_file_without_busy_errors() {
+        const SEEDED: usize = 50;
+        const WRITERS: usize = 4;
+        const WRITES: usize = 50;
+
+        let db = Arc::new(test_db());
+        let journal_mode: String = db.conn().unwrap().query_row("PRAGMA journal_mode", [], |row| row.get(0)).unwrap();
+        assert_eq!(journal_mode, "wal");
+        for i in 0..SEEDED {
+            db.create_profile(&profile(&format!("seed-{}", i))).unwrap();
+        }
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let mut threads = Vec::new();
+        for _ in 0..6 {
+            let (db, errors) = (Arc::clone(&db), Arc::clone(&errors));
+            threads.push(std::thread::spawn(move || {
+                let mut last = 0;
+                for _ in 0..100 {
+                    match db.get_profiles() {
+                        // Rows are only ever added, so no reader may see the count go down
+                        Ok(profiles) if profiles.len() >= last.max(SEEDED) => last = profiles.len(),
+                        Ok(profiles) => errors.lock().unwrap().push(format!("read {} rows after {}", profiles.len(), last)),
+                        Err(e) => errors.lock().unwrap().push(e.to_string()),
+                    }
+                }
+            }));
+        }
+        for writer in 0..WRITERS {
+            let (db, errors) = (Arc::clone(&db), Arc::clone(&errors));
+            threads.push(std::thread::spawn(move || {
+                for i in 0..WRITES {
+                    let id = format!("w{}-{}", writer, i);
+                    let result = db.create_profile(&profile(&id))
+                        .and_then(|_| db.touch_profile_usage(&id, "1.1.1.1", "2024-01-01T00:00:00Z"));
+                    if let Err(e) = result {
+                        errors.lock().unwrap().push(e.to_string());
+                    }
+                }
+            }));
+        }
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let errors = errors.lock().unwrap();
+        assert!(errors.is_empty(), "{:?}", *errors);
+        assert_eq!(db.get_profiles().unwrap().len(), SEEDED + WRITERS * WRITES);
+        let audited: i64 = db.conn().unwrap()
+            .query_row("SELECT COUNT(*) FROM audit_log WHERE action = 'create'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(audited as usize, SEEDED + WRITERS * WRITES);
+    }
+
+    pub(crate) fn group(id: &str) -> DbGroup {
+        DbGroup {
+            id: id.to_string(),
+            name: id.to_string(),
+            color: "#ffffff".to_string(),
+            description: String::new(),
+            created_at: String::new(),
+            updated_at: String::new(),
+        }
+    }
+
+    #[test]
+    fn failed_transaction_commits_nothing() {
+        let db = test_db();
+        db.create_profile(&profile("a")).unwrap();
+
+        let result: Result<(), AppError> = db.with_transaction(|tx| {
+            insert_profile(tx, &profile("b"), "")?;
+            insert_profile(tx, &profile("c"), "")?;
+            tx.execute("UPDATE profiles SET group_id = 'g' WHERE id = 'a'", [])?;
+            Err(AppError::Validation("stop here".to_string()))
+        });
+        assert_eq!(result, Err(AppError::Validation("stop here".to_string())));
+
+        let profiles = db.get_profiles().unwrap();
+        assert_eq!(profiles.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), ["a"]);
+        assert_eq!(profiles[0].group_id, "");
+
+        // The same work without the error commits
+        db.with_transaction(|tx| insert_profile(tx, &profile("b"), "").map(|_| ())).unwrap();
+        assert_eq!(db.get_profiles().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn group_with_profiles_rolls_back_on_a_missing_profile() {
+        let db = test_db();
+        db.create_profile(&profile("a")).unwrap();
+        db.create_profile(&profile("b")).unwrap();
+
+        assert!(db.create_group_with_profiles(&group("g1"), &ids(&["a", "missing"])).is_err());
+        assert!(db.get_groups().unwrap().is_empty());
+        assert_eq!(db.get_profile("a").unwrap().unwrap().group_id, "");
+
+        let created = db.create_group_with_profiles(&group(""), &ids(&["a", "b", "a"])).unwrap();
+        assert!(!created.id.is_empty());
+        for id in ["a", "b"] {
+            assert_eq!(db.get_profile(id).unwrap().unwrap().group_id, created.id);
+        }
+    }
+
+    /// The `detail` column of each step SQLite plans for `sql`
+    fn query_plan(db: &Database, sql: &str) -> Vec<String> {
+        let conn = db.conn().unwrap();
+        let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).unwrap();
+        let details = stmt.query_map([""], |row| row.get(3)).unwrap();
+        details.collect::<SqlResult<Vec<String>>>().unwrap()
+    }
+
+    #[test]
+    fn profiles_by_status_filter_and_order() {
+        let db = test_db();
+        for (id, status, last_used_at) in [("a", "banned", "2024-01-01"), ("b", "banned", "2024-06-01"), ("c", "active", "2024-09-01")] {
+            let mut p = profile(id);
+            p.status = status.to_string();
+            p.last_used_at = last_used_at.to_string();
+            db.create_profile(&p).unwrap();
+        }
+
+        let banned: Vec<_> = db.get_profiles_by_status("banned").unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(banned, ["b", "a"]);
+        assert!(db.get_profiles_by_status("idle").unwrap().is_empty());
+    }
+
+    #[test]
+    fn profiles_by_status_uses_the_status_index() {
+        let db = test_db();
+        let plan = query_plan(&db, &format!("SELECT {} FROM profiles WHERE status = ?1 ORDER BY last_used_at DESC", PROFILE_COLUMNS));
+        assert!(plan.iter().any(|step| step.contains("USING INDEX idx_profiles_status")), "{:?}", plan);
+    }
+
+    #[test]
+    fn vacuum_shrinks_the_file_after_deletes() {
+        let db = test_db();
+        let created: Vec<String> = (0..300).map(|i| {
+            let mut p = profile(&format!("p{}", i));
+            p.notes = "x".repeat(2000);
+            db.create_profile(&p).unwrap().id
+        }).collect();
+        db.delete_profiles(&created).unwrap();
+
+        let result = db.vacuum().unwrap();
+        assert!(result.size_after < result.size_before, "{:?}", result);
+        assert_eq!(std::fs::metadata(db.path()).unwrap().len(), result.size_after);
+    }
+
+    #[test]
+    fn vacuum_waits_for_checked_out_connections() {
+        let db = Arc::new(test_db());
+        let held = db.conn().unwrap();
+        let released = Arc::new(Mutex::new(false));
+
+        let vacuum = {
+            let (db, released) = (Arc::clone(&db), Arc::clone(&released));
+            std::thread::spawn(move || {
+                db.vacuum().unwrap();
+                *released.lock().unwrap()
+            })
+        };
+        std::thread::sleep(Duration::from_millis(300));
+        *released.lock().unwrap() = true;
+        drop(held);
+
+        assert!(vacuum.join().unwrap(), "vacuum ran while a connection was checked out");
+    }
+
+    #[test]
+    fn relocate_waits_for_writes_on_the_old_file() {
+        let db = Arc::new(test_db());
+        db.create_profile(&profile("before")).unwrap();
+        let old_path = db.path();
+        let new_path = db.dir.join("moved").join("moved.db");
+
+        // A command that is mid-write when the move starts
+        let mut conn = db.conn().unwrap();
+        let writer = std::thread::spawn(move || {
+            let tx = conn.transaction().unwrap();
+            insert_profile(&tx, &profile("during"), "").unwrap();
+            std::thread::sleep(Duration::from_millis(300));
+            tx.commit().unwrap();
+        });
+
+        let remembered = Mutex::new(Vec::new());
+        let moved = db.relocate_with(&new_path, |path| {
+            remembered.lock().unwrap().push(path.to_path_buf());
+            Ok(())
+        }).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(moved.path, new_path.to_string_lossy());
+        assert_eq!(remembered.into_inner().unwrap(), std::slice::from_ref(&new_path));
+        assert_eq!(db.path(), new_path);
+        assert!(!old_path.exists());
+        assert!(db.get_profile("before").unwrap().is_some());
+        assert!(db.get_profile("during").unwrap().is_some(), "write committed during the move was lost");
+    }
+
+    #[test]
+    fn relocate_refuses_an_existing_file() {
+        let db = test_db();
+        let taken = db.dir.join("taken.db");
+        std::fs::write(&taken, b"").unwrap();
+
+        let result = db.relocate_with(&taken, |_| panic!("location saved for a refused move"));
+        assert!(matches!(result, Err(AppError::Validation(_))));
+        assert_eq!(db.path(), db.dir.join("test.db"));
+    }
+
+    #[test]
+    fn proxy_tests_record_the_egress_ip_and_group_shared_ips() {
+        let db = test_db();
+        for id in ["a", "b", "c", "untested"] {
+            db.create_proxy(&proxy(id)).unwrap();
+        }
+        db.record_proxy_test("a", 120, Some("203.0.113.7")).unwrap();
+        db.record_proxy_test("b", 90, Some("203.0.113.7")).unwrap();
+        db.record_proxy_test("c", 80, Some("198.51.100.4")).unwrap();
+        // A failed test keeps the last IP seen
+        db.record_proxy_test("a", 5000, None).unwrap();
+
+        let a = db.get_proxy("a").unwrap().unwrap();
+        assert_eq!((a.last_ip.as_str(), a.status.as_str()), ("203.0.113.7", "failed"));
+        assert_eq!(db.find_proxies_sharing_ip().unwrap(), [("203.0.113.7".to_string(), ids(&["a", "b"]))]);
+    }
+
+    #[test]
+    fn every_profile_mutator_shows_up_in_updated_since() {
+        let db = test_db();
+        for id in ["a", "b", "c"] {
+            db.create_profile(&profile(id)).unwrap();
+        }
+        db.create_proxy(&proxy("px")).unwrap();
+        db.create_group(&group("g")).unwrap();
+
+        // Ids returned by get_profiles_updated_since for changes made by `mutate`
+        let changed_by = |mutate: &dyn Fn()| -> Vec<String> {
+            let since = now_iso();
+            std::thread::sleep(Duration::from_millis(5));
+            mutate();
+            let mut changed: Vec<String> = db.get_profiles_updated_since(&since).unwrap().into_iter().map(|p| p.id).collect();
+            changed.sort();
+            changed
+        };
+
+        assert_eq!(changed_by(&|| {
+            let mut a = db.get_profile("a").unwrap().unwrap();
+            a.notes = "edited".to_string();
+            db.update_profile(&a).unwrap();
+        }), ["a"]);
+        assert_eq!(changed_by(&|| {
+            let patch = json!({ "notes": "patched" });
+            db.patch_profile("b", patch.as_object().unwrap()).unwrap();
+        }), ["b"]);
+        assert_eq!(changed_by(&|| db.touch_profile_usage("c", "203.0.113.7", &now_iso()).unwrap()), ["c"]);
+        assert_eq!(changed_by(&|| db.set_profile_status("a", "banned").unwrap()), ["a"]);
+        assert_eq!(changed_by(&|| { db.move_profiles_to_group("g", &ids(&["a", "b"])).unwrap(); }), ["a", "b"]);
+        assert_eq!(changed_by(&|| db.delete_group("g").unwrap()), ["a", "b"]);
+        assert_eq!(changed_by(&|| { db.assign_proxy_to_profiles("px", &ids(&["b", "c"])).unwrap(); }), ["b", "c"]);
+        assert_eq!(changed_by(&|| { db.rotate_proxies_over_profiles(&ids(&["px"]), &ids(&["a"])).unwrap(); }), ["a"]);
+        assert_eq!(changed_by(&|| { db.delete_proxy("px", true).unwrap(); }), ["a", "b", "c"]);
+        assert!(changed_by(&|| ()).is_empty());
+    }
+
+    #[test]
+    fn bulk_profile_mutators_write_audit_rows() {
+        let db = test_db();
+        for id in ["a", "b"] {
+            db.create_profile(&profile(id)).unwrap();
+        }
+        for id in ["p1", "p2"] {
+            db.create_proxy(&proxy(id)).unwrap();
+        }
+        db.create_group(&group("g")).unwrap();
+
+        // The changes recorded by the newest audit row for `id`
+        let last_change = |id: &str| -> Value {
+            let entry = db.get_audit_log(Some(id), 1).unwrap().remove(0);
+            assert_eq!((entry.entity_type.as_str(), entry.action.as_str()), ("profile", "update"));
+            serde_json::from_str::<Value>(&entry.details_json).unwrap()["changes"].clone()
+        };
+
+        db.assign_proxy_to_profiles("p1", &ids(&["a", "b"])).unwrap();
+        for id in ["a", "b"] {
+            assert_eq!(last_change(id)["proxyId"], json!({ "from": "", "to": "p1" }));
+        }
+
+        db.rotate_proxies_over_profiles(&ids(&["p2", "p1"]), &ids(&["a", "b"])).unwrap();
+        assert_eq!(last_change("a")["proxyId"], json!({ "from": "p1", "to": "p2" }));
+
+        db.move_profiles_to_group("g", &ids(&["a", "b"])).unwrap();
+        assert_eq!(last_change("b")["groupId"], json!({ "from": "", "to": "g" }));
+
+        db.touch_profile_usage("a", "203.0.113.7", "2024-05-01T00:00:00.000Z").unwrap();
+        assert_eq!(last_change("a")["lastIP"], json!({ "from": "", "to": "203.0.113.7" }));
+
+        db.delete_proxy("p2", true).unwrap();
+        assert_eq!(last_change("a")["proxyId"], json!({ "from": "p2", "to": "" }));
+
+        db.delete_group("g").unwrap();
+        assert_eq!(last_change("b")["groupId"], json!({ "from": "g", "to": "" }));
+
+        // b still used p1, so the rotation left it alone and wrote nothing for it
+        let b_proxy_rows = db.get_audit_log(Some("b"), 100).unwrap().into_iter()
+            .filter(|e| e.details_json.contains("proxyId"))
+            .count();
+        assert_eq!(b_proxy_rows, 1);
+    }
+
+    #[test]
+    fn benchmark_history_and_comparison() {
+        let db = test_db();
+        let first = db.record_benchmark("quick", "chromium", &json!({ "tests": { "launchMs": 800, "pages": [{ "name": "blank", "loadMs": 40 }] } })).unwrap();
+        let second = db.record_benchmark("quick", "chromium", &json!({ "tests": { "launchMs": 600, "pages": [{ "name": "blank", "loadMs": 0 }] } })).unwrap();
+        db.record_benchmark("full", "camoufox", &json!({ "benchmarks": { "launchMs": 1200 } })).unwrap();
+
+        let history: Vec<i64> = db.get_benchmark_history(2).unwrap().into_iter().map(|run| run.id).collect();
+        assert_eq!(history, [second.id + 1, second.id]);
+
+        let deltas = db.compare_benchmark_runs(first.id, second.id).unwrap();
+        assert_eq!(deltas, [
+            BenchmarkDelta { metric: "launchMs".to_string(), before: 800.0, after: 600.0, change: -200.0, percent: Some(-25.0) },
+            BenchmarkDelta { metric: "pages.blank.loadMs".to_string(), before: 40.0, after: 0.0, change: -40.0, percent: Some(-100.0) },
+        ]);
+        let from_zero = db.compare_benchmark_runs(second.id, first.id).unwrap();
+        assert_eq!(from_zero[1].percent, None);
+        assert!(matches!(db.compare_benchmark_runs(first.id, 999), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn benchmark_csv_escapes_commas_and_quotes() {
+        let db = test_db();
+        db.record_benchmark("quick", "chrome \"canary\", beta", &json!({ "tests": { "launchMs": 700 }, "durationMs": 9000 })).unwrap();
+        db.record_benchmark("quick", "chromium", &json!({ "tests": { "launchMs": 650, "cpu": 12.5 } })).unwrap();
+
+        let dest = db.dir.join("bench.csv");
+        assert_eq!(db.export_benchmark_csv(&dest).unwrap(), 2);
+        let csv = std::fs::read_to_string(&dest).unwrap();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], "id,kind,engine,createdAt,launchMs,cpu");
+        assert!(lines[1].starts_with("1,quick,\"chrome \"\"canary\"\", beta\","), "{}", lines[1]);
+        assert!(lines[1].ends_with(",700,"), "{}", lines[1]);
+        assert!(lines[2].ends_with(",650,12.5"), "{}", lines[2]);
+        assert_eq!(lines[3], "");
+
+        assert!(matches!(db.export_benchmark_csv(&db.path()), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn deleting_a_proxy_in_use_needs_force_and_clears_references() {
+        let db = test_db();
+        db.create_proxy(&proxy("px")).unwrap();
+        for id in ["a", "b"] {
+            let mut p = profile(id);
+            p.proxy_id = "px".to_string();
+            db.create_profile(&p).unwrap();
+        }
+        db.create_profile(&profile("c")).unwrap();
+
+        let refused = db.delete_proxy("px", false).unwrap_err();
+        assert!(matches!(&refused, AppError::Validation(m) if m.contains("2 profile(s)")), "{:?}", refused);
+        assert!(db.get_proxy("px").unwrap().is_some());
+        assert_eq!(db.get_profile("a").unwrap().unwrap().proxy_id, "px");
+
+        assert_eq!(db.delete_proxy("px", true).unwrap(), 2);
+        assert!(db.get_proxy("px").unwrap().is_none());
+        for id in ["a", "b"] {
+            assert_eq!(db.get_profile(id).unwrap().unwrap().proxy_id, "");
+        }
+        // An unused proxy deletes without force
+        db.create_proxy(&proxy("idle")).unwrap();
+        assert_eq!(db.delete_proxy("idle", false).unwrap(), 0);
+    }
+
+    #[test]
+    fn cloned_workflow_keeps_its_json_byte_for_byte() {
+        let db = test_db();
+        let mut source = workflow("w");
+        // Key order and spacing a re-serialization would change
+        source.blocks = r#"[ {"type": "navigate", "id": "b1",  "url": "https://example.com"} ]"#.to_string();
+        source.variables = r#"{"zeta": 1, "alpha":"x"}"#.to_string();
+        source.settings = "{\n  \"timeout\": 30000\n}".to_string();
+        source.last_run_at = "2024-03-01T00:00:00.000Z".to_string();
+        source.run_count = 7;
+        db.create_workflow(&source).unwrap();
+
+        let clone = db.clone_workflow("w", "w copy").unwrap();
+        assert_ne!(clone.id, "w");
+        assert_eq!(clone.name, "w copy");
+        assert_eq!((clone.blocks.as_str(), clone.variables.as_str(), clone.settings.as_str()),
+            (source.blocks.as_str(), source.variables.as_str(), source.settings.as_str()));
+        assert_eq!((clone.run_count, clone.last_run_at.as_str()), (0, ""));
+
+        let stored = db.get_workflow(&clone.id).unwrap().unwrap();
+        assert_eq!((stored.blocks, stored.variables, stored.settings), (source.blocks, source.variables, source.settings));
+        assert!(matches!(db.clone_workflow("missing", "x"), Err(AppError::NotFound(_))));
+    }
+
+    /// Group `id` as stored
+    fn stored_group(db: &Database, id: &str) -> DbGroup {
+        db.get_groups().unwrap().into_iter().find(|g| g.id == id).unwrap()
+    }
+
+    #[test]
+    fn group_names_are_unique_ignoring_case() {
+        let db = test_db();
+        db.create_group(&group("work")).unwrap();
+        db.create_group(&group("home")).unwrap();
+
+        let taken = db.rename_group("home", " WORK ");
+        assert!(matches!(taken, Err(AppError::Validation(_))), "{:?}", taken);
+        assert_eq!(stored_group(&db, "home").name, "home");
+
+        db.rename_group("home", "Personal").unwrap();
+        assert_eq!(stored_group(&db, "home").name, "Personal");
+        // A group may change the case of its own name
+        db.rename_group("work", "Work").unwrap();
+        assert_eq!(stored_group(&db, "work").name, "Work");
+
+        assert!(matches!(db.rename_group("home", "  "), Err(AppError::Validation(_))));
+        assert!(matches!(db.rename_group("missing", "x"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn renaming_a_group_to_its_own_name_changes_nothing() {
+        let db = test_db();
+        db.create_group(&group("work")).unwrap();
+        let before = stored_group(&db, "work");
+
+        std::thread::sleep(Duration::from_millis(5));
+        db.rename_group("work", "work").unwrap();
+        let after = stored_group(&db, "work");
+        assert_eq!((after.name, after.updated_at), (before.name, before.updated_at));
+    }
+
+    #[test]
+    fn recent_profiles_put_never_used_ones_last() {
+        let db = test_db();
+        for (id, last_used_at, created_at) in [
+            ("old", "2024-05-01T10:00:00.000Z", "2024-01-01T00:00:00.000Z"),
+            ("never", "", "2024-01-01T00:00:00.000Z"),
+            // Sorts after "old" as text but is an earlier instant
+            ("offset", "2024-05-01T12:00:00+03:00", "2024-01-01T00:00:00.000Z"),
+            ("newest", "2024-06-01T00:00:00Z", "2024-01-01T00:00:00.000Z"),
+            ("never-newer", "", "2024-02-01T00:00:00.000Z"),
+        ] {
+            let mut p = profile(id);
+            p.last_used_at = last_used_at.to_string();
+            p.created_at = created_at.to_string();
+            db.create_profile(&p).unwrap();
+        }
+
+        let recent: Vec<String> = db.get_recent_profiles(10).unwrap().into_iter().map(|p| p.id).collect();
+        assert_eq!(recent, ["newest", "old", "offset", "never-newer", "never"]);
+        assert_eq!(db.get_recent_profiles(2).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn search_proxies_combines_filters() {
+        let db = test_db();
+        for (id, country, status, host) in [
+            ("us-ok", "US", "active", "us1.proxy.net"),
+            ("us-bad", "US", "failed", "us2.proxy.net"),
+            ("de-ok", "DE", "active", "de_1.example.org"),
+            ("odd", "DE", "failed", "50%off.example.org"),
+        ] {
+            let mut p = proxy(id);
+            (p.country, p.status, p.host) = (country.to_string(), status.to_string(), host.to_string());
+            db.create_proxy(&p).unwrap();
+        }
+        let search = |country: Option<&str>, status: Option<&str>, host: Option<&str>| -> Vec<String> {
+            let found = db.search_proxies(country.map(Into::into), status.map(Into::into), host.map(Into::into)).unwrap();
+            let mut ids: Vec<String> = found.into_iter().map(|p| p.id).collect();
+            ids.sort();
+            ids
+        };
+
+        assert_eq!(search(None, None, None), ["de-ok", "odd", "us-bad", "us-ok"]);
+        assert_eq!(search(Some(" us "), None, None), ["us-bad", "us-ok"]);
+        assert_eq!(search(None, Some("active"), None), ["de-ok", "us-ok"]);
+        assert_eq!(search(None, None, Some("proxy.net")), ["us-bad", "us-ok"]);
+        assert_eq!(search(Some("US"), Some("active"), Some("proxy")), ["us-ok"]);
+        assert_eq!(search(Some(""), Some("  "), None).len(), 4);
+
+        // LIKE wildcards in the search text match literally
+        assert_eq!(search(None, None, Some("%")), ["odd"]);
+        assert_eq!(search(None, None, Some("_1")), ["de-ok"]);
+        assert!(search(None, None, Some("' OR 1=1 --")).is_empty());
+    }
+
+    #[test]
+    fn groups_with_counts_include_empty_groups() {
+        let db = test_db();
+        for id in ["zeta", "alpha", "empty"] {
+            db.create_group(&group(id)).unwrap();
+        }
+        for (id, group_id) in [("a", "alpha"), ("b", "alpha"), ("c", "zeta"), ("loose", "")] {
+            let mut p = profile(id);
+            p.group_id = group_id.to_string();
+            db.create_profile(&p).unwrap();
+        }
+
+        let counts: Vec<(String, i64)> = db.get_groups_with_counts().unwrap().into_iter().map(|(g, n)| (g.name, n)).collect();
+        assert_eq!(counts, [("alpha".to_string(), 2), ("empty".to_string(), 0), ("zeta".to_string(), 1)]);
+    }
+
+    #[test]
+    fn set_profile_status_changes_only_the_status() {
+        let db = test_db();
+        let mut p = profile("a");
+        p.notes = "keep me".to_string();
+        db.create_profile(&p).unwrap();
+
+        db.set_profile_status("a", "banned").unwrap();
+        let stored = db.get_profile("a").unwrap().unwrap();
+        assert_eq!((stored.status.as_str(), stored.notes.as_str()), ("banned", "keep me"));
+        assert!(stored.updated_at > p.updated_at);
+
+        let rejected = db.set_profile_status("a", "deleted");
+        assert!(matches!(&rejected, Err(AppError::Validation(m)) if m.contains("active, idle, banned, archived")), "{:?}", rejected);
+        assert_eq!(db.get_profile("a").unwrap().unwrap().status, "banned");
+        assert!(matches!(db.set_profile_status("missing", "idle"), Err(AppError::NotFound(_))));
+    }
+
+    /// Pre-populate "a" and "b", then import a renamed "a" plus a new "c" listed twice
+    fn import_batch(db: &Database) -> Vec<DbProfile> {
+        db.create_profile(&profile("a")).unwrap();
+        db.create_profile(&profile("b")).unwrap();
+        let mut renamed = profile("a");
+        renamed.name = "A2".into();
+        vec![renamed, profile("c"), profile("c")]
+    }
+
+    #[test]
+    fn import_skip_keeps_existing_rows() {
+        let db = test_db();
+        let batch = import_batch(&db);
+
+        let result = db.import_profiles(&batch, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.inserted, result.skipped, result.overwritten), (1, 2, 0));
+        assert_eq!(db.get_profile("a").unwrap().unwrap().name, "a");
+        assert_eq!(db.get_profiles().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn import_overwrite_upserts_colliding_rows() {
+        let db = test_db();
+        let batch = import_batch(&db);
+
+        let result = db.import_profiles(&batch, ImportStrategy::Overwrite).unwrap();
+        assert_eq!((result.inserted, result.skipped, result.overwritten), (1, 0, 2));
+        assert_eq!(db.get_profile("a").unwrap().unwrap().name, "A2");
+        assert_eq!(db.get_profile("b").unwrap().unwrap().name, "b");
+        assert_eq!(db.get_profiles().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn import_regenerate_id_inserts_copies() {
+        let db = test_db();
+        let batch = import_batch(&db);
+
+        let result = db.import_profiles(&batch, ImportStrategy::RegenerateId).unwrap();
+        assert_eq!((result.inserted, result.skipped, result.overwritten), (3, 0, 0));
+        assert_eq!(db.get_profile("a").unwrap().unwrap().name, "a");
+
+        let profiles = db.get_profiles().unwrap();
+        assert_eq!(profiles.len(), 5);
+        let copy = profiles.iter().find(|p| p.name == "A2").unwrap();
+        assert_ne!(copy.id, "a");
+        assert_eq!(profiles.iter().filter(|p| p.name == "c").count(), 2);
+
+        let strategy: ImportStrategy = serde_json::from_str("\"regenerateId\"").unwrap();
+        assert_eq!(strategy, ImportStrategy::RegenerateId);
+    }
+
+    #[test]
+    fn create_fills_empty_ids_and_timestamps() {
+        let db = test_db();
+        let uuid = |id: &str| uuid::Uuid::parse_str(id).is_ok();
+        let stamped = |at: &str| chrono::DateTime::parse_from_rfc3339(at).is_ok() && at.ends_with('Z');
+
+        let mut empty = profile("");
+        empty.created_at.clear();
+        empty.updated_at.clear();
+        let created = db.create_profile(&empty).unwrap();
+        assert!(uuid(&created.id));
+        assert!(stamped(&created.created_at) && stamped(&created.updated_at));
+        assert!(db.get_profile(&created.id).unwrap().is_some());
+
+        let mut empty = proxy("");
+        empty.created_at.clear();
+        empty.updated_at.clear();
+        let created = db.create_proxy(&empty).unwrap();
+        assert!(uuid(&created.id) && stamped(&created.created_at) && stamped(&created.updated_at));
+
+        let mut empty = workflow("");
+        empty.created_at.clear();
+        empty.updated_at.clear();
+        let created = db.create_workflow(&empty).unwrap();
+        assert!(uuid(&created.id) && stamped(&created.created_at) && stamped(&created.updated_at));
+
+        let created = db.create_group(&group("")).unwrap();
+        assert!(uuid(&created.id) && stamped(&created.created_at) && stamped(&created.updated_at));
+
+        // Two creates never share a generated id
+        assert_ne!(db.create_group(&DbGroup { name: "other".into(), ..group("") }).unwrap().id, created.id);
+    }
+
+    #[test]
+    fn create_keeps_provided_ids_and_timestamps() {
+        let db = test_db();
+        let at = "2024-01-01T00:00:00.000Z";
+
+        let created = db.create_profile(&profile("p1")).unwrap();
+        assert_eq!((created.id.as_str(), created.created_at.as_str(), created.updated_at.as_str()), ("p1", at, at));
+        let stored = db.get_profile("p1").unwrap().unwrap();
+        assert_eq!((stored.created_at.as_str(), stored.updated_at.as_str()), (at, at));
+
+        let created = db.create_proxy(&proxy("x1")).unwrap();
+        assert_eq!((created.id.as_str(), created.created_at.as_str()), ("x1", at));
+
+        let created = db.create_workflow(&workflow("w1")).unwrap();
+        assert_eq!((created.id.as_str(), created.created_at.as_str()), ("w1", at));
+
+        let created = db.create_group(&DbGroup { created_at: at.into(), updated_at: at.into(), ..group("g1") }).unwrap();
+        assert_eq!((created.id.as_str(), created.created_at.as_str(), created.updated_at.as_str()), ("g1", at, at));
+    }
+
+    fn profile_with_status(id: &str, status: &str) -> DbProfile {
+        DbProfile { status: status.to_string(), ..profile(id) }
+    }
+
+    #[test]
+    fn purge_dry_run_lists_without_deleting() {
+        let db = test_db();
+        db.create_profile(&profile_with_status("a1", "archived")).unwrap();
+        db.create_profile(&profile_with_status("a2", "archived")).unwrap();
+        db.create_profile(&profile_with_status("b1", "banned")).unwrap();
+
+        let result = db.purge_profiles_by_status("archived", true).unwrap();
+        assert!(result.dry_run);
+        assert_eq!(result.deleted, 0);
+        let mut purged = result.ids.clone();
+        purged.sort();
+        assert_eq!(purged, ids(&["a1", "a2"]));
+        assert_eq!(db.get_profiles().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn purge_deletes_only_the_given_status() {
+        let db = test_db();
+        db.create_profile(&profile_with_status("a1", "archived")).unwrap();
+        db.create_profile(&profile_with_status("b1", "banned")).unwrap();
+        db.create_profile(&profile("live")).unwrap();
+
+        let result = db.purge_profiles_by_status("banned", false).unwrap();
+        assert!(!result.dry_run);
+        assert_eq!((result.deleted, result.ids), (1, ids(&["b1"])));
+        assert!(db.get_profile("b1").unwrap().is_none());
+        assert!(db.get_profile("a1").unwrap().is_some());
+        assert!(db.get_profile("live").unwrap().is_some());
+
+        // Active profiles can never be purged, not even in a dry run
+        for dry_run in [true, false] {
+            assert!(matches!(db.purge_profiles_by_status("active", dry_run), Err(AppError::Validation(_))));
+        }
+        assert!(db.get_profile("live").unwrap().is_some());
+    }
+
+    #[test]
+    fn profile_relations_are_joined_when_set() {
+        let db = test_db();
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_group(&group("g1")).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x1".into(), group_id: "g1".into(), ..profile("both") }).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x1".into(), ..profile("proxy-only") }).unwrap();
+        db.create_profile(&DbProfile { group_id: "g1".into(), ..profile("group-only") }).unwrap();
+        db.create_profile(&profile("neither")).unwrap();
+
+        let both = db.get_profile_with_relations("both").unwrap().unwrap();
+        assert_eq!(both.profile.id, "both");
+        assert_eq!(both.proxy.unwrap().host, "1.2.3.4");
+        assert_eq!(both.group.unwrap().name, "g1");
+
+        let proxy_only = db.get_profile_with_relations("proxy-only").unwrap().unwrap();
+        assert_eq!((proxy_only.proxy.is_some(), proxy_only.group.is_none()), (true, true));
+
+        let group_only = db.get_profile_with_relations("group-only").unwrap().unwrap();
+        assert_eq!((group_only.proxy.is_none(), group_only.group.is_some()), (true, true));
+
+        let neither = db.get_profile_with_relations("neither").unwrap().unwrap();
+        assert!(neither.proxy.is_none() && neither.group.is_none());
+
+        // A dangling id reads as no relation, and a missing profile as None
+        db.create_profile(&DbProfile { proxy_id: "gone".into(), ..profile("dangling") }).unwrap();
+        assert!(db.get_profile_with_relations("dangling").unwrap().unwrap().proxy.is_none());
+        assert!(db.get_profile_with_relations("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn orphaned_references_are_found_and_cleared() {
+        let db = test_db();
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_group(&group("g1")).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x1".into(), group_id: "g1".into(), ..profile("fine") }).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x-gone".into(), group_id: "g-gone".into(), ..profile("orphan") }).unwrap();
+        db.create_profile(&DbProfile { group_id: "g-gone".into(), ..profile("half") }).unwrap();
+
+        let mut found: Vec<_> = db.find_orphaned_references().unwrap().into_iter()
+            .map(|o| (o.profile_id, o.field, o.missing_id))
+            .collect();
+        found.sort();
+        let entry = |id: &str, field: &str, missing: &str| (id.to_string(), field.to_string(), missing.to_string());
+        assert_eq!(found, vec![
+            entry("half", "groupId", "g-gone"),
+            entry("orphan", "groupId", "g-gone"),
+            entry("orphan", "proxyId", "x-gone"),
+        ]);
+
+        assert_eq!(db.fix_orphaned_references().unwrap(), 3);
+        assert!(db.find_orphaned_references().unwrap().is_empty());
+        let orphan = db.get_profile("orphan").unwrap().unwrap();
+        assert_eq!((orphan.proxy_id.as_str(), orphan.group_id.as_str()), ("", ""));
+        let fine = db.get_profile("fine").unwrap().unwrap();
+        assert_eq!((fine.proxy_id.as_str(), fine.group_id.as_str()), ("x1", "g1"));
+        assert_eq!(db.fix_orphaned_references().unwrap(), 0);
+    }
+
+    /// One row in every table, with a profile pointing at the proxy and group
+    fn populate_backup_tables(db: &Database) {
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_group(&DbGroup { description: "d".into(), ..group("g1") }).unwrap();
+        db.create_workflow(&workflow("w1")).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x1".into(), group_id: "g1".into(), ..profile("a") }).unwrap();
+        db.create_profile(&profile("b")).unwrap();
+    }
+
+    fn all_tables(db: &Database) -> Value {
+        json!({
+            "groups": db.get_groups().unwrap(),
+            "proxies": db.get_proxies().unwrap(),
+            "workflows": db.get_workflows().unwrap(),
+            "profiles": db.get_profiles().unwrap(),
+        })
+    }
+
+    #[test]
+    fn full_backup_round_trips_through_a_wipe() {
+        let db = test_db();
+        populate_backup_tables(&db);
+        let file = db.dir.join("backup.json");
+        assert!(matches!(db.export_all(&db.path()), Err(AppError::Validation(_))));
+        db.export_all(&file).unwrap();
+        let before = all_tables(&db);
+
+        let result = db.import_all(&file, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.groups.skipped, result.proxies.skipped, result.workflows.skipped, result.profiles.skipped), (1, 1, 1, 2));
+
+        db.delete_profiles(&ids(&["a", "b"])).unwrap();
+        db.delete_group("g1").unwrap();
+        db.delete_proxy("x1", true).unwrap();
+        db.delete_workflow("w1").unwrap();
+        assert_eq!(all_tables(&db), json!({ "groups": [], "proxies": [], "workflows": [], "profiles": [] }));
+
+        let result = db.import_all(&file, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.groups.inserted, result.proxies.inserted, result.workflows.inserted, result.profiles.inserted), (1, 1, 1, 2));
+        assert_eq!(all_tables(&db), before);
+    }
+
+    #[test]
+    fn full_backup_regenerated_ids_keep_relations() {
+        let db = test_db();
+        populate_backup_tables(&db);
+        let file = db.dir.join("backup.json");
+        db.export_all(&file).unwrap();
+
+        let result = db.import_all(&file, ImportStrategy::RegenerateId).unwrap();
+        assert_eq!(result.profiles.inserted, 2);
+        let copy = db.get_profiles().unwrap().into_iter()
+            .find(|p| p.name == "a" && p.id != "a")
+            .unwrap();
+        assert_ne!((copy.proxy_id.as_str(), copy.group_id.as_str()), ("x1", "g1"));
+        assert!(db.get_proxy(&copy.proxy_id).unwrap().is_some());
+        assert!(db.get_groups().unwrap().iter().any(|g| g.id == copy.group_id));
+        assert!(db.find_orphaned_references().unwrap().is_empty());
+    }
+
+    #[test]
+    fn full_backup_header_is_checked() {
+        let db = test_db();
+        populate_backup_tables(&db);
+        let file = db.dir.join("backup.json");
+        db.export_all(&file).unwrap();
+
+        let mut backup: Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+        backup["schemaVersion"] = json!(99);
+        std::fs::write(&file, backup.to_string()).unwrap();
+        assert!(db.import_all(&file, ImportStrategy::Skip).unwrap_err().to_string().contains("99"));
+
+        std::fs::write(&file, r#"{"cookies": []}"#).unwrap();
+        assert!(db.import_all(&file, ImportStrategy::Skip).is_err());
+        assert_eq!(db.get_profiles().unwrap().len(), 2);
+    }
+
+    /// A stored profile as JSON, minus the fields a patch is expected to change
+    fn profile_without(db: &Database, id: &str, changed: &[&str]) -> Value {
+        let mut value = serde_json::to_value(db.get_profile(id).unwrap().unwrap()).unwrap();
+        for key in changed.iter().chain(&["updatedAt", "fingerprintHash"]) {
+            value.as_object_mut().unwrap().remove(*key);
+        }
+        value
+    }
+
+    fn patch(value: Value) -> serde_json::Map<String, Value> {
+        value.as_object().unwrap().clone()
+    }
+
+    #[test]
+    fn patching_one_field_leaves_the_rest() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+        let before = profile_without(&db, "p1", &["notes"]);
+
+        let patched = db.patch_profile("p1", &patch(json!({ "notes": "checked" }))).unwrap();
+        assert_eq!(patched.notes, "checked");
+        assert_ne!(patched.updated_at, "2024-01-01T00:00:00.000Z");
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().notes, "checked");
+        assert_eq!(profile_without(&db, "p1", &["notes"]), before);
+    }
+
+    #[test]
+    fn patching_several_fields_converts_each_type() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+        let changed = ["name", "viewportWidth", "canvasNoise", "blockImages"];
+        let before = profile_without(&db, "p1", &changed);
+
+        let patched = db.patch_profile("p1", &patch(json!({
+            "name": "renamed", "viewport_width": 1280, "canvas_noise": 0.5, "block_images": true
+        }))).unwrap();
+        assert_eq!(patched.name, "renamed");
+        assert_eq!(patched.viewport_width, 1280);
+        assert_eq!(patched.canvas_noise, 0.5);
+        assert!(patched.block_images);
+        assert_eq!(profile_without(&db, "p1", &changed), before);
+    }
+
+    #[test]
+    fn patches_with_bad_columns_or_values_change_nothing() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+        let before = serde_json::to_value(db.get_profile("p1").unwrap().unwrap()).unwrap();
+
+        for bad in [
+            json!({ "nickname": "x" }),
+            json!({ "name = 'x', notes": "y" }),
+            json!({ "id": "p2" }),
+            json!({ "created_at": "2020-01-01T00:00:00.000Z" }),
+            json!({ "notes": "fine", "viewport_width": "wide" }),
+            json!({ "viewport_width": 1.5 }),
+            json!({ "canvas_noise": "high" }),
+            json!({}),
+        ] {
+            let result = db.patch_profile("p1", &patch(bad.clone()));
+            assert!(matches!(result, Err(AppError::Validation(_))), "{} -> {:?}", bad, result);
+        }
+        assert!(matches!(db.patch_profile("missing", &patch(json!({ "notes": "x" }))), Err(AppError::NotFound(_))));
+        assert_eq!(serde_json::to_value(db.get_profile("p1").unwrap().unwrap()).unwrap(), before);
+    }
+
+    #[test]
+    fn jsonl_export_and_import_round_trip_10k_profiles() {
+        let db = test_db();
+        let profiles: Vec<DbProfile> = (0..10_000).map(|i| profile(&format!("p{:05}", i))).collect();
+        assert_eq!(db.import_profiles(&profiles, ImportStrategy::Skip).unwrap().inserted, 10_000);
+
+        let file = db.dir.join("profiles.jsonl");
+        assert_eq!(db.export_profiles_jsonl(&file).unwrap(), 10_000);
+        let contents = std::fs::read_to_string(&file).unwrap();
+        assert_eq!(contents.lines().count(), 10_000);
+        assert!(contents.lines().all(|line| line.starts_with('{') && line.ends_with('}')));
+
+        let restored = test_db();
+        let result = restored.import_profiles_jsonl(&file, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.inserted, result.skipped), (10_000, 0));
+        assert_eq!(restored.get_profiles().unwrap().len(), 10_000);
+        assert_eq!(
+            serde_json::to_value(restored.get_profile("p04242").unwrap()).unwrap(),
+            serde_json::to_value(db.get_profile("p04242").unwrap()).unwrap()
+        );
+
+        assert_eq!(restored.import_profiles_jsonl(&file, ImportStrategy::Skip).unwrap().skipped, 10_000);
+    }
+
+    #[test]
+    fn jsonl_import_is_all_or_nothing() {
+        let db = test_db();
+        let file = db.dir.join("profiles.jsonl");
+        let line = |id: &str| serde_json::to_string(&profile(id)).unwrap();
+        std::fs::write(&file, format!("{}\n\n{}\nnot json\n", line("a"), line("b"))).unwrap();
+
+        let error = db.import_profiles_jsonl(&file, ImportStrategy::Skip).unwrap_err();
+        assert!(error.to_string().contains("Line 4"), "{}", error);
+        assert!(db.get_profiles().unwrap().is_empty());
+    }
+
+    #[test]
+    fn workflows_filter_by_status() {
+        let db = test_db();
+        db.create_workflow(&workflow("on1")).unwrap();
+        db.create_workflow(&DbWorkflow { status: "inactive".into(), ..workflow("off1") }).unwrap();
+        db.create_workflow(&workflow("on2")).unwrap();
+
+        let names = |status: &str| {
+            let mut names: Vec<String> = db.get_workflows_by_status(status).unwrap().into_iter().map(|w| w.id).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names("active"), ids(&["on1", "on2"]));
+        assert_eq!(names("inactive"), ids(&["off1"]));
+        assert!(names("paused").is_empty());
+    }
+
+    #[test]
+    fn workflow_status_toggle_touches_only_status() {
+        let db = test_db();
+        let blocks = r#"[{"type":"navigate","url":"https://example.com"}]"#;
+        db.create_workflow(&DbWorkflow { blocks: blocks.into(), ..workflow("w1") }).unwrap();
+        let mut before = serde_json::to_value(db.get_workflow("w1").unwrap().unwrap()).unwrap();
+
+        db.set_workflow_status("w1", "inactive").unwrap();
+        let after = db.get_workflow("w1").unwrap().unwrap();
+        assert_eq!(after.status, "inactive");
+        assert_ne!(after.updated_at, "2024-01-01T00:00:00.000Z");
+        assert_eq!(db.get_workflows_by_status("inactive").unwrap().len(), 1);
+
+        let mut after = serde_json::to_value(after).unwrap();
+        for value in [&mut before, &mut after] {
+            let object = value.as_object_mut().unwrap();
+            object.remove("status");
+            object.remove("updatedAt");
+        }
+        assert_eq!(after, before);
+
+        assert!(matches!(db.set_workflow_status("w1", "paused"), Err(AppError::Validation(_))));
+        assert!(matches!(db.set_workflow_status("missing", "active"), Err(AppError::NotFound(_))));
+        assert_eq!(db.get_workflow("w1").unwrap().unwrap().status, "inactive");
+    }
+
+    #[test]
+    fn identical_fingerprints_are_grouped_and_deduped() {
+        let db = test_db();
+        // Same fingerprint; name, notes, status, proxy and timestamps aren't part of it
+        db.create_profile(&DbProfile { created_at: "2024-01-01T00:00:00.000Z".into(), ..profile("twin-old") }).unwrap();
+        db.create_profile(&DbProfile {
+            notes: "clone".into(),
+            status: "archived".into(),
+            proxy_id: "x1".into(),
+            created_at: "2024-02-01T00:00:00.000Z".into(),
+            ..profile("twin-new")
+        }).unwrap();
+        db.create_profile(&DbProfile { canvas_noise: 0.07, ..profile("distinct") }).unwrap();
+
+        assert_eq!(db.find_duplicate_profiles().unwrap(), vec![ids(&["twin-old", "twin-new"])]);
+
+        let result = db.dedupe_profiles(DedupeKeep::Newest).unwrap();
+        assert_eq!(result, DedupeResult { kept: ids(&["twin-new"]), deleted: ids(&["twin-old"]) });
+        assert!(db.get_profile("twin-old").unwrap().is_none());
+        assert!(db.get_profile("distinct").unwrap().is_some());
+        assert!(db.find_duplicate_profiles().unwrap().is_empty());
+        assert_eq!(db.dedupe_profiles(DedupeKeep::Oldest).unwrap().deleted.len(), 0);
+    }
+
+    #[test]
+    fn locale_bundle_sets_all_four_fields() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+
+        db.set_profile_locale_bundle("p1", " Europe/London ", "en-GB", "en-GB,en", "gb").unwrap();
+        let stored = db.get_profile("p1").unwrap().unwrap();
+        assert_eq!(
+            (stored.timezone.as_str(), stored.locale.as_str(), stored.language.as_str(), stored.country.as_str()),
+            ("Europe/London", "en-GB", "en-GB,en", "GB")
+        );
+        assert_ne!(stored.updated_at, "2024-01-01T00:00:00.000Z");
+        assert_eq!(stored.name, "p1");
+    }
+
+    #[test]
+    fn invalid_locale_bundles_change_nothing() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+
+        let error = db.set_profile_locale_bundle("p1", "Mars/Olympus_Mons", "en-GB", "en", "GB").unwrap_err();
+        assert!(matches!(&error, AppError::Validation(m) if m.contains("Unknown timezone 'Mars/Olympus_Mons'")), "{:?}", error);
+        assert!(matches!(db.set_profile_locale_bundle("p1", "Europe/London", "en-GB", "en", "XX"), Err(AppError::Validation(_))));
+        assert!(matches!(db.set_profile_locale_bundle("p1", "Europe/London", "en-GB", "en", "GBR"), Err(AppError::Validation(_))));
+        assert!(matches!(db.set_profile_locale_bundle("p1", "Europe/London", " ", "en", "GB"), Err(AppError::Validation(_))));
+        assert!(matches!(db.set_profile_locale_bundle("missing", "Europe/London", "en-GB", "en", "GB"), Err(AppError::NotFound(_))));
+
+        let stored = db.get_profile("p1").unwrap().unwrap();
+        assert_eq!((stored.timezone.as_str(), stored.country.as_str()), ("America/New_York", "US"));
+        assert_eq!(stored.updated_at, "2024-01-01T00:00:00.000Z");
+    }
+
+    #[test]
+    fn profile_proxy_resolves_the_stored_proxy_id() {
+        let db = test_db();
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "x1".into(), ..profile("with") }).unwrap();
+        db.create_profile(&profile("without")).unwrap();
+        db.create_profile(&DbProfile { proxy_id: "gone".into(), ..profile("dangling") }).unwrap();
+
+        assert_eq!(db.get_profile_proxy("with").unwrap().unwrap().id, "x1");
+        assert!(db.get_profile_proxy("without").unwrap().is_none());
+        assert!(db.get_profile_proxy("missing").unwrap().is_none());
+        assert!(matches!(db.get_profile_proxy("dangling"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn cookie_snapshots_save_list_and_restore() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+        let jar = crate::cookies::normalize_cookie_import(json!([
+            { "name": "sid", "value": "abc", "domain": ".example.com", "expires": 1_900_000_000.0, "httpOnly": true },
+            { "name": "theme", "value": "dark", "domain": "example.com" }
+        ])).unwrap();
+        let blob = crate::cookies::serialize_cookies(&jar, crate::cookies::CookieFormat::Json).unwrap();
+
+        let saved = db.save_cookie_snapshot("p1", " logged-in ", &blob, jar.len(), false).unwrap();
+        assert_eq!((saved.snapshot_name.as_str(), saved.cookie_count, saved.encrypted), ("logged-in", 2, false));
+        std::thread::sleep(Duration::from_millis(5));
+        db.save_cookie_snapshot("p1", "empty", "[]", 0, false).unwrap();
+
+        let names: Vec<String> = db.list_cookie_snapshots("p1").unwrap().into_iter().map(|s| s.snapshot_name).collect();
+        assert_eq!(names, ids(&["empty", "logged-in"]));
+        assert!(db.list_cookie_snapshots("other").unwrap().is_empty());
+
+        let restored = db.get_cookie_snapshot("p1", "logged-in").unwrap();
+        assert_eq!(restored, blob);
+        let restored = crate::cookies::parse_cookies(&restored, crate::cookies::CookieFormat::Json, None).unwrap();
+        assert_eq!(restored, jar);
+    }
+
+    #[test]
+    fn saving_a_cookie_snapshot_again_replaces_it() {
+        let db = test_db();
+        db.create_profile(&profile("p1")).unwrap();
+        db.save_cookie_snapshot("p1", "daily", "[]", 0, false).unwrap();
+        db.save_cookie_snapshot("p1", "daily", "encrypted-envelope", 3, true).unwrap();
+
+        let snapshots = db.list_cookie_snapshots("p1").unwrap();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!((snapshots[0].cookie_count, snapshots[0].encrypted), (3, true));
+        assert_eq!(db.get_cookie_snapshot("p1", "daily").unwrap(), "encrypted-envelope");
+
+        assert!(matches!(db.save_cookie_snapshot("p1", "  ", "[]", 0, false), Err(AppError::Validation(_))));
+        assert!(matches!(db.save_cookie_snapshot("missing", "daily", "[]", 0, false), Err(AppError::NotFound(_))));
+        assert!(matches!(db.get_cookie_snapshot("p1", "weekly"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn reset_fingerprint_restores_schema_defaults() {
+        let db = test_db();
+        db.create_group(&group("g1")).unwrap();
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_profile(&DbProfile {
+            group_id: "g1".into(),
+            proxy_id: "x1".into(),
+            notes: "keep me".into(),
+            user_agent: "odd agent".into(),
+            viewport_width: 333,
+            canvas_noise: 0.9,
+            block_images: true,
+            timezone: "Asia/Tokyo".into(),
+            ..profile("p1")
+        }).unwrap();
+        // A row that got every column's schema default
+        db.conn().unwrap().execute("INSERT INTO profiles (id, name, created_at, updated_at) VALUES ('fresh', 'fresh', '', '')", []).unwrap();
+
+        let reset = db.reset_profile_fingerprint("p1").unwrap();
+        assert_eq!(
+            (reset.name.as_str(), reset.group_id.as_str(), reset.proxy_id.as_str(), reset.notes.as_str()),
+            ("p1", "g1", "x1", "keep me")
+        );
+        assert_eq!(reset.created_at, "2024-01-01T00:00:00.000Z");
+        assert_ne!(reset.updated_at, "2024-01-01T00:00:00.000Z");
+        assert_eq!((reset.viewport_width, reset.block_images), (1920, false));
+
+        let fingerprint = |profile: &DbProfile| {
+            let mut value = serde_json::to_value(profile).unwrap();
+            let object = value.as_object_mut().unwrap();
+            for kept in [
+                "id", "name", "groupId", "proxyId", "notes", "bookmarks", "platformTags", "status",
+                "lastUsedAt", "lastIP", "fingerprintSeed", "fingerprintHash", "createdAt", "updatedAt",
+            ] {
+                object.remove(kept);
+            }
+            value
+        };
+        let fresh = db.get_profile("fresh").unwrap().unwrap();
+        assert_eq!(fingerprint(&db.get_profile("p1").unwrap().unwrap()), fingerprint(&fresh));
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().fingerprint_seed, "");
+
+        assert!(matches!(db.reset_profile_fingerprint("missing"), Err(AppError::NotFound(_))));
+    }
+
+    #[test]
+    fn grouping_by_tag_matches_whole_tags() {
+        let db = test_db();
+        db.create_group(&group("fb")).unwrap();
+        let tagged = |id: &str, tags: &str| DbProfile { platform_tags: tags.into(), ..profile(id) };
+        db.create_profile(&tagged("multi", r#"["google","fb","tiktok"]"#)).unwrap();
+        db.create_profile(&tagged("only", r#"["fb"]"#)).unwrap();
+        db.create_profile(&tagged("substring", r#"["fbads","myfb"]"#)).unwrap();
+        db.create_profile(&tagged("other-case", r#"["FB"]"#)).unwrap();
+        db.create_profile(&tagged("malformed", "fb,google")).unwrap();
+        db.create_profile(&tagged("nested", r#"[["fb"],{"fb":1}]"#)).unwrap();
+
+        assert_eq!(db.group_profiles_by_tag(" fb ", "fb").unwrap(), 2);
+        let mut moved: Vec<String> = db.get_profiles_by_group("fb").unwrap().into_iter().map(|p| p.id).collect();
+        moved.sort();
+        assert_eq!(moved, ids(&["multi", "only"]));
+        assert_eq!(db.get_profile("substring").unwrap().unwrap().group_id, "");
+
+        // Already grouped profiles aren't counted again
+        assert_eq!(db.group_profiles_by_tag("fb", "fb").unwrap(), 0);
+        assert_eq!(db.group_profiles_by_tag("tiktok", "").unwrap(), 1);
+        assert_eq!(db.get_profile("multi").unwrap().unwrap().group_id, "");
 
-    let data_dir = project_dirs.data_dir();
-    Ok(data_dir.join("mmo-express.db"))
+        assert!(matches!(db.group_profiles_by_tag("fb", "missing"), Err(AppError::NotFound(_))));
+        assert!(matches!(db.group_profiles_by_tag("  ", "fb"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn integrity_check_reports_a_truncated_file() {
+        let db = test_db();
+        let report = db.check_integrity().unwrap();
+        assert_eq!(report, IntegrityReport { ok: true, issues: vec![], guidance: None });
+
+        for i in 0..300 {
+            db.create_profile(&DbProfile { notes: "x".repeat(500), ..profile(&format!("p{}", i)) }).unwrap();
+        }
+        db.conn().unwrap().execute_batch("PRAGMA wal_checkpoint(TRUNCATE)").unwrap();
+        let damaged = db.dir.join("damaged.db");
+        std::fs::copy(db.path(), &damaged).unwrap();
+        assert_eq!(Database::check_file_integrity(&damaged, false).unwrap(), report);
+
+        // Drop the last few pages, where the newest profile rows live
+        let len = std::fs::metadata(&damaged).unwrap().len();
+        assert!(len > 100_000);
+        std::fs::OpenOptions::new().write(true).open(&damaged).unwrap().set_len(len - 4 * 4096).unwrap();
+
+        for quick in [true, false] {
+            let report = Database::check_file_integrity(&damaged, quick).unwrap();
+            assert!(!report.ok && !report.issues.is_empty(), "{:?}", report);
+            assert!(report.guidance.unwrap().contains("backup"));
+        }
+    }
+
+    /// Group "g" with two profiles on proxy "x1", one on a deleted proxy, plus unrelated rows
+    fn populate_bundle_group(db: &Database) {
+        db.create_group(&group("g")).unwrap();
+        db.create_proxy(&proxy("x1")).unwrap();
+        db.create_proxy(&proxy("unused")).unwrap();
+        for (id, proxy_id) in [("a", "x1"), ("b", "x1"), ("c", "gone")] {
+            db.create_profile(&DbProfile { group_id: "g".into(), proxy_id: proxy_id.into(), ..profile(id) }).unwrap();
+        }
+        db.create_profile(&profile("other")).unwrap();
+    }
+
+    #[test]
+    fn group_bundle_round_trips_into_another_database() {
+        let db = test_db();
+        let dest = db.dir.join("bundle.json");
+        assert!(matches!(db.export_group_bundle("g", &dest), Err(AppError::NotFound(_))));
+        populate_bundle_group(&db);
+
+        let bundle = db.export_group_bundle("g", &dest).unwrap();
+        assert_eq!(bundle.group.id, "g");
+        assert_eq!(bundle.proxies.iter().map(|p| p.id.as_str()).collect::<Vec<_>>(), ["x1"]);
+        assert_eq!(bundle.profiles.len(), 3);
+        assert!(bundle.profiles.iter().find(|p| p.id == "c").unwrap().proxy_id.is_empty());
+
+        let other = test_db();
+        let result = other.import_group_bundle(&dest, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.group_id.as_str(), result.group.inserted, result.proxies.inserted, result.profiles.inserted), ("g", 1, 1, 3));
+        let restored = other.get_profile("a").unwrap().unwrap();
+        assert_eq!((restored.group_id.as_str(), restored.proxy_id.as_str()), ("g", "x1"));
+        assert_eq!(other.get_proxy("x1").unwrap().unwrap().host, "1.2.3.4");
+        assert!(other.get_profile("other").unwrap().is_none());
+        assert!(other.find_orphaned_references().unwrap().is_empty());
+
+        // Same ids already present: skip leaves everything as is
+        let result = db.import_group_bundle(&dest, ImportStrategy::Skip).unwrap();
+        assert_eq!((result.group.skipped, result.proxies.skipped, result.profiles.skipped), (1, 1, 3));
+    }
+
+    #[test]
+    fn group_bundle_regenerated_ids_stay_consistent() {
+        let db = test_db();
+        populate_bundle_group(&db);
+        let dest = db.dir.join("bundle.json");
+        db.export_group_bundle("g", &dest).unwrap();
+
+        let result = db.import_group_bundle(&dest, ImportStrategy::RegenerateId).unwrap();
+        assert_ne!(result.group_id, "g");
+        assert_eq!(result.profiles.inserted, 3);
+        let copies = db.get_profiles_by_group(&result.group_id).unwrap();
+        assert_eq!(copies.len(), 3);
+        let new_proxy = &copies.iter().find(|p| !p.proxy_id.is_empty()).unwrap().proxy_id;
+        assert_ne!(new_proxy, "x1");
+        assert_eq!(db.get_proxy(new_proxy).unwrap().unwrap().host, "1.2.3.4");
+        assert_eq!(copies.iter().filter(|p| &p.proxy_id == new_proxy).count(), 2);
+        assert_eq!(db.get_profiles_by_group("g").unwrap().len(), 3);
+
+        std::fs::write(&dest, r#"{"format":"mmo-express-backup","schemaVersion":2}"#).unwrap();
+        assert!(matches!(db.import_group_bundle(&dest, ImportStrategy::Skip), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn bad_screen_geometry_only_warns_on_save() {
+        let db = test_db();
+        let mut oversize = DbProfile { viewport_width: 2560, screen_width: 1921, ..profile("p1") };
+        db.create_profile(&oversize).unwrap();
+        oversize.viewport_height = 4000;
+        db.update_profile(&oversize).unwrap();
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().viewport_height, 4000);
+    }
+
+    #[test]
+    fn valid_proxy_configs_pass() {
+        assert_eq!(validate_proxy_config(&proxy("x1")), Ok(()));
+        let with_auth = DbProxy { username: "user".into(), password: "pw".into(), proxy_type: "socks5".into(), ..proxy("x2") };
+        assert_eq!(validate_proxy_config(&with_auth), Ok(()));
+        assert_eq!(validate_proxy_config(&DbProxy { port: 65535, ..proxy("x3") }), Ok(()));
+    }
+
+    #[test]
+    fn each_proxy_config_failure_is_reported() {
+        let errors = |proxy: DbProxy| validate_proxy_config(&proxy).unwrap_err();
+
+        assert_eq!(errors(DbProxy { host: "  ".into(), ..proxy("x1") }), ["host is required"]);
+        assert_eq!(errors(DbProxy { port: 0, ..proxy("x1") }), ["port must be between 1 and 65535 (got 0)"]);
+        assert_eq!(errors(DbProxy { port: 65536, ..proxy("x1") }), ["port must be between 1 and 65535 (got 65536)"]);
+        let bad_type = errors(DbProxy { proxy_type: "ftp".into(), ..proxy("x1") });
+        assert_eq!(bad_type.len(), 1);
+        assert!(bad_type[0].contains("ftp"), "{:?}", bad_type);
+        assert_eq!(errors(DbProxy { username: "user".into(), ..proxy("x1") }), ["username and password must be given together"]);
+        assert_eq!(errors(DbProxy { password: "pw".into(), ..proxy("x1") }), ["username and password must be given together"]);
+
+        // Every problem at once
+        assert_eq!(errors(DbProxy { host: String::new(), port: -1, proxy_type: "ftp".into(), password: "pw".into(), ..proxy("x1") }).len(), 4);
+    }
+
+    #[test]
+    fn invalid_proxies_are_not_saved() {
+        let db = test_db();
+        let result = db.create_proxy(&DbProxy { port: 0, ..proxy("x1") });
+        assert!(matches!(result, Err(AppError::Validation(m)) if m.contains("port")));
+        assert!(db.get_proxy("x1").unwrap().is_none());
+
+        db.create_proxy(&proxy("x1")).unwrap();
+        assert!(matches!(db.update_proxy(&DbProxy { host: String::new(), ..proxy("x1") }), Err(AppError::Validation(_))));
+        assert_eq!(db.get_proxy("x1").unwrap().unwrap().host, "1.2.3.4");
+    }
+
+    #[test]
+    fn fingerprint_hash_ignores_non_fingerprint_fields() {
+        let db = test_db();
+        let created = db.create_profile(&profile("p1")).unwrap();
+        let hash = created.fingerprint_hash.clone();
+        assert_eq!(hash.len(), 64);
+        assert!(hash.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase()));
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().fingerprint_hash, hash);
+
+        db.update_profile(&DbProfile { notes: "renewed".into(), name: "renamed".into(), ..created.clone() }).unwrap();
+        db.patch_profile("p1", json!({ "status": "archived", "proxy_id": "x1" }).as_object().unwrap()).unwrap();
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().fingerprint_hash, hash);
+
+        db.patch_profile("p1", json!({ "canvas_noise": 0.05 }).as_object().unwrap()).unwrap();
+        let changed = db.get_profile("p1").unwrap().unwrap().fingerprint_hash;
+        assert_ne!(changed, hash);
+        db.patch_profile("p1", json!({ "canvas_noise": 0.02 }).as_object().unwrap()).unwrap();
+        assert_eq!(db.get_profile("p1").unwrap().unwrap().fingerprint_hash, hash, "the hash depends only on the values");
+    }
+
+    #[test]
+    fn profiles_are_found_by_fingerprint_hash() {
+        let db = test_db();
+        let hash = db.create_profile(&profile("a")).unwrap().fingerprint_hash;
+        db.create_profile(&DbProfile { created_at: "2025-01-01T00:00:00.000Z".into(), ..profile("b") }).unwrap();
+        db.create_profile(&DbProfile { cpu_cores: 2, ..profile("c") }).unwrap();
+
+        let found: Vec<String> = db.get_profiles_by_fingerprint_hash(&format!(" {} ", hash.to_ascii_uppercase())).unwrap()
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        assert_eq!(found, ids(&["a", "b"]));
+        assert!(db.get_profiles_by_fingerprint_hash("0000").unwrap().is_empty());
+    }
+
+    #[test]
+    fn profile_csv_quotes_commas_quotes_and_newlines() {
+        let db = test_db();
+        db.create_profile(&DbProfile { notes: r#"buy milk, eggs; said "hi""#.into(), ..profile("a") }).unwrap();
+        db.create_profile(&DbProfile {
+            notes: "line one\nline two".into(),
+            platform_tags: r#"["fb","google"]"#.into(),
+            created_at: "2024-02-01T00:00:00.000Z".into(),
+            ..profile("b")
+        }).unwrap();
+        let dest = db.dir.join("profiles.csv");
+
+        let columns = ids(&["id", "notes", "platform_tags", "canvas_noise"]);
+        assert_eq!(db.export_profiles_csv(&dest, Some(&columns)).unwrap(), 2);
+        assert_eq!(std::fs::read_to_string(&dest).unwrap(), concat!(
+            "id,notes,platform_tags,canvas_noise\r\n",
+            "a,\"buy milk, eggs; said \"\"hi\"\"\",[],0.02\r\n",
+            "b,\"line one\nline two\",\"[\"\"fb\"\",\"\"google\"\"]\",0.02\r\n",
+        ));
+    }
+
+    #[test]
+    fn profile_csv_checks_columns() {
+        let db = test_db();
+        db.create_profile(&profile("a")).unwrap();
+        let dest = db.dir.join("profiles.csv");
+
+        assert_eq!(db.export_profiles_csv(&dest, None).unwrap(), 1);
+        let contents = std::fs::read_to_string(&dest).unwrap();
+        let header = contents.lines().next().unwrap();
+        assert_eq!(header.split(',').count(), PROFILE_COLUMNS.split(',').count());
+        assert!(header.starts_with("id,name,browser_type,"));
+
+        let unknown = db.export_profiles_csv(&dest, Some(&ids(&["id", "password; DROP TABLE profiles"])));
+        assert!(matches!(unknown, Err(AppError::Validation(m)) if m.contains("password")));
+        assert!(matches!(db.export_profiles_csv(&dest, Some(&[])), Err(AppError::Validation(_))));
+        assert!(matches!(db.export_profiles_csv(&db.path(), None), Err(AppError::Validation(_))));
+    }
+
+    fn in_millis(ms: i64) -> String {
+        (chrono::Utc::now() + chrono::Duration::milliseconds(ms)).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+    }
+
+    #[test]
+    fn near_future_scheduled_run_fires_once() {
+        let db = test_db();
+        db.create_workflow(&workflow("w1")).unwrap();
+        let run = db.schedule_workflow_run("w1", &in_millis(300)).unwrap();
+        let later = db.schedule_workflow_run("w1", &in_millis(60_000)).unwrap();
+        assert_eq!(run.status, "pending");
+
+        assert!(db.take_due_scheduled_runs().unwrap().is_empty());
+        std::thread::sleep(std::time::Duration::from_millis(400));
+
+        let fired = db.take_due_scheduled_runs().unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!((fired[0].id.as_str(), fired[0].status.as_str()), (run.id.as_str(), "triggered"));
+        assert!(!fired[0].triggered_at.is_empty());
+        assert!(db.take_due_scheduled_runs().unwrap().is_empty());
+
+        let listed = db.list_scheduled_runs().unwrap();
+        let statuses: Vec<_> = listed.iter().map(|r| (r.id.as_str(), r.status.as_str())).collect();
+        assert_eq!(statuses, [(run.id.as_str(), "triggered"), (later.id.as_str(), "pending")]);
+        assert!(matches!(db.cancel_scheduled_run(&run.id), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn scheduled_runs_validate_cancel_and_reschedule() {
+        let db = test_db();
+        db.create_workflow(&workflow("w1")).unwrap();
+        assert!(matches!(db.schedule_workflow_run("w1", &in_millis(-1000)), Err(AppError::Validation(_))));
+        assert!(matches!(db.schedule_workflow_run("w1", "3am"), Err(AppError::Validation(_))));
+        assert!(matches!(db.schedule_workflow_run("missing", &in_millis(60_000)), Err(AppError::NotFound(_))));
+
+        let cancelled = db.schedule_workflow_run("w1", &in_millis(60_000)).unwrap();
+        db.cancel_scheduled_run(&cancelled.id).unwrap();
+        assert!(matches!(db.cancel_scheduled_run("missing"), Err(AppError::NotFound(_))));
+
+        // A run missed while the app was closed moves to the startup delay instead of firing at once
+        let missed = db.schedule_workflow_run("w1", &in_millis(200)).unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(300));
+        let startup_at = in_millis(60_000);
+        assert_eq!(db.reschedule_overdue_runs(&startup_at).unwrap(), 1);
+        assert!(db.take_due_scheduled_runs().unwrap().is_empty());
+
+        let listed = db.list_scheduled_runs().unwrap();
+        let missed = listed.iter().find(|r| r.id == missed.id).unwrap();
+        assert_eq!((missed.run_at.as_str(), missed.status.as_str()), (startup_at.as_str(), "pending"));
+        let cancelled = listed.iter().find(|r| r.id == cancelled.id).unwrap();
+        assert_eq!(cancelled.status, "cancelled");
+    }
+
+    #[test]
+    fn extensions_are_assigned_retrieved_and_unassigned() {
+        let db = test_db();
+        for id in ["p1", "p2", "p3"] {
+            db.create_profile(&profile(id)).unwrap();
+        }
+
+        assert_eq!(db.assign_extension_to_profiles("ublock", &ids(&["p1", "p2", "missing"])).unwrap(), 2);
+        assert_eq!(db.assign_extension_to_profiles("metamask", &ids(&["p1"])).unwrap(), 1);
+        assert_eq!(db.assign_extension_to_profiles("ublock", &[]).unwrap(), 0);
+        assert!(matches!(db.assign_extension_to_profiles("  ", &ids(&["p1"])), Err(AppError::Validation(_))));
+
+        // Both may share an assigned_at millisecond, so compare them sorted
+        let mut assigned: Vec<_> = db.get_profile_extensions("p1").unwrap().into_iter().map(|e| (e.extension_id, e.enabled)).collect();
+        assigned.sort();
+        assert_eq!(assigned, [("metamask".to_string(), true), ("ublock".to_string(), true)]);
+        assert!(db.get_profile_extensions("p3").unwrap().is_empty());
+
+        // Disabling keeps the assignment; assigning again re-enables it
+        db.set_profile_extension_enabled("p2", "ublock", false).unwrap();
+        assert!(!db.get_profile_extensions("p2").unwrap()[0].enabled);
+        assert_eq!(db.assign_extension_to_profiles("ublock", &ids(&["p2"])).unwrap(), 1);
+        assert!(db.get_profile_extensions("p2").unwrap()[0].enabled);
+        assert!(matches!(db.set_profile_extension_enabled("p3", "ublock", true), Err(AppError::NotFound(_))));
+
+        assert!(matches!(db.unassign_extension_from_profiles(" ", &ids(&["p1"])), Err(AppError::Validation(_))));
+        assert_eq!(db.unassign_extension_from_profiles(" ublock ", &ids(&["p1", "p2", "p3"])).unwrap(), 2);
+        let left: Vec<_> = db.get_profile_extensions("p1").unwrap().into_iter().map(|e| e.extension_id).collect();
+        assert_eq!(left, ["metamask"]);
+        assert!(db.get_profile_extensions("p2").unwrap().is_empty());
+    }
 }
@@ -0,0 +1,64 @@
+use serde::Serialize;
+use std::fmt;
+
+// ============ Error Type ============
+
+/// Error returned by every command. Serialized as `{ kind, message }` so the
+/// frontend can switch on `kind` instead of matching message text.
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", content = "message", rename_all = "camelCase")]
+pub enum AppError {
+    NotFound(String),
+    Validation(String),
+    Db(String),
+    Sidecar(String),
+    Io(String),
+    Timeout(String),
+    RateLimited(String),
+}
+
+impl AppError {
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::NotFound(m)
+            | AppError::Validation(m)
+            | AppError::Db(m)
+            | AppError::Sidecar(m)
+            | AppError::Io(m)
+            | AppError::Timeout(m)
+            | AppError::RateLimited(m) => m,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        AppError::Db(e.to_string())
+    }
+}
+
+impl From<r2d2::Error> for AppError {
+    fn from(e: r2d2::Error) -> Self {
+        AppError::Db(e.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::Validation(e.to_string())
+    }
+}
@@ -0,0 +1,237 @@
+use serde::Serialize;
+
+use crate::database::DbProfile;
+use crate::lint;
+
+// ============ Types ============
+
+/// How identifiable a profile is. Each scored attribute contributes `-log2(share of browsers
+/// with that value)` bits; attributes are treated as independent, so the total is an upper
+/// bound on what a tracker learns but stable and easy to explain.
+#[derive(Debug, Serialize, Clone)]
+pub struct FingerprintScore {
+    #[serde(rename = "bitsOfEntropy")]
+    pub bits_of_entropy: f64,
+    #[serde(rename = "rarityBucket")]
+    pub rarity_bucket: RarityBucket,
+    /// The attributes contributing most, highest first
+    #[serde(rename = "rarestFields")]
+    pub rarest_fields: Vec<FieldScore>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RarityBucket {
+    Common,
+    Uncommon,
+    Rare,
+    Unique,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FieldScore {
+    /// Profile field (frontend name) to change to lower the score
+    pub field: String,
+    /// The value as it was scored, e.g. "1920x1080" or "chrome on windows"
+    pub value: String,
+    /// Share of browsers reporting this value, 0.0 to 1.0
+    pub frequency: f64,
+    pub bits: f64,
+}
+
+/// One field that differs between two profiles, with the frontend field name
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct FieldDiff {
+    pub field: String,
+    pub left: serde_json::Value,
+    pub right: serde_json::Value,
+}
+
+// ============ Frequency Tables ============
+
+// Coarse shares of desktop and mobile browsers, rounded from public browser statistics.
+// Values not listed get `OTHER`.
+
+/// Share assumed for values missing from a table
+const OTHER: f64 = 0.002;
+
+const SCREEN_RESOLUTIONS: &[((i32, i32), f64)] = &[
+    ((1920, 1080), 0.22), ((1536, 864), 0.08), ((1366, 768), 0.07), ((390, 844), 0.05),
+    ((2560, 1440), 0.04), ((1440, 900), 0.03), ((360, 800), 0.03), ((1280, 720), 0.02),
+    ((414, 896), 0.02), ((412, 915), 0.02), ((1600, 900), 0.02), ((1280, 800), 0.02),
+    ((393, 873), 0.02), ((3840, 2160), 0.015), ((1680, 1050), 0.01), ((2560, 1600), 0.01),
+];
+
+/// (browser, OS) as recognized from the user agent
+const BROWSER_OS: &[((&str, &str), f64)] = &[
+    (("chrome", "windows"), 0.42), (("chrome", "android"), 0.17), (("safari", "ios"), 0.12),
+    (("chrome", "macos"), 0.07), (("edge", "windows"), 0.06), (("safari", "macos"), 0.04),
+    (("firefox", "windows"), 0.03), (("chrome", "linux"), 0.015), (("chrome", "ios"), 0.01),
+    (("firefox", "macos"), 0.005), (("firefox", "linux"), 0.005), (("edge", "macos"), 0.005),
+];
+
+/// `navigator.hardwareConcurrency`
+const CPU_CORES: &[(i32, f64)] = &[
+    (8, 0.30), (4, 0.24), (12, 0.11), (16, 0.10), (6, 0.09), (2, 0.05), (10, 0.03),
+    (20, 0.02), (24, 0.015), (14, 0.01), (32, 0.004),
+];
+
+/// `navigator.deviceMemory`, which browsers cap at 8 (so higher values stand out)
+const DEVICE_MEMORY: &[(i32, f64)] = &[(8, 0.62), (4, 0.22), (2, 0.08), (1, 0.01)];
+
+const PIXEL_RATIOS: &[(f64, f64)] = &[
+    (1.0, 0.45), (2.0, 0.22), (1.25, 0.10), (1.5, 0.08), (3.0, 0.08), (2.625, 0.02), (2.75, 0.02),
+    (1.75, 0.01),
+];
+
+const MAX_TOUCH_POINTS: &[(i32, f64)] = &[(0, 0.68), (5, 0.20), (10, 0.10), (1, 0.01)];
+
+/// Vendor in the WebGL vendor string's parentheses, e.g. "Google Inc. (NVIDIA)"
+const WEBGL_VENDORS: &[(&str, f64)] = &[
+    ("Intel", 0.36), ("NVIDIA", 0.28), ("Apple", 0.16), ("AMD", 0.11), ("Qualcomm", 0.04),
+    ("ARM", 0.03),
+];
+
+/// Number of fonts reported, as (up to, share)
+const FONT_COUNTS: &[(usize, f64)] = &[(9, 0.02), (29, 0.25), (59, 0.35), (119, 0.28), (usize::MAX, 0.10)];
+
+/// Fields returned in `rarest_fields`
+const RAREST_FIELD_COUNT: usize = 3;
+
+/// Fields that differ between any two profiles or are derived from other fields, skipped by
+/// `diff_profiles` unless asked for
+const PROFILE_METADATA_FIELDS: &[&str] = &["id", "createdAt", "updatedAt", "lastUsedAt", "fingerprintHash"];
+
+// ============ Scoring ============
+
+/// Score how much a profile's attributes narrow it down among real browsers. Attributes the
+/// profile leaves to the real browser (e.g. an empty font list) are not scored.
+pub fn score_fingerprint(profile: &DbProfile) -> FingerprintScore {
+    let mut fields = Vec::new();
+    let mut score = |field: &str, value: String, frequency: f64| {
+        fields.push(FieldScore {
+            field: field.to_string(),
+            value,
+            frequency,
+            bits: round2(-frequency.log2()),
+        });
+    };
+
+    let resolution = (profile.screen_width, profile.screen_height);
+    score("screenWidth", format!("{}x{}", resolution.0, resolution.1), lookup(SCREEN_RESOLUTIONS, &resolution));
+
+    let ua_browser = lint::browser_from_user_agent(&profile.user_agent).unwrap_or("unknown");
+    let ua_os = lint::os_from_user_agent(&profile.user_agent).unwrap_or("unknown");
+    score("userAgent", format!("{} on {}", ua_browser, ua_os), lookup(BROWSER_OS, &(ua_browser, ua_os)));
+
+    score("cpuCores", profile.cpu_cores.to_string(), lookup(CPU_CORES, &profile.cpu_cores));
+    score("deviceMemory", profile.device_memory.to_string(), lookup(DEVICE_MEMORY, &profile.device_memory));
+    score("pixelRatio", profile.pixel_ratio.to_string(), lookup(PIXEL_RATIOS, &profile.pixel_ratio));
+    score("maxTouchPoints", profile.max_touch_points.to_string(), lookup(MAX_TOUCH_POINTS, &profile.max_touch_points));
+
+    if let Some(vendor) = lint::vendor_in_parens(&profile.webgl_vendor) {
+        let frequency = WEBGL_VENDORS.iter()
+            .find(|(known, _)| vendor.eq_ignore_ascii_case(known))
+            .map_or(OTHER, |(_, frequency)| *frequency);
+        score("webglVendor", vendor.to_string(), frequency);
+    }
+
+    let font_count = serde_json::from_str::<Vec<serde_json::Value>>(&profile.fonts).map_or(0, |fonts| fonts.len());
+    if font_count > 0 {
+        let frequency = FONT_COUNTS.iter()
+            .find(|(up_to, _)| font_count <= *up_to)
+            .map_or(OTHER, |(_, frequency)| *frequency);
+        score("fonts", format!("{} fonts", font_count), frequency);
+    }
+
+    let bits_of_entropy = round2(fields.iter().map(|f| f.bits).sum());
+    fields.sort_by(|a, b| b.bits.total_cmp(&a.bits).then_with(|| a.field.cmp(&b.field)));
+    fields.truncate(RAREST_FIELD_COUNT);
+
+    FingerprintScore {
+        bits_of_entropy,
+        rarity_bucket: bucket(bits_of_entropy),
+        rarest_fields: fields,
+    }
+}
+
+// ============ Comparison ============
+
+/// Every field whose value differs between the two profiles, in field name order. The id,
+/// timestamps and fingerprint hash are left out unless `include_metadata` is set.
+pub fn diff_profiles(left: &DbProfile, right: &DbProfile, include_metadata: bool) -> Vec<FieldDiff> {
+    let (Ok(serde_json::Value::Object(left)), Ok(serde_json::Value::Object(mut right))) =
+        (serde_json::to_value(left), serde_json::to_value(right))
+    else {
+        return Vec::new();
+    };
+
+    let mut diffs: Vec<FieldDiff> = left
+        .into_iter()
+        .filter(|(field, _)| include_metadata || !PROFILE_METADATA_FIELDS.contains(&field.as_str()))
+        .filter_map(|(field, left)| {
+            let right = right.remove(&field).unwrap_or(serde_json::Value::Null);
+            (left != right).then_some(FieldDiff { field, left, right })
+        })
+        .collect();
+    diffs.sort_by(|a, b| a.field.cmp(&b.field));
+    diffs
+}
+
+fn lookup<K: PartialEq>(table: &[(K, f64)], key: &K) -> f64 {
+    table.iter().find(|(k, _)| k == key).map_or(OTHER, |(_, frequency)| *frequency)
+}
+
+/// A typical desktop profile scores about 10 bits; about 33 bits singles out one person on earth
+fn bucket(bits: f64) -> RarityBucket {
+    match bits {
+        b if b < 14.0 => RarityBucket::Common,
+        b if b < 20.0 => RarityBucket::Uncommon,
+        b if b < 28.0 => RarityBucket::Rare,
+        _ => RarityBucket::Unique,
+    }
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::tests::profile;
+    use serde_json::json;
+
+    #[test]
+    fn diff_reports_exactly_the_differing_fields() {
+        let left = profile("left");
+        let right = DbProfile {
+            name: "left".into(),
+            timezone: "Europe/Berlin".into(),
+            cpu_cores: 4,
+            block_images: true,
+            created_at: "2025-06-01T00:00:00.000Z".into(),
+            ..profile("right")
+        };
+
+        let diffs = diff_profiles(&left, &right, false);
+        let diffs: Vec<(&str, &serde_json::Value, &serde_json::Value)> =
+            diffs.iter().map(|d| (d.field.as_str(), &d.left, &d.right)).collect();
+        assert_eq!(diffs, [
+            ("blockImages", &json!(false), &json!(true)),
+            ("cpuCores", &json!(8), &json!(4)),
+            ("timezone", &json!("America/New_York"), &json!("Europe/Berlin")),
+        ]);
+    }
+
+    #[test]
+    fn diff_includes_metadata_only_when_asked() {
+        let left = profile("left");
+        let right = DbProfile { name: "left".into(), updated_at: "2025-06-01T00:00:00.000Z".into(), ..profile("right") };
+        assert!(diff_profiles(&left, &right, false).is_empty());
+        assert!(diff_profiles(&left, &left, true).is_empty());
+
+        let fields: Vec<String> = diff_profiles(&left, &right, true).into_iter().map(|d| d.field).collect();
+        assert_eq!(fields, ["id", "updatedAt"]);
+    }
+}
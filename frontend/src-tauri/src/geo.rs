@@ -0,0 +1,400 @@
+use serde::Serialize;
+
+use crate::error::AppError;
+
+// ============ Types ============
+
+#[derive(Debug, Serialize, Clone)]
+pub struct GeoSuggestion {
+    pub country: String,
+    pub timezone: String,
+    pub locale: String,
+    pub language: String,
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+/// Result of `check_timezone`
+#[derive(Debug, Serialize, Clone, PartialEq, Eq)]
+pub struct TimezoneCheck {
+    pub valid: bool,
+    /// Standard (non-DST) offset from UTC
+    #[serde(rename = "utcOffset")]
+    pub utc_offset: Option<String>,
+    /// Offset while daylight saving time is in effect, None if the zone has none
+    #[serde(rename = "dstUtcOffset")]
+    pub dst_utc_offset: Option<String>,
+}
+
+struct CountryGeo {
+    code: &'static str,
+    timezone: &'static str,
+    locale: &'static str,
+    language: &'static str,
+    latitude: f64,
+    longitude: f64,
+}
+
+// ============ Country Table ============
+
+// Mirrors sidecar/geo/countries.js (default timezone per country), with
+// coordinates of the city that timezone is named after (or the capital).
+const COUNTRIES: &[CountryGeo] = &[
+    // North America
+    CountryGeo { code: "US", timezone: "America/New_York", locale: "en-US", language: "en-US,en", latitude: 40.7128, longitude: -74.0060 },
+    CountryGeo { code: "CA", timezone: "America/Toronto", locale: "en-CA", language: "en-CA,en,fr-CA,fr", latitude: 43.6532, longitude: -79.3832 },
+    CountryGeo { code: "MX", timezone: "America/Mexico_City", locale: "es-MX", language: "es-MX,es,en", latitude: 19.4326, longitude: -99.1332 },
+
+    // Europe
+    CountryGeo { code: "GB", timezone: "Europe/London", locale: "en-GB", language: "en-GB,en", latitude: 51.5074, longitude: -0.1278 },
+    CountryGeo { code: "DE", timezone: "Europe/Berlin", locale: "de-DE", language: "de-DE,de,en", latitude: 52.5200, longitude: 13.4050 },
+    CountryGeo { code: "FR", timezone: "Europe/Paris", locale: "fr-FR", language: "fr-FR,fr,en", latitude: 48.8566, longitude: 2.3522 },
+    CountryGeo { code: "IT", timezone: "Europe/Rome", locale: "it-IT", language: "it-IT,it,en", latitude: 41.9028, longitude: 12.4964 },
+    CountryGeo { code: "ES", timezone: "Europe/Madrid", locale: "es-ES", language: "es-ES,es,en", latitude: 40.4168, longitude: -3.7038 },
+    CountryGeo { code: "PT", timezone: "Europe/Lisbon", locale: "pt-PT", language: "pt-PT,pt,en", latitude: 38.7223, longitude: -9.1393 },
+    CountryGeo { code: "NL", timezone: "Europe/Amsterdam", locale: "nl-NL", language: "nl-NL,nl,en", latitude: 52.3676, longitude: 4.9041 },
+    CountryGeo { code: "BE", timezone: "Europe/Brussels", locale: "nl-BE", language: "nl-BE,nl,fr-BE,fr,en", latitude: 50.8503, longitude: 4.3517 },
+    CountryGeo { code: "CH", timezone: "Europe/Zurich", locale: "de-CH", language: "de-CH,de,fr-CH,fr,it-CH,it,en", latitude: 47.3769, longitude: 8.5417 },
+    CountryGeo { code: "AT", timezone: "Europe/Vienna", locale: "de-AT", language: "de-AT,de,en", latitude: 48.2082, longitude: 16.3738 },
+    CountryGeo { code: "PL", timezone: "Europe/Warsaw", locale: "pl-PL", language: "pl-PL,pl,en", latitude: 52.2297, longitude: 21.0122 },
+    CountryGeo { code: "SE", timezone: "Europe/Stockholm", locale: "sv-SE", language: "sv-SE,sv,en", latitude: 59.3293, longitude: 18.0686 },
+    CountryGeo { code: "NO", timezone: "Europe/Oslo", locale: "nb-NO", language: "nb-NO,no,en", latitude: 59.9139, longitude: 10.7522 },
+    CountryGeo { code: "DK", timezone: "Europe/Copenhagen", locale: "da-DK", language: "da-DK,da,en", latitude: 55.6761, longitude: 12.5683 },
+    CountryGeo { code: "FI", timezone: "Europe/Helsinki", locale: "fi-FI", language: "fi-FI,fi,sv,en", latitude: 60.1699, longitude: 24.9384 },
+    CountryGeo { code: "IE", timezone: "Europe/Dublin", locale: "en-IE", language: "en-IE,en,ga", latitude: 53.3498, longitude: -6.2603 },
+    CountryGeo { code: "CZ", timezone: "Europe/Prague", locale: "cs-CZ", language: "cs-CZ,cs,en", latitude: 50.0755, longitude: 14.4378 },
+    CountryGeo { code: "RO", timezone: "Europe/Bucharest", locale: "ro-RO", language: "ro-RO,ro,en", latitude: 44.4268, longitude: 26.1025 },
+    CountryGeo { code: "HU", timezone: "Europe/Budapest", locale: "hu-HU", language: "hu-HU,hu,en", latitude: 47.4979, longitude: 19.0402 },
+    CountryGeo { code: "GR", timezone: "Europe/Athens", locale: "el-GR", language: "el-GR,el,en", latitude: 37.9838, longitude: 23.7275 },
+    CountryGeo { code: "UA", timezone: "Europe/Kyiv", locale: "uk-UA", language: "uk-UA,uk,ru,en", latitude: 50.4501, longitude: 30.5234 },
+    CountryGeo { code: "RU", timezone: "Europe/Moscow", locale: "ru-RU", language: "ru-RU,ru,en", latitude: 55.7558, longitude: 37.6173 },
+    CountryGeo { code: "TR", timezone: "Europe/Istanbul", locale: "tr-TR", language: "tr-TR,tr,en", latitude: 41.0082, longitude: 28.9784 },
+
+    // Asia
+    CountryGeo { code: "JP", timezone: "Asia/Tokyo", locale: "ja-JP", language: "ja-JP,ja,en", latitude: 35.6762, longitude: 139.6503 },
+    CountryGeo { code: "KR", timezone: "Asia/Seoul", locale: "ko-KR", language: "ko-KR,ko,en", latitude: 37.5665, longitude: 126.9780 },
+    CountryGeo { code: "CN", timezone: "Asia/Shanghai", locale: "zh-CN", language: "zh-CN,zh,en", latitude: 31.2304, longitude: 121.4737 },
+    CountryGeo { code: "TW", timezone: "Asia/Taipei", locale: "zh-TW", language: "zh-TW,zh,en", latitude: 25.0330, longitude: 121.5654 },
+    CountryGeo { code: "HK", timezone: "Asia/Hong_Kong", locale: "zh-HK", language: "zh-HK,zh,en", latitude: 22.3193, longitude: 114.1694 },
+    CountryGeo { code: "SG", timezone: "Asia/Singapore", locale: "en-SG", language: "en-SG,en,zh,ms,ta", latitude: 1.3521, longitude: 103.8198 },
+    CountryGeo { code: "MY", timezone: "Asia/Kuala_Lumpur", locale: "ms-MY", language: "ms-MY,ms,en,zh", latitude: 3.1390, longitude: 101.6869 },
+    CountryGeo { code: "TH", timezone: "Asia/Bangkok", locale: "th-TH", language: "th-TH,th,en", latitude: 13.7563, longitude: 100.5018 },
+    CountryGeo { code: "VN", timezone: "Asia/Ho_Chi_Minh", locale: "vi-VN", language: "vi-VN,vi,en", latitude: 10.8231, longitude: 106.6297 },
+    CountryGeo { code: "ID", timezone: "Asia/Jakarta", locale: "id-ID", language: "id-ID,id,en", latitude: -6.2088, longitude: 106.8456 },
+    CountryGeo { code: "PH", timezone: "Asia/Manila", locale: "en-PH", language: "en-PH,en,tl", latitude: 14.5995, longitude: 120.9842 },
+    CountryGeo { code: "IN", timezone: "Asia/Kolkata", locale: "en-IN", language: "en-IN,en,hi", latitude: 28.6139, longitude: 77.2090 },
+    CountryGeo { code: "PK", timezone: "Asia/Karachi", locale: "ur-PK", language: "ur-PK,ur,en", latitude: 24.8607, longitude: 67.0011 },
+    CountryGeo { code: "BD", timezone: "Asia/Dhaka", locale: "bn-BD", language: "bn-BD,bn,en", latitude: 23.8103, longitude: 90.4125 },
+    CountryGeo { code: "AE", timezone: "Asia/Dubai", locale: "ar-AE", language: "ar-AE,ar,en", latitude: 25.2048, longitude: 55.2708 },
+    CountryGeo { code: "SA", timezone: "Asia/Riyadh", locale: "ar-SA", language: "ar-SA,ar,en", latitude: 24.7136, longitude: 46.6753 },
+    CountryGeo { code: "IL", timezone: "Asia/Jerusalem", locale: "he-IL", language: "he-IL,he,en,ar", latitude: 31.7683, longitude: 35.2137 },
+
+    // Oceania
+    CountryGeo { code: "AU", timezone: "Australia/Sydney", locale: "en-AU", language: "en-AU,en", latitude: -33.8688, longitude: 151.2093 },
+    CountryGeo { code: "NZ", timezone: "Pacific/Auckland", locale: "en-NZ", language: "en-NZ,en,mi", latitude: -36.8485, longitude: 174.7633 },
+
+    // South America
+    CountryGeo { code: "BR", timezone: "America/Sao_Paulo", locale: "pt-BR", language: "pt-BR,pt,en", latitude: -23.5505, longitude: -46.6333 },
+    CountryGeo { code: "AR", timezone: "America/Argentina/Buenos_Aires", locale: "es-AR", language: "es-AR,es,en", latitude: -34.6037, longitude: -58.3816 },
+    CountryGeo { code: "CL", timezone: "America/Santiago", locale: "es-CL", language: "es-CL,es,en", latitude: -33.4489, longitude: -70.6693 },
+    CountryGeo { code: "CO", timezone: "America/Bogota", locale: "es-CO", language: "es-CO,es,en", latitude: 4.7110, longitude: -74.0721 },
+    CountryGeo { code: "PE", timezone: "America/Lima", locale: "es-PE", language: "es-PE,es,en", latitude: -12.0464, longitude: -77.0428 },
+
+    // Africa
+    CountryGeo { code: "ZA", timezone: "Africa/Johannesburg", locale: "en-ZA", language: "en-ZA,en,af,zu", latitude: -26.2041, longitude: 28.0473 },
+    CountryGeo { code: "EG", timezone: "Africa/Cairo", locale: "ar-EG", language: "ar-EG,ar,en", latitude: 30.0444, longitude: 31.2357 },
+    CountryGeo { code: "NG", timezone: "Africa/Lagos", locale: "en-NG", language: "en-NG,en", latitude: 6.5244, longitude: 3.3792 },
+    CountryGeo { code: "KE", timezone: "Africa/Nairobi", locale: "en-KE", language: "en-KE,en,sw", latitude: -1.2921, longitude: 36.8219 },
+    CountryGeo { code: "MA", timezone: "Africa/Casablanca", locale: "ar-MA", language: "ar-MA,ar,fr,en", latitude: 33.5731, longitude: -7.5898 },
+];
+
+// ============ Timezones and Country Codes ============
+
+/// Canonical zones from the IANA tz database's zone.tab, plus UTC, sorted for binary search,
+/// with their standard and daylight-saving UTC offsets in minutes under the 2025b rules (the
+/// two are equal where there is no DST; Dublin's and Casablanca's reversed DST is normalized
+/// so the daylight offset is the larger one). Backward-compatible aliases such as "Asia/Calcutta"
+/// are left out on purpose.
+const TIMEZONES: &[(&str, i32, i32)] = &[
+    ("Africa/Abidjan", 0, 0), ("Africa/Accra", 0, 0), ("Africa/Addis_Ababa", 180, 180),
+    ("Africa/Algiers", 60, 60), ("Africa/Asmara", 180, 180), ("Africa/Bamako", 0, 0),
+    ("Africa/Bangui", 60, 60), ("Africa/Banjul", 0, 0), ("Africa/Bissau", 0, 0),
+    ("Africa/Blantyre", 120, 120), ("Africa/Brazzaville", 60, 60), ("Africa/Bujumbura", 120, 120),
+    ("Africa/Cairo", 120, 180), ("Africa/Casablanca", 0, 60), ("Africa/Ceuta", 60, 120),
+    ("Africa/Conakry", 0, 0), ("Africa/Dakar", 0, 0), ("Africa/Dar_es_Salaam", 180, 180),
+    ("Africa/Djibouti", 180, 180), ("Africa/Douala", 60, 60), ("Africa/El_Aaiun", 0, 60),
+    ("Africa/Freetown", 0, 0), ("Africa/Gaborone", 120, 120), ("Africa/Harare", 120, 120),
+    ("Africa/Johannesburg", 120, 120), ("Africa/Juba", 120, 120), ("Africa/Kampala", 180, 180),
+    ("Africa/Khartoum", 120, 120), ("Africa/Kigali", 120, 120), ("Africa/Kinshasa", 60, 60),
+    ("Africa/Lagos", 60, 60), ("Africa/Libreville", 60, 60), ("Africa/Lome", 0, 0),
+    ("Africa/Luanda", 60, 60), ("Africa/Lubumbashi", 120, 120), ("Africa/Lusaka", 120, 120),
+    ("Africa/Malabo", 60, 60), ("Africa/Maputo", 120, 120), ("Africa/Maseru", 120, 120),
+    ("Africa/Mbabane", 120, 120), ("Africa/Mogadishu", 180, 180), ("Africa/Monrovia", 0, 0),
+    ("Africa/Nairobi", 180, 180), ("Africa/Ndjamena", 60, 60), ("Africa/Niamey", 60, 60),
+    ("Africa/Nouakchott", 0, 0), ("Africa/Ouagadougou", 0, 0), ("Africa/Porto-Novo", 60, 60),
+    ("Africa/Sao_Tome", 0, 0), ("Africa/Tripoli", 120, 120), ("Africa/Tunis", 60, 60),
+    ("Africa/Windhoek", 120, 120), ("America/Adak", -600, -540), ("America/Anchorage", -540, -480),
+    ("America/Anguilla", -240, -240), ("America/Antigua", -240, -240),
+    ("America/Araguaina", -180, -180), ("America/Argentina/Buenos_Aires", -180, -180),
+    ("America/Argentina/Catamarca", -180, -180), ("America/Argentina/Cordoba", -180, -180),
+    ("America/Argentina/Jujuy", -180, -180), ("America/Argentina/La_Rioja", -180, -180),
+    ("America/Argentina/Mendoza", -180, -180), ("America/Argentina/Rio_Gallegos", -180, -180),
+    ("America/Argentina/Salta", -180, -180), ("America/Argentina/San_Juan", -180, -180),
+    ("America/Argentina/San_Luis", -180, -180), ("America/Argentina/Tucuman", -180, -180),
+    ("America/Argentina/Ushuaia", -180, -180), ("America/Aruba", -240, -240),
+    ("America/Asuncion", -180, -180), ("America/Atikokan", -300, -300),
+    ("America/Bahia", -180, -180), ("America/Bahia_Banderas", -360, -360),
+    ("America/Barbados", -240, -240), ("America/Belem", -180, -180), ("America/Belize", -360, -360),
+    ("America/Blanc-Sablon", -240, -240), ("America/Boa_Vista", -240, -240),
+    ("America/Bogota", -300, -300), ("America/Boise", -420, -360),
+    ("America/Cambridge_Bay", -420, -360), ("America/Campo_Grande", -240, -240),
+    ("America/Cancun", -300, -300), ("America/Caracas", -240, -240),
+    ("America/Cayenne", -180, -180), ("America/Cayman", -300, -300),
+    ("America/Chicago", -360, -300), ("America/Chihuahua", -360, -360),
+    ("America/Ciudad_Juarez", -420, -360), ("America/Costa_Rica", -360, -360),
+    ("America/Coyhaique", -180, -180), ("America/Creston", -420, -420),
+    ("America/Cuiaba", -240, -240), ("America/Curacao", -240, -240), ("America/Danmarkshavn", 0, 0),
+    ("America/Dawson", -420, -420), ("America/Dawson_Creek", -420, -420),
+    ("America/Denver", -420, -360), ("America/Detroit", -300, -240),
+    ("America/Dominica", -240, -240), ("America/Edmonton", -420, -360),
+    ("America/Eirunepe", -300, -300), ("America/El_Salvador", -360, -360),
+    ("America/Fort_Nelson", -420, -420), ("America/Fortaleza", -180, -180),
+    ("America/Glace_Bay", -240, -180), ("America/Goose_Bay", -240, -180),
+    ("America/Grand_Turk", -300, -240), ("America/Grenada", -240, -240),
+    ("America/Guadeloupe", -240, -240), ("America/Guatemala", -360, -360),
+    ("America/Guayaquil", -300, -300), ("America/Guyana", -240, -240),
+    ("America/Halifax", -240, -180), ("America/Havana", -300, -240),
+    ("America/Hermosillo", -420, -420), ("America/Indiana/Indianapolis", -300, -240),
+    ("America/Indiana/Knox", -360, -300), ("America/Indiana/Marengo", -300, -240),
+    ("America/Indiana/Petersburg", -300, -240), ("America/Indiana/Tell_City", -360, -300),
+    ("America/Indiana/Vevay", -300, -240), ("America/Indiana/Vincennes", -300, -240),
+    ("America/Indiana/Winamac", -300, -240), ("America/Inuvik", -420, -360),
+    ("America/Iqaluit", -300, -240), ("America/Jamaica", -300, -300),
+    ("America/Juneau", -540, -480), ("America/Kentucky/Louisville", -300, -240),
+    ("America/Kentucky/Monticello", -300, -240), ("America/Kralendijk", -240, -240),
+    ("America/La_Paz", -240, -240), ("America/Lima", -300, -300),
+    ("America/Los_Angeles", -480, -420), ("America/Lower_Princes", -240, -240),
+    ("America/Maceio", -180, -180), ("America/Managua", -360, -360), ("America/Manaus", -240, -240),
+    ("America/Marigot", -240, -240), ("America/Martinique", -240, -240),
+    ("America/Matamoros", -360, -300), ("America/Mazatlan", -420, -420),
+    ("America/Menominee", -360, -300), ("America/Merida", -360, -360),
+    ("America/Metlakatla", -540, -480), ("America/Mexico_City", -360, -360),
+    ("America/Miquelon", -180, -120), ("America/Moncton", -240, -180),
+    ("America/Monterrey", -360, -360), ("America/Montevideo", -180, -180),
+    ("America/Montserrat", -240, -240), ("America/Nassau", -300, -240),
+    ("America/New_York", -300, -240), ("America/Nome", -540, -480), ("America/Noronha", -120, -120),
+    ("America/North_Dakota/Beulah", -360, -300), ("America/North_Dakota/Center", -360, -300),
+    ("America/North_Dakota/New_Salem", -360, -300), ("America/Nuuk", -120, -60),
+    ("America/Ojinaga", -360, -300), ("America/Panama", -300, -300),
+    ("America/Paramaribo", -180, -180), ("America/Phoenix", -420, -420),
+    ("America/Port-au-Prince", -300, -240), ("America/Port_of_Spain", -240, -240),
+    ("America/Porto_Velho", -240, -240), ("America/Puerto_Rico", -240, -240),
+    ("America/Punta_Arenas", -180, -180), ("America/Rankin_Inlet", -360, -300),
+    ("America/Recife", -180, -180), ("America/Regina", -360, -360),
+    ("America/Resolute", -360, -300), ("America/Rio_Branco", -300, -300),
+    ("America/Santarem", -180, -180), ("America/Santiago", -240, -180),
+    ("America/Santo_Domingo", -240, -240), ("America/Sao_Paulo", -180, -180),
+    ("America/Scoresbysund", -120, -60), ("America/Sitka", -540, -480),
+    ("America/St_Barthelemy", -240, -240), ("America/St_Johns", -210, -150),
+    ("America/St_Kitts", -240, -240), ("America/St_Lucia", -240, -240),
+    ("America/St_Thomas", -240, -240), ("America/St_Vincent", -240, -240),
+    ("America/Swift_Current", -360, -360), ("America/Tegucigalpa", -360, -360),
+    ("America/Thule", -240, -180), ("America/Tijuana", -480, -420), ("America/Toronto", -300, -240),
+    ("America/Tortola", -240, -240), ("America/Vancouver", -480, -420),
+    ("America/Whitehorse", -420, -420), ("America/Winnipeg", -360, -300),
+    ("America/Yakutat", -540, -480), ("Antarctica/Casey", 480, 480), ("Antarctica/Davis", 420, 420),
+    ("Antarctica/DumontDUrville", 600, 600), ("Antarctica/Macquarie", 600, 660),
+    ("Antarctica/Mawson", 300, 300), ("Antarctica/McMurdo", 720, 780),
+    ("Antarctica/Palmer", -180, -180), ("Antarctica/Rothera", -180, -180),
+    ("Antarctica/Syowa", 180, 180), ("Antarctica/Troll", 0, 120), ("Antarctica/Vostok", 300, 300),
+    ("Arctic/Longyearbyen", 60, 120), ("Asia/Aden", 180, 180), ("Asia/Almaty", 300, 300),
+    ("Asia/Amman", 180, 180), ("Asia/Anadyr", 720, 720), ("Asia/Aqtau", 300, 300),
+    ("Asia/Aqtobe", 300, 300), ("Asia/Ashgabat", 300, 300), ("Asia/Atyrau", 300, 300),
+    ("Asia/Baghdad", 180, 180), ("Asia/Bahrain", 180, 180), ("Asia/Baku", 240, 240),
+    ("Asia/Bangkok", 420, 420), ("Asia/Barnaul", 420, 420), ("Asia/Beirut", 120, 180),
+    ("Asia/Bishkek", 360, 360), ("Asia/Brunei", 480, 480), ("Asia/Chita", 540, 540),
+    ("Asia/Colombo", 330, 330), ("Asia/Damascus", 180, 180), ("Asia/Dhaka", 360, 360),
+    ("Asia/Dili", 540, 540), ("Asia/Dubai", 240, 240), ("Asia/Dushanbe", 300, 300),
+    ("Asia/Famagusta", 120, 180), ("Asia/Gaza", 120, 180), ("Asia/Hebron", 120, 180),
+    ("Asia/Ho_Chi_Minh", 420, 420), ("Asia/Hong_Kong", 480, 480), ("Asia/Hovd", 420, 420),
+    ("Asia/Irkutsk", 480, 480), ("Asia/Jakarta", 420, 420), ("Asia/Jayapura", 540, 540),
+    ("Asia/Jerusalem", 120, 180), ("Asia/Kabul", 270, 270), ("Asia/Kamchatka", 720, 720),
+    ("Asia/Karachi", 300, 300), ("Asia/Kathmandu", 345, 345), ("Asia/Khandyga", 540, 540),
+    ("Asia/Kolkata", 330, 330), ("Asia/Krasnoyarsk", 420, 420), ("Asia/Kuala_Lumpur", 480, 480),
+    ("Asia/Kuching", 480, 480), ("Asia/Kuwait", 180, 180), ("Asia/Macau", 480, 480),
+    ("Asia/Magadan", 660, 660), ("Asia/Makassar", 480, 480), ("Asia/Manila", 480, 480),
+    ("Asia/Muscat", 240, 240), ("Asia/Nicosia", 120, 180), ("Asia/Novokuznetsk", 420, 420),
+    ("Asia/Novosibirsk", 420, 420), ("Asia/Omsk", 360, 360), ("Asia/Oral", 300, 300),
+    ("Asia/Phnom_Penh", 420, 420), ("Asia/Pontianak", 420, 420), ("Asia/Pyongyang", 540, 540),
+    ("Asia/Qatar", 180, 180), ("Asia/Qostanay", 300, 300), ("Asia/Qyzylorda", 300, 300),
+    ("Asia/Riyadh", 180, 180), ("Asia/Sakhalin", 660, 660), ("Asia/Samarkand", 300, 300),
+    ("Asia/Seoul", 540, 540), ("Asia/Shanghai", 480, 480), ("Asia/Singapore", 480, 480),
+    ("Asia/Srednekolymsk", 660, 660), ("Asia/Taipei", 480, 480), ("Asia/Tashkent", 300, 300),
+    ("Asia/Tbilisi", 240, 240), ("Asia/Tehran", 210, 210), ("Asia/Thimphu", 360, 360),
+    ("Asia/Tokyo", 540, 540), ("Asia/Tomsk", 420, 420), ("Asia/Ulaanbaatar", 480, 480),
+    ("Asia/Urumqi", 360, 360), ("Asia/Ust-Nera", 600, 600), ("Asia/Vientiane", 420, 420),
+    ("Asia/Vladivostok", 600, 600), ("Asia/Yakutsk", 540, 540), ("Asia/Yangon", 390, 390),
+    ("Asia/Yekaterinburg", 300, 300), ("Asia/Yerevan", 240, 240), ("Atlantic/Azores", -60, 0),
+    ("Atlantic/Bermuda", -240, -180), ("Atlantic/Canary", 0, 60), ("Atlantic/Cape_Verde", -60, -60),
+    ("Atlantic/Faroe", 0, 60), ("Atlantic/Madeira", 0, 60), ("Atlantic/Reykjavik", 0, 0),
+    ("Atlantic/South_Georgia", -120, -120), ("Atlantic/St_Helena", 0, 0),
+    ("Atlantic/Stanley", -180, -180), ("Australia/Adelaide", 570, 630),
+    ("Australia/Brisbane", 600, 600), ("Australia/Broken_Hill", 570, 630),
+    ("Australia/Darwin", 570, 570), ("Australia/Eucla", 525, 525), ("Australia/Hobart", 600, 660),
+    ("Australia/Lindeman", 600, 600), ("Australia/Lord_Howe", 630, 660),
+    ("Australia/Melbourne", 600, 660), ("Australia/Perth", 480, 480),
+    ("Australia/Sydney", 600, 660), ("Etc/GMT", 0, 0), ("Etc/UTC", 0, 0),
+    ("Europe/Amsterdam", 60, 120), ("Europe/Andorra", 60, 120), ("Europe/Astrakhan", 240, 240),
+    ("Europe/Athens", 120, 180), ("Europe/Belgrade", 60, 120), ("Europe/Berlin", 60, 120),
+    ("Europe/Bratislava", 60, 120), ("Europe/Brussels", 60, 120), ("Europe/Bucharest", 120, 180),
+    ("Europe/Budapest", 60, 120), ("Europe/Busingen", 60, 120), ("Europe/Chisinau", 120, 180),
+    ("Europe/Copenhagen", 60, 120), ("Europe/Dublin", 0, 60), ("Europe/Gibraltar", 60, 120),
+    ("Europe/Guernsey", 0, 60), ("Europe/Helsinki", 120, 180), ("Europe/Isle_of_Man", 0, 60),
+    ("Europe/Istanbul", 180, 180), ("Europe/Jersey", 0, 60), ("Europe/Kaliningrad", 120, 120),
+    ("Europe/Kirov", 180, 180), ("Europe/Kyiv", 120, 180), ("Europe/Lisbon", 0, 60),
+    ("Europe/Ljubljana", 60, 120), ("Europe/London", 0, 60), ("Europe/Luxembourg", 60, 120),
+    ("Europe/Madrid", 60, 120), ("Europe/Malta", 60, 120), ("Europe/Mariehamn", 120, 180),
+    ("Europe/Minsk", 180, 180), ("Europe/Monaco", 60, 120), ("Europe/Moscow", 180, 180),
+    ("Europe/Oslo", 60, 120), ("Europe/Paris", 60, 120), ("Europe/Podgorica", 60, 120),
+    ("Europe/Prague", 60, 120), ("Europe/Riga", 120, 180), ("Europe/Rome", 60, 120),
+    ("Europe/Samara", 240, 240), ("Europe/San_Marino", 60, 120), ("Europe/Sarajevo", 60, 120),
+    ("Europe/Saratov", 240, 240), ("Europe/Simferopol", 180, 180), ("Europe/Skopje", 60, 120),
+    ("Europe/Sofia", 120, 180), ("Europe/Stockholm", 60, 120), ("Europe/Tallinn", 120, 180),
+    ("Europe/Tirane", 60, 120), ("Europe/Ulyanovsk", 240, 240), ("Europe/Vaduz", 60, 120),
+    ("Europe/Vatican", 60, 120), ("Europe/Vienna", 60, 120), ("Europe/Vilnius", 120, 180),
+    ("Europe/Volgograd", 180, 180), ("Europe/Warsaw", 60, 120), ("Europe/Zagreb", 60, 120),
+    ("Europe/Zurich", 60, 120), ("Indian/Antananarivo", 180, 180), ("Indian/Chagos", 360, 360),
+    ("Indian/Christmas", 420, 420), ("Indian/Cocos", 390, 390), ("Indian/Comoro", 180, 180),
+    ("Indian/Kerguelen", 300, 300), ("Indian/Mahe", 240, 240), ("Indian/Maldives", 300, 300),
+    ("Indian/Mauritius", 240, 240), ("Indian/Mayotte", 180, 180), ("Indian/Reunion", 240, 240),
+    ("Pacific/Apia", 780, 780), ("Pacific/Auckland", 720, 780), ("Pacific/Bougainville", 660, 660),
+    ("Pacific/Chatham", 765, 825), ("Pacific/Chuuk", 600, 600), ("Pacific/Easter", -360, -300),
+    ("Pacific/Efate", 660, 660), ("Pacific/Fakaofo", 780, 780), ("Pacific/Fiji", 720, 720),
+    ("Pacific/Funafuti", 720, 720), ("Pacific/Galapagos", -360, -360),
+    ("Pacific/Gambier", -540, -540), ("Pacific/Guadalcanal", 660, 660), ("Pacific/Guam", 600, 600),
+    ("Pacific/Honolulu", -600, -600), ("Pacific/Kanton", 780, 780),
+    ("Pacific/Kiritimati", 840, 840), ("Pacific/Kosrae", 660, 660), ("Pacific/Kwajalein", 720, 720),
+    ("Pacific/Majuro", 720, 720), ("Pacific/Marquesas", -570, -570), ("Pacific/Midway", -660, -660),
+    ("Pacific/Nauru", 720, 720), ("Pacific/Niue", -660, -660), ("Pacific/Norfolk", 660, 720),
+    ("Pacific/Noumea", 660, 660), ("Pacific/Pago_Pago", -660, -660), ("Pacific/Palau", 540, 540),
+    ("Pacific/Pitcairn", -480, -480), ("Pacific/Pohnpei", 660, 660),
+    ("Pacific/Port_Moresby", 600, 600), ("Pacific/Rarotonga", -600, -600),
+    ("Pacific/Saipan", 600, 600), ("Pacific/Tahiti", -600, -600), ("Pacific/Tarawa", 720, 720),
+    ("Pacific/Tongatapu", 780, 780), ("Pacific/Wake", 720, 720), ("Pacific/Wallis", 720, 720),
+    ("UTC", 0, 0),
+];
+
+/// ISO 3166-1 alpha-2 codes, sorted for binary search
+const COUNTRY_CODES: &[&str] = &[
+    "AD", "AE", "AF", "AG", "AI", "AL", "AM", "AO", "AQ", "AR", "AS", "AT", "AU", "AW", "AX", "AZ",
+    "BA", "BB", "BD", "BE", "BF", "BG", "BH", "BI", "BJ", "BL", "BM", "BN", "BO", "BQ", "BR", "BS",
+    "BT", "BV", "BW", "BY", "BZ", "CA", "CC", "CD", "CF", "CG", "CH", "CI", "CK", "CL", "CM", "CN",
+    "CO", "CR", "CU", "CV", "CW", "CX", "CY", "CZ", "DE", "DJ", "DK", "DM", "DO", "DZ", "EC", "EE",
+    "EG", "EH", "ER", "ES", "ET", "FI", "FJ", "FK", "FM", "FO", "FR", "GA", "GB", "GD", "GE", "GF",
+    "GG", "GH", "GI", "GL", "GM", "GN", "GP", "GQ", "GR", "GS", "GT", "GU", "GW", "GY", "HK", "HM",
+    "HN", "HR", "HT", "HU", "ID", "IE", "IL", "IM", "IN", "IO", "IQ", "IR", "IS", "IT", "JE", "JM",
+    "JO", "JP", "KE", "KG", "KH", "KI", "KM", "KN", "KP", "KR", "KW", "KY", "KZ", "LA", "LB", "LC",
+    "LI", "LK", "LR", "LS", "LT", "LU", "LV", "LY", "MA", "MC", "MD", "ME", "MF", "MG", "MH", "MK",
+    "ML", "MM", "MN", "MO", "MP", "MQ", "MR", "MS", "MT", "MU", "MV", "MW", "MX", "MY", "MZ", "NA",
+    "NC", "NE", "NF", "NG", "NI", "NL", "NO", "NP", "NR", "NU", "NZ", "OM", "PA", "PE", "PF", "PG",
+    "PH", "PK", "PL", "PM", "PN", "PR", "PS", "PT", "PW", "PY", "QA", "RE", "RO", "RS", "RU", "RW",
+    "SA", "SB", "SC", "SD", "SE", "SG", "SH", "SI", "SJ", "SK", "SL", "SM", "SN", "SO", "SR", "SS",
+    "ST", "SV", "SX", "SY", "SZ", "TC", "TD", "TF", "TG", "TH", "TJ", "TK", "TL", "TM", "TN", "TO",
+    "TR", "TT", "TV", "TW", "TZ", "UA", "UG", "UM", "US", "UY", "UZ", "VA", "VC", "VE", "VG", "VI",
+    "VN", "VU", "WF", "WS", "YE", "YT", "ZA", "ZM", "ZW",
+];
+
+// ============ Lookup ============
+
+pub fn suggest_geo_for_country(country: &str) -> Result<GeoSuggestion, AppError> {
+    let code = country.trim().to_ascii_uppercase();
+
+    COUNTRIES
+        .iter()
+        .find(|c| c.code == code)
+        .map(|c| GeoSuggestion {
+            country: c.code.to_string(),
+            timezone: c.timezone.to_string(),
+            locale: c.locale.to_string(),
+            language: c.language.to_string(),
+            latitude: c.latitude,
+            longitude: c.longitude,
+        })
+        .ok_or_else(|| AppError::NotFound(format!("No geo data for country code: {}", country)))
+}
+
+pub fn is_known_timezone(timezone: &str) -> bool {
+    find_timezone(timezone).is_some()
+}
+
+/// Every timezone `is_known_timezone` accepts, sorted
+pub fn timezone_names() -> Vec<&'static str> {
+    TIMEZONES.iter().map(|(name, _, _)| *name).collect()
+}
+
+/// Check a timezone and look up its offsets, e.g. "+05:30". Offsets are None for unknown zones.
+pub fn check_timezone(timezone: &str) -> TimezoneCheck {
+    let zone = find_timezone(timezone);
+    TimezoneCheck {
+        valid: zone.is_some(),
+        utc_offset: zone.map(|(_, standard, _)| format_utc_offset(*standard)),
+        dst_utc_offset: zone.filter(|(_, standard, daylight)| standard != daylight)
+            .map(|(_, _, daylight)| format_utc_offset(*daylight)),
+    }
+}
+
+fn find_timezone(timezone: &str) -> Option<&'static (&'static str, i32, i32)> {
+    TIMEZONES.binary_search_by_key(&timezone, |(name, _, _)| name).ok().map(|index| &TIMEZONES[index])
+}
+
+/// 330 -> "+05:30", -210 -> "-03:30"
+fn format_utc_offset(minutes: i32) -> String {
+    let sign = if minutes < 0 { '-' } else { '+' };
+    format!("{}{:02}:{:02}", sign, minutes.abs() / 60, minutes.abs() % 60)
+}
+
+/// Whether `code` is an ISO 3166-1 alpha-2 country code (case-sensitive, uppercase)
+pub fn is_country_code(code: &str) -> bool {
+    COUNTRY_CODES.binary_search(&code).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(timezone: &str) -> (Option<String>, Option<String>) {
+        let check = check_timezone(timezone);
+        (check.utc_offset, check.dst_utc_offset)
+    }
+
+    #[test]
+    fn timezone_list_is_sorted_and_complete() {
+        let names = timezone_names();
+        assert!(names.len() > 400, "only {} zones", names.len());
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+        for timezone in ["America/New_York", "Asia/Ho_Chi_Minh", "Europe/Berlin", "UTC", "Asia/Kolkata", "Pacific/Chatham"] {
+            assert!(names.contains(&timezone), "{}", timezone);
+        }
+    }
+
+    #[test]
+    fn known_timezones_validate_with_offsets() {
+        for timezone in ["America/New_York", "Asia/Ho_Chi_Minh", "Europe/Berlin", "UTC", "Asia/Kolkata"] {
+            assert!(check_timezone(timezone).valid, "{}", timezone);
+            assert!(is_known_timezone(timezone), "{}", timezone);
+        }
+        assert_eq!(offsets("Asia/Kolkata"), (Some("+05:30".to_string()), None));
+        assert_eq!(offsets("America/St_Johns"), (Some("-03:30".to_string()), Some("-02:30".to_string())));
+        assert_eq!(offsets("Europe/Dublin"), (Some("+00:00".to_string()), Some("+01:00".to_string())));
+    }
+
+    #[test]
+    fn unknown_timezones_are_rejected() {
+        // Case matters, aliases and POSIX-style offsets aren't canonical names
+        for timezone in ["", "Mars/Olympus", "america/new_york", "Asia/Calcutta", "GMT+7", "Europe/Berlin "] {
+            assert!(!check_timezone(timezone).valid, "{:?}", timezone);
+            assert!(!is_known_timezone(timezone), "{:?}", timezone);
+        }
+        assert_eq!(offsets("Nope"), (None, None));
+    }
+}
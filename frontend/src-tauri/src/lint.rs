@@ -0,0 +1,419 @@
+use serde::Serialize;
+
+use crate::database::DbProfile;
+use crate::geo;
+
+// ============ Types ============
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warn,
+    Error,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct LintFinding {
+    pub severity: Severity,
+    /// Profile field (frontend name) the finding is about
+    pub field: String,
+    pub message: String,
+}
+
+/// Result of `validate_user_agent`. `ok` means the UA was recognized and agrees with the
+/// declared OS and browser; `errors` are for UAs that couldn't be parsed at all.
+#[derive(Debug, Serialize, Clone)]
+pub struct UaValidation {
+    pub ok: bool,
+    /// The UA with surrounding quotes, a pasted `User-Agent:` prefix and extra whitespace removed
+    pub normalized: String,
+    pub parsed: ParsedUserAgent,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ParsedUserAgent {
+    pub browser: Option<String>,
+    pub version: Option<String>,
+    pub os: Option<String>,
+}
+
+struct Findings(Vec<LintFinding>);
+
+impl Findings {
+    fn warn(&mut self, field: &str, message: String) {
+        self.push(Severity::Warn, field, message);
+    }
+
+    fn error(&mut self, field: &str, message: String) {
+        self.push(Severity::Error, field, message);
+    }
+
+    fn push(&mut self, severity: Severity, field: &str, message: String) {
+        self.0.push(LintFinding { severity, field: field.to_string(), message });
+    }
+}
+
+// ============ Reference Data ============
+
+/// Screen sizes real devices report, landscape for desktops and portrait for phones and
+/// tablets. Includes the physical-pixel sizes the profile editor's device presets use.
+const KNOWN_SCREEN_RESOLUTIONS: &[(i32, i32)] = &[
+    // Desktops and laptops
+    (1024, 768), (1152, 864), (1280, 720), (1280, 768), (1280, 800), (1280, 1024), (1360, 768),
+    (1366, 768), (1400, 1050), (1440, 900), (1470, 956), (1512, 982), (1536, 864), (1600, 900),
+    (1600, 1200), (1680, 1050), (1728, 1117), (1920, 1080), (1920, 1200), (2048, 1152),
+    (2560, 1080), (2560, 1440), (2560, 1600), (2880, 1800), (3440, 1440), (3840, 1600),
+    (3840, 2160), (5120, 1440), (5120, 2880),
+    // Phones
+    (320, 568), (360, 640), (360, 740), (360, 760), (360, 780), (360, 800), (375, 667),
+    (375, 812), (384, 854), (390, 844), (393, 851), (393, 852), (393, 873), (412, 732),
+    (412, 892), (412, 915), (414, 736), (414, 896), (428, 926), (430, 932),
+    // Tablets
+    (768, 1024), (800, 1280), (810, 1080), (820, 1180), (834, 1112), (834, 1194), (1024, 1366),
+    // Physical pixels of high-density displays
+    (720, 1600), (1080, 1920), (1080, 2340), (1080, 2400), (1170, 2532), (1179, 2556),
+    (1284, 2778), (1290, 2796), (1440, 3088), (1440, 3200), (1620, 2160), (2048, 2732),
+    (3024, 1964), (3456, 2234), (4480, 2520),
+];
+
+// ============ Linter ============
+
+/// Cross-field consistency checks for things detection sites compare against each other.
+/// Returns an empty list for a coherent profile.
+pub fn lint_profile(profile: &DbProfile) -> Vec<LintFinding> {
+    let mut findings = Findings(Vec::new());
+
+    check_user_agent(profile, &mut findings);
+    check_hardware(profile, &mut findings);
+    check_screen(profile, &mut findings);
+    check_webgl(profile, &mut findings);
+    check_locale(profile, &mut findings);
+
+    findings.0
+}
+
+/// Check a user agent on its own against the OS and browser type it is meant for,
+/// for the profile editor to call as soon as a UA is pasted
+pub fn validate_user_agent(ua: &str, os: &str, browser_type: &str) -> UaValidation {
+    let normalized = normalize_user_agent(ua);
+    let ua = normalized.as_str();
+    let os = os.trim().to_ascii_lowercase();
+    let browser_type = browser_type.trim().to_ascii_lowercase();
+
+    let ua_os = os_from_user_agent(ua);
+    let ua_browser = browser_from_user_agent(ua);
+    let parsed = ParsedUserAgent {
+        browser: ua_browser.map(str::to_string),
+        version: ua_browser.and_then(|browser| browser_version(ua, browser)).map(str::to_string),
+        os: ua_os.map(str::to_string),
+    };
+
+    let mut warnings = Vec::new();
+    let mut errors = Vec::new();
+    if ua.is_empty() {
+        errors.push("User agent is empty".to_string());
+    } else {
+        if !ua.starts_with("Mozilla/5.0 (") {
+            errors.push("User agent does not start with 'Mozilla/5.0 (', as every current browser's does".to_string());
+        }
+        if ua_os.is_none() {
+            errors.push("Could not recognize an operating system in the user agent".to_string());
+        }
+        if ua_browser.is_none() {
+            errors.push("Could not recognize a browser in the user agent".to_string());
+        } else if parsed.version.is_none() {
+            errors.push("Could not find the browser version in the user agent".to_string());
+        }
+    }
+
+    if let Some(ua_os) = ua_os.filter(|ua_os| !os.is_empty() && *ua_os != os) {
+        warnings.push(format!("User agent is for {} but the profile OS is {}", ua_os, os));
+    }
+    if let Some(ua_browser) = ua_browser.filter(|ua_browser| !browser_type.is_empty() && *ua_browser != browser_type) {
+        warnings.push(format!("User agent is {} but the browser type is {}", ua_browser, browser_type));
+    }
+    if ua_browser == Some("safari") && ua_os.is_some_and(|ua_os| !matches!(ua_os, "macos" | "ios")) {
+        warnings.push(format!("Safari does not run on {}", ua_os.unwrap_or_default()));
+    }
+
+    UaValidation {
+        ok: warnings.is_empty() && errors.is_empty(),
+        normalized,
+        parsed,
+        warnings,
+        errors,
+    }
+}
+
+fn check_user_agent(profile: &DbProfile, findings: &mut Findings) {
+    let os = profile.os.to_ascii_lowercase();
+    let ua = profile.user_agent.as_str();
+    let ua_os = os_from_user_agent(ua);
+
+    // navigator.platform must match the OS
+    if let Some(expected) = expected_platform_prefix(&os) {
+        if !profile.platform.starts_with(expected) {
+            findings.error("platform", format!("Platform '{}' does not match OS {} (expected {}*)", profile.platform, os, expected));
+        }
+    }
+
+    // UA must describe the same OS as the profile, and so must the platform sites compare it to
+    if let Some(ua_os) = ua_os.filter(|ua_os| !os.is_empty() && *ua_os != os) {
+        findings.error("userAgent", format!("User agent is for {} but the profile OS is {}", ua_os, os));
+
+        if let Some(expected) = expected_platform_prefix(ua_os) {
+            if !profile.platform.starts_with(expected) {
+                findings.error("platform", format!("Platform '{}' contradicts the user agent's OS ({})", profile.platform, ua_os));
+            }
+        }
+    }
+
+    // Browser type must match the UA's browser token
+    if let Some(ua_browser) = browser_from_user_agent(ua) {
+        let browser = profile.browser_type.to_ascii_lowercase();
+        if !browser.is_empty() && ua_browser != browser {
+            findings.error("browserType", format!("Browser type is {} but the user agent is {}", browser, ua_browser));
+        }
+
+        // Major version should match too
+        if let Some(ua_version) = browser_major_version(ua, ua_browser) {
+            let version = profile.browser_version.split('.').next().unwrap_or("");
+            if !version.is_empty() && ua_browser == browser && version != ua_version {
+                findings.warn("browserVersion", format!("Browser version {} differs from the user agent's {}", profile.browser_version, ua_version));
+            }
+        }
+    }
+
+    // Safari only ships on Apple platforms
+    if profile.browser_type.eq_ignore_ascii_case("safari") && !matches!(os.as_str(), "macos" | "ios") {
+        findings.error("browserType", format!("Safari does not run on {}", os));
+    }
+}
+
+fn check_hardware(profile: &DbProfile, findings: &mut Findings) {
+    let mobile = is_mobile_os(&profile.os);
+
+    if !mobile && profile.max_touch_points > 0 {
+        findings.warn("maxTouchPoints", format!("Desktop {} profile reports {} touch points", profile.os, profile.max_touch_points));
+    }
+    if mobile && profile.max_touch_points == 0 {
+        findings.error("maxTouchPoints", format!("Mobile {} profile reports no touch support", profile.os));
+    }
+
+    // Chromium caps navigator.deviceMemory at 8
+    let chromium = !matches!(profile.browser_type.to_ascii_lowercase().as_str(), "firefox" | "safari");
+    if chromium && profile.device_memory > 8 {
+        findings.warn("deviceMemory", format!("navigator.deviceMemory is capped at 8 in Chromium browsers, got {}", profile.device_memory));
+    }
+
+    if profile.cpu_cores <= 0 {
+        findings.error("cpuCores", format!("CPU core count must be positive, got {}", profile.cpu_cores));
+    }
+}
+
+/// Viewport and screen size checks on their own: the viewport must fit on the screen, and the
+/// screen must be a resolution real devices report. Part of `lint_profile`.
+pub fn check_screen_geometry(profile: &DbProfile) -> Vec<LintFinding> {
+    let mut findings = Findings(Vec::new());
+
+    if profile.viewport_width > profile.screen_width {
+        findings.error("viewportWidth", format!(
+            "Viewport width {} is larger than the screen width {}", profile.viewport_width, profile.screen_width
+        ));
+    }
+    if profile.viewport_height > profile.screen_height {
+        findings.error("viewportHeight", format!(
+            "Viewport height {} is larger than the screen height {}", profile.viewport_height, profile.screen_height
+        ));
+    }
+
+    // Either orientation, since phones and tablets report whichever way they are held
+    let (width, height) = (profile.screen_width, profile.screen_height);
+    if !KNOWN_SCREEN_RESOLUTIONS.iter().any(|known| *known == (width, height) || *known == (height, width)) {
+        findings.warn("screenWidth", format!("Screen resolution {}x{} is not one real devices report", width, height));
+    }
+
+    findings.0
+}
+
+fn check_screen(profile: &DbProfile, findings: &mut Findings) {
+    findings.0.extend(check_screen_geometry(profile));
+
+    if !matches!(profile.color_depth, 24 | 30 | 32) {
+        findings.warn("colorDepth", format!("Unusual color depth {}", profile.color_depth));
+    }
+}
+
+fn check_webgl(profile: &DbProfile, findings: &mut Findings) {
+    let renderer = profile.webgl_renderer.to_ascii_lowercase();
+    if renderer.is_empty() {
+        return;
+    }
+
+    // "Google Inc. (NVIDIA)" must be paired with an NVIDIA renderer
+    if let Some(gpu_vendor) = vendor_in_parens(&profile.webgl_vendor) {
+        if !renderer.contains(&gpu_vendor.to_ascii_lowercase()) {
+            findings.error("webglRenderer", format!("WebGL vendor '{}' does not match renderer '{}'", profile.webgl_vendor, profile.webgl_renderer));
+        }
+    }
+
+    let os = profile.os.to_ascii_lowercase();
+    if renderer.contains("direct3d") && os != "windows" {
+        findings.error("webglRenderer", format!("Direct3D renderer on a {} profile", os));
+    }
+    if renderer.contains("apple") && !matches!(os.as_str(), "macos" | "ios") {
+        findings.error("webglRenderer", format!("Apple GPU renderer on a {} profile", os));
+    }
+}
+
+fn check_locale(profile: &DbProfile, findings: &mut Findings) {
+    // Auto modes are resolved from the proxy at launch time
+    if profile.locale_mode != "auto" && !profile.locale.is_empty() {
+        let primary = profile.language.split(',').next().unwrap_or("").trim();
+        if !primary.is_empty() && !primary.eq_ignore_ascii_case(&profile.locale) {
+            findings.warn("language", format!("Primary language '{}' differs from locale '{}'", primary, profile.locale));
+        }
+
+        if let Some((_, region)) = profile.locale.split_once('-') {
+            if !profile.country.is_empty() && !region.eq_ignore_ascii_case(&profile.country) {
+                findings.warn("locale", format!("Locale '{}' is for region {} but the country is {}", profile.locale, region, profile.country));
+            }
+        }
+    }
+
+    if profile.timezone_mode != "auto" && !profile.timezone.is_empty() && !profile.country.is_empty() {
+        if let Ok(expected) = geo::suggest_geo_for_country(&profile.country) {
+            let region = |tz: &str| tz.split('/').next().unwrap_or("").to_string();
+            if region(&profile.timezone) != region(&expected.timezone) {
+                findings.warn("timezone", format!(
+                    "Timezone {} is unlikely for country {} (expected {})",
+                    profile.timezone, profile.country, expected.timezone
+                ));
+            }
+        }
+    }
+}
+
+// ============ Helpers ============
+
+pub(crate) fn os_from_user_agent(ua: &str) -> Option<&'static str> {
+    // Order matters: iOS and Android UAs also mention Mac OS X / Linux
+    if ua.contains("iPhone") || ua.contains("iPad") {
+        Some("ios")
+    } else if ua.contains("Android") {
+        Some("android")
+    } else if ua.contains("Windows NT") {
+        Some("windows")
+    } else if ua.contains("Macintosh") || ua.contains("Mac OS X") {
+        Some("macos")
+    } else if ua.contains("Linux") || ua.contains("X11") {
+        Some("linux")
+    } else {
+        None
+    }
+}
+
+pub(crate) fn browser_from_user_agent(ua: &str) -> Option<&'static str> {
+    // Order matters: Edge UAs contain Chrome, Chrome UAs contain Safari
+    if ua.contains("Firefox/") {
+        Some("firefox")
+    } else if ua.contains("Edg/") {
+        Some("edge")
+    } else if ua.contains("Chrome/") || ua.contains("CriOS/") {
+        Some("chrome")
+    } else if ua.contains("Safari/") {
+        Some("safari")
+    } else {
+        None
+    }
+}
+
+fn browser_major_version<'a>(ua: &'a str, browser: &str) -> Option<&'a str> {
+    browser_version(ua, browser)?.split('.').next()
+}
+
+/// Full version after the browser's token, e.g. "120.0.6099.109"
+fn browser_version<'a>(ua: &'a str, browser: &str) -> Option<&'a str> {
+    let token = match browser {
+        "firefox" => "Firefox/",
+        "edge" => "Edg/",
+        "chrome" if ua.contains("CriOS/") => "CriOS/",
+        "chrome" => "Chrome/",
+        "safari" => "Version/",
+        _ => return None,
+    };
+    let rest = &ua[ua.find(token)? + token.len()..];
+    let end = rest.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(rest.len());
+    Some(rest[..end].trim_end_matches('.')).filter(|v| v.starts_with(|c: char| c.is_ascii_digit()))
+}
+
+/// Strip what tends to come along when a UA is copied from a header dump or code
+fn normalize_user_agent(ua: &str) -> String {
+    let ua = ua.trim();
+    let ua = ua.strip_prefix("User-Agent:").or_else(|| ua.strip_prefix("user-agent:")).unwrap_or(ua);
+    let ua = ua.trim().trim_matches(|c| c == '"' || c == '\'');
+    ua.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Same values the profile editor assigns per OS
+fn expected_platform_prefix(os: &str) -> Option<&'static str> {
+    match os {
+        "windows" => Some("Win"),
+        "macos" => Some("Mac"),
+        "linux" | "android" => Some("Linux"),
+        "ios" => Some("iP"),
+        _ => None,
+    }
+}
+
+fn is_mobile_os(os: &str) -> bool {
+    matches!(os.to_ascii_lowercase().as_str(), "android" | "ios")
+}
+
+/// "Google Inc. (NVIDIA)" -> "NVIDIA"
+pub(crate) fn vendor_in_parens(vendor: &str) -> Option<&str> {
+    let start = vendor.find('(')? + 1;
+    let end = vendor[start..].find(')')? + start;
+    Some(vendor[start..end].trim()).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(screen: (i32, i32), viewport: (i32, i32)) -> DbProfile {
+        let mut profile = crate::database::tests::profile("p1");
+        (profile.screen_width, profile.screen_height) = screen;
+        (profile.viewport_width, profile.viewport_height) = viewport;
+        profile
+    }
+
+    fn findings(profile: &DbProfile) -> Vec<(Severity, String)> {
+        check_screen_geometry(profile).into_iter().map(|f| (f.severity, f.field)).collect()
+    }
+
+    #[test]
+    fn fitting_viewport_on_a_real_screen_passes() {
+        assert!(findings(&profile((1920, 1080), (1920, 1080))).is_empty());
+        assert!(findings(&profile((1366, 768), (1366, 657))).is_empty());
+        // Phones may report either orientation
+        assert!(findings(&profile((390, 844), (390, 664))).is_empty());
+        assert!(findings(&profile((844, 390), (844, 390))).is_empty());
+    }
+
+    #[test]
+    fn oversize_viewport_is_an_error() {
+        assert_eq!(findings(&profile((1920, 1080), (2560, 1080))), [(Severity::Error, "viewportWidth".to_string())]);
+        assert_eq!(findings(&profile((1920, 1080), (1920, 1200))), [(Severity::Error, "viewportHeight".to_string())]);
+        assert_eq!(findings(&profile((1920, 1080), (1921, 1081))).len(), 2);
+    }
+
+    #[test]
+    fn nonexistent_resolution_is_a_warning() {
+        assert_eq!(findings(&profile((1921, 1080), (1280, 720))), [(Severity::Warn, "screenWidth".to_string())]);
+        let finding = &check_screen_geometry(&profile((1000, 1000), (800, 600)))[0];
+        assert_eq!(finding.message, "Screen resolution 1000x1000 is not one real devices report");
+    }
+}
@@ -0,0 +1,279 @@
+use serde::{Deserialize, Serialize};
+#[cfg(target_os = "windows")]
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::AppError;
+
+// ============ Types ============
+
+/// A running browser, collapsed to its top-level process. Helper processes
+/// (renderers, GPU, content processes) are counted in `memory_bytes`.
+#[derive(Debug, Serialize, Clone)]
+pub struct BrowserProcess {
+    pub pid: u32,
+    pub name: String,
+    #[serde(rename = "memoryBytes")]
+    pub memory_bytes: u64,
+    /// CPU use as `ps` reports it, summed over helper processes; not available on Windows
+    #[serde(rename = "cpuPercent")]
+    pub cpu_percent: Option<f64>,
+    /// Profile directory from `--user-data-dir` / `-profile`, if the browser was given one
+    #[serde(rename = "userDataDir")]
+    pub user_data_dir: Option<String>,
+    /// Set when a sidecar worker still tracks the browser
+    #[serde(rename = "sessionId")]
+    pub session_id: Option<String>,
+    #[serde(rename = "profileId")]
+    pub profile_id: Option<String>,
+}
+
+/// What one session's browser is using. The sidecar reports what it can measure and the
+/// rest comes from the OS, so any field may be missing.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SessionUsage {
+    #[serde(rename = "sessionId")]
+    pub session_id: String,
+    pub pid: Option<u32>,
+    #[serde(rename = "memoryMb")]
+    pub memory_mb: Option<f64>,
+    #[serde(rename = "cpuPercent")]
+    pub cpu_percent: Option<f64>,
+}
+
+struct ProcessEntry {
+    pid: u32,
+    ppid: u32,
+    memory_bytes: u64,
+    cpu_percent: Option<f64>,
+    command_line: String,
+    name: String,
+}
+
+/// Executable names of the browsers the sidecar launches (system Chrome, Playwright builds)
+const BROWSER_EXECUTABLES: &[&str] = &[
+    "chrome", "google chrome", "chromium", "chromium-browser", "headless_shell",
+    "chrome-headless-shell", "firefox", "firefox-bin", "minibrowser",
+];
+
+// ============ Scan ============
+
+/// Every running browser process, by executable name. This also matches browsers the
+/// user started themselves; callers narrow it down by `user_data_dir`.
+pub fn scan_browser_processes() -> Result<Vec<BrowserProcess>, AppError> {
+    let browsers: Vec<ProcessEntry> = list_processes()?
+        .into_iter()
+        .filter(|p| BROWSER_EXECUTABLES.contains(&p.name.as_str()))
+        .collect();
+
+    // Chrome and Firefox spawn helpers running the same executable; report only the root
+    let parents: HashMap<u32, u32> = browsers.iter().map(|p| (p.pid, p.ppid)).collect();
+    let root_of = |mut pid: u32| {
+        while let Some(&ppid) = parents.get(&pid).filter(|ppid| parents.contains_key(ppid)) {
+            pid = ppid;
+        }
+        pid
+    };
+
+    let mut memory: HashMap<u32, u64> = HashMap::new();
+    let mut cpu: HashMap<u32, f64> = HashMap::new();
+    for process in &browsers {
+        *memory.entry(root_of(process.pid)).or_default() += process.memory_bytes;
+        if let Some(cpu_percent) = process.cpu_percent {
+            *cpu.entry(root_of(process.pid)).or_default() += cpu_percent;
+        }
+    }
+
+    Ok(browsers.iter()
+        .filter(|p| !parents.contains_key(&p.ppid))
+        .map(|p| BrowserProcess {
+            pid: p.pid,
+            name: p.name.clone(),
+            memory_bytes: memory.get(&p.pid).copied().unwrap_or(p.memory_bytes),
+            cpu_percent: cpu.get(&p.pid).copied(),
+            user_data_dir: user_data_dir(&p.command_line),
+            session_id: None,
+            profile_id: None,
+        })
+        .collect())
+}
+
+/// Whether a browser's profile directory lives under `profiles_dir`, i.e. the sidecar launched it
+pub fn is_under(process: &BrowserProcess, profiles_dir: &Path) -> bool {
+    process.user_data_dir.as_deref().is_some_and(|dir| Path::new(dir).starts_with(profiles_dir))
+}
+
+// ============ Session Usage ============
+
+const BYTES_PER_MB: f64 = 1024.0 * 1024.0;
+
+/// Combine what the sidecar `reported` with `browsers` tagged with their sessions, heaviest
+/// first. The sidecar's values win; the OS fills in memory, which the sidecar doesn't measure,
+/// and pid and CPU where it couldn't. Sessions only the OS knows about are included too.
+pub fn session_usage(reported: Vec<SessionUsage>, browsers: &[BrowserProcess]) -> Vec<SessionUsage> {
+    let mut usage: Vec<SessionUsage> = reported;
+    for browser in browsers {
+        let Some(session_id) = &browser.session_id else { continue };
+        let index = match usage.iter().position(|u| &u.session_id == session_id) {
+            Some(index) => index,
+            None => {
+                usage.push(SessionUsage { session_id: session_id.clone(), pid: None, memory_mb: None, cpu_percent: None });
+                usage.len() - 1
+            }
+        };
+        let session = &mut usage[index];
+        session.pid = session.pid.or(Some(browser.pid));
+        session.memory_mb = session.memory_mb.or(Some(round1(browser.memory_bytes as f64 / BYTES_PER_MB)));
+        session.cpu_percent = session.cpu_percent.or(browser.cpu_percent);
+    }
+
+    usage.sort_by(|a, b| {
+        b.memory_mb.unwrap_or(0.0).total_cmp(&a.memory_mb.unwrap_or(0.0)).then_with(|| a.session_id.cmp(&b.session_id))
+    });
+    usage
+}
+
+/// Sessions using more than `threshold_mb` of memory, heaviest first
+pub fn heavy_sessions(usage: Vec<SessionUsage>, threshold_mb: f64) -> Vec<SessionUsage> {
+    usage.into_iter().filter(|u| u.memory_mb.is_some_and(|mb| mb > threshold_mb)).collect()
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+pub fn kill_process(pid: u32) -> Result<(), AppError> {
+    #[cfg(target_os = "windows")]
+    let status = Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    #[cfg(not(target_os = "windows"))]
+    let status = Command::new("kill")
+        .arg(pid.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(AppError::Io(format!("Failed to kill process {}", pid)))
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn list_processes() -> Result<Vec<ProcessEntry>, AppError> {
+    let output = Command::new("ps")
+        .args(["-axo", "pid=,ppid=,rss=,pcpu=,args="])
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(AppError::Io("ps failed to list processes".to_string()));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let ppid = fields.next()?.parse().ok()?;
+            let rss_kb: u64 = fields.next()?.parse().ok()?;
+            let cpu_percent = fields.next()?.parse().ok();
+            let command_line = fields.collect::<Vec<_>>().join(" ");
+            // The executable path may contain spaces ("Google Chrome.app"), flags start with " -"
+            let executable = command_line.split(" -").next().unwrap_or("");
+            let name = executable.rsplit('/').next().unwrap_or("").to_ascii_lowercase();
+            Some(ProcessEntry { pid, ppid, memory_bytes: rss_kb * 1024, cpu_percent, command_line, name })
+        })
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+fn list_processes() -> Result<Vec<ProcessEntry>, AppError> {
+    let output = Command::new("powershell")
+        .args([
+            "-NoProfile",
+            "-Command",
+            "Get-CimInstance Win32_Process | Select-Object ProcessId,ParentProcessId,WorkingSetSize,Name,CommandLine | ConvertTo-Json -Compress",
+        ])
+        .stdin(Stdio::null())
+        .output()?;
+    if !output.status.success() {
+        return Err(AppError::Io("PowerShell failed to list processes".to_string()));
+    }
+
+    let processes: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    Ok(processes.iter()
+        .filter_map(|p| {
+            let name = p.get("Name")?.as_str()?.to_ascii_lowercase();
+            Some(ProcessEntry {
+                pid: p.get("ProcessId")?.as_u64()? as u32,
+                ppid: p.get("ParentProcessId").and_then(Value::as_u64).unwrap_or(0) as u32,
+                memory_bytes: p.get("WorkingSetSize").and_then(Value::as_u64).unwrap_or(0),
+                cpu_percent: None,
+                command_line: p.get("CommandLine").and_then(Value::as_str).unwrap_or("").to_string(),
+                name: name.trim_end_matches(".exe").to_string(),
+            })
+        })
+        .collect())
+}
+
+/// `--user-data-dir=<dir>` (Chromium) or `-profile <dir>` (Firefox)
+fn user_data_dir(command_line: &str) -> Option<String> {
+    let value = if let Some((_, rest)) = command_line.split_once("--user-data-dir=") {
+        rest
+    } else {
+        command_line.split_once(" -profile ")?.1
+    };
+    // Paths may contain spaces, so the value runs up to the next flag
+    let value = value.split(" -").next().unwrap_or("").trim().trim_matches('"');
+    Some(value.to_string()).filter(|v| !v.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn browser(pid: u32, session: Option<&str>, memory_mb: u64, cpu_percent: Option<f64>) -> BrowserProcess {
+        BrowserProcess {
+            pid,
+            name: "chrome".to_string(),
+            memory_bytes: memory_mb * 1024 * 1024,
+            cpu_percent,
+            user_data_dir: None,
+            session_id: session.map(str::to_string),
+            profile_id: None,
+        }
+    }
+
+    #[test]
+    fn session_usage_fills_gaps_from_the_os_and_sorts_heaviest_first() {
+        // What a worker answers to getSessionResourceUsage
+        let reported: Vec<SessionUsage> = serde_json::from_value(json!([
+            { "sessionId": "a", "pid": 10, "memoryMb": null, "cpuPercent": 12.5 },
+            { "sessionId": "b", "pid": null, "memoryMb": 900.0, "cpuPercent": null },
+        ])).unwrap();
+        let browsers = [
+            browser(10, Some("a"), 300, Some(50.0)),
+            browser(20, Some("b"), 100, Some(3.0)),
+            browser(30, Some("c"), 500, None),
+            browser(40, None, 9000, None),
+        ];
+
+        let usage = session_usage(reported, &browsers);
+        assert_eq!(serde_json::to_value(&usage).unwrap(), json!([
+            { "sessionId": "b", "pid": 20, "memoryMb": 900.0, "cpuPercent": 3.0 },
+            { "sessionId": "c", "pid": 30, "memoryMb": 500.0, "cpuPercent": null },
+            { "sessionId": "a", "pid": 10, "memoryMb": 300.0, "cpuPercent": 12.5 },
+        ]));
+
+        let heavy: Vec<String> = heavy_sessions(usage, 400.0).into_iter().map(|u| u.session_id).collect();
+        assert_eq!(heavy, ["b", "c"]);
+    }
+}
@@ -0,0 +1,339 @@
+use base64::Engine;
+use std::io::{Read, Write};
+use std::net::{IpAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::database::DbProxy;
+use crate::error::AppError;
+use crate::ProxyType;
+
+/// Plain-HTTP service that answers with the caller's public IP as the whole body
+const EGRESS_IP_HOST: &str = "api.ipify.org";
+const EGRESS_IP_PORT: u16 = 80;
+
+/// Largest response read from the IP service; an IP address is far smaller
+const MAX_RESPONSE_BYTES: u64 = 16 * 1024;
+
+// ============ Egress IP ============
+
+/// Ask the IP service, through the proxy, which address the request came from. This is a
+/// full proxied request (handshake, credentials, tunnel to the service), so a proxy that
+/// accepts connections but won't forward them fails here. https proxies are tunnelled with
+/// CONNECT like http ones.
+pub fn egress_ip(proxy: &DbProxy, timeout: Duration) -> Result<IpAddr, AppError> {
+    egress_ip_from(proxy, EGRESS_IP_HOST, EGRESS_IP_PORT, timeout)
+}
+
+fn egress_ip_from(proxy: &DbProxy, target_host: &str, target_port: u16, timeout: Duration) -> Result<IpAddr, AppError> {
+    let proxy_type = ProxyType::try_from(proxy.proxy_type.as_str())?;
+    let port = u16::try_from(proxy.port)
+        .map_err(|_| AppError::Validation(format!("Invalid proxy port: {}", proxy.port)))?;
+
+    let addr = (proxy.host.trim_start_matches('[').trim_end_matches(']'), port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| AppError::NotFound(format!("Could not resolve {}", proxy.host)))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout).map_err(|e| timed_out(e, "Proxy did not accept a connection"))?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    match proxy_type {
+        ProxyType::Http | ProxyType::Https => http_connect(&mut stream, proxy, target_host, target_port)?,
+        ProxyType::Socks4 => socks4_connect(&mut stream, proxy, target_host, target_port)?,
+        ProxyType::Socks5 => socks5_connect(&mut stream, proxy, target_host, target_port)?,
+    }
+
+    // HTTP/1.0 keeps the body unchunked and the server closes when it is done
+    write!(stream, "GET / HTTP/1.0\r\nHost: {}\r\nAccept: text/plain\r\n\r\n", target_host)
+        .map_err(|e| timed_out(e, "Proxy stopped responding"))?;
+    let mut response = Vec::new();
+    stream.take(MAX_RESPONSE_BYTES).read_to_end(&mut response).map_err(|e| timed_out(e, "Proxy stopped responding"))?;
+
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n")
+        .ok_or_else(|| AppError::Io("Incomplete response through the proxy".to_string()))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1) != Some("200") {
+        return Err(AppError::Io(format!("IP check through the proxy failed: {}", status)));
+    }
+    body.trim().parse::<IpAddr>()
+        .map_err(|_| AppError::Io(format!("IP check returned '{}', not an IP address", body.trim())))
+}
+
+/// A read or write that ran out of time is a `Timeout`, anything else an `Io` error
+fn timed_out(e: std::io::Error, what: &str) -> AppError {
+    match e.kind() {
+        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock => AppError::Timeout(what.to_string()),
+        _ => AppError::Io(format!("{}: {}", what, e)),
+    }
+}
+
+fn has_credentials(proxy: &DbProxy) -> bool {
+    !proxy.username.is_empty() || !proxy.password.is_empty()
+}
+
+// ============ Handshakes ============
+
+/// `CONNECT host:port` with Basic credentials when the proxy has them
+fn http_connect(stream: &mut TcpStream, proxy: &DbProxy, host: &str, port: u16) -> Result<(), AppError> {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if has_credentials(proxy) {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", proxy.username, proxy.password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    stream.write_all(request.as_bytes()).map_err(|e| timed_out(e, "Proxy stopped responding"))?;
+
+    // Read the reply head byte by byte so nothing of the tunnelled response is consumed
+    let mut head = Vec::new();
+    let mut byte = [0u8; 1];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() as u64 >= MAX_RESPONSE_BYTES {
+            return Err(AppError::Io("Proxy sent an oversized CONNECT reply".to_string()));
+        }
+        stream.read_exact(&mut byte).map_err(|e| timed_out(e, "Proxy closed the connection during CONNECT"))?;
+        head.push(byte[0]);
+    }
+
+    let head = String::from_utf8_lossy(&head);
+    let status = head.lines().next().unwrap_or_default();
+    match status.split_whitespace().nth(1) {
+        Some("200") => Ok(()),
+        Some("407") => Err(AppError::Validation("Proxy rejected the username or password".to_string())),
+        _ => Err(AppError::Io(format!("Proxy refused the tunnel: {}", status))),
+    }
+}
+
+/// SOCKS4a, so the proxy resolves the host; the username is sent as the user id
+fn socks4_connect(stream: &mut TcpStream, proxy: &DbProxy, host: &str, port: u16) -> Result<(), AppError> {
+    let mut request = vec![4, 1];
+    request.extend_from_slice(&port.to_be_bytes());
+    request.extend_from_slice(&[0, 0, 0, 1]);
+    request.extend_from_slice(proxy.username.as_bytes());
+    request.push(0);
+    request.extend_from_slice(host.as_bytes());
+    request.push(0);
+    stream.write_all(&request).map_err(|e| timed_out(e, "Proxy stopped responding"))?;
+
+    let mut reply = [0u8; 8];
+    stream.read_exact(&mut reply).map_err(|e| timed_out(e, "Proxy closed the connection during the SOCKS4 handshake"))?;
+    match reply[1] {
+        0x5A => Ok(()),
+        0x5C | 0x5D => Err(AppError::Validation("Proxy rejected the user id".to_string())),
+        code => Err(AppError::Io(format!("Proxy refused the connection (SOCKS4 code {:#04x})", code))),
+    }
+}
+
+/// SOCKS5 with username/password auth (RFC 1929) offered when the proxy has credentials,
+/// connecting by host name so the proxy resolves it
+fn socks5_connect(stream: &mut TcpStream, proxy: &DbProxy, host: &str, port: u16) -> Result<(), AppError> {
+    let handshake = |e| timed_out(e, "Proxy closed the connection during the SOCKS5 handshake");
+    let greeting: &[u8] = if has_credentials(proxy) { &[5, 2, 0, 2] } else { &[5, 1, 0] };
+    stream.write_all(greeting).map_err(handshake)?;
+    let mut choice = [0u8; 2];
+    stream.read_exact(&mut choice).map_err(handshake)?;
+    if choice[0] != 5 {
+        return Err(AppError::Io("Proxy did not answer as a SOCKS5 proxy".to_string()));
+    }
+
+    match choice[1] {
+        0 => {}
+        2 => {
+            if proxy.username.len() > 255 || proxy.password.len() > 255 {
+                return Err(AppError::Validation("SOCKS5 username and password must be at most 255 bytes".to_string()));
+            }
+            let mut auth = vec![1, proxy.username.len() as u8];
+            auth.extend_from_slice(proxy.username.as_bytes());
+            auth.push(proxy.password.len() as u8);
+            auth.extend_from_slice(proxy.password.as_bytes());
+            stream.write_all(&auth).map_err(handshake)?;
+            let mut status = [0u8; 2];
+            stream.read_exact(&mut status).map_err(handshake)?;
+            if status[1] != 0 {
+                return Err(AppError::Validation("Proxy rejected the username or password".to_string()));
+            }
+        }
+        _ => return Err(AppError::Validation("Proxy requires an authentication method that isn't supported".to_string())),
+    }
+
+    if host.len() > 255 {
+        return Err(AppError::Validation(format!("Host name too long for SOCKS5: {}", host)));
+    }
+    let mut request = vec![5, 1, 0, 3, host.len() as u8];
+    request.extend_from_slice(host.as_bytes());
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).map_err(handshake)?;
+
+    let mut reply = [0u8; 4];
+    stream.read_exact(&mut reply).map_err(handshake)?;
+    if reply[1] != 0 {
+        return Err(AppError::Io(format!("Proxy refused the connection (SOCKS5 code {:#04x})", reply[1])));
+    }
+    // Skip the bound address and port the proxy reports
+    let address_len = match reply[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8; 1];
+            stream.read_exact(&mut len).map_err(handshake)?;
+            len[0] as usize
+        }
+        other => return Err(AppError::Io(format!("Proxy sent an unknown SOCKS5 address type {}", other))),
+    };
+    let mut bound = vec![0u8; address_len + 2];
+    stream.read_exact(&mut bound).map_err(handshake)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::thread::JoinHandle;
+
+    const TIMEOUT: Duration = Duration::from_secs(2);
+
+    fn proxy(proxy_type: &str, port: u16, username: &str, password: &str) -> DbProxy {
+        serde_json::from_value(serde_json::json!({
+            "id": "px", "name": "px", "type": proxy_type, "host": "127.0.0.1", "port": port,
+            "username": username, "password": password, "country": "", "city": "", "status": "",
+            "lastTestedAt": "", "lastIP": "", "createdAt": "", "updatedAt": "",
+        })).unwrap()
+    }
+
+    /// A one-connection proxy on a free local port; `serve` gets the accepted stream
+    fn fake_proxy(serve: impl FnOnce(TcpStream) + Send + 'static) -> (u16, JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || serve(listener.accept().unwrap().0));
+        (port, handle)
+    }
+
+    /// Answer the tunnelled GET the way the IP service would
+    fn answer_ip(stream: &mut TcpStream, ip: &str) {
+        let mut request = Vec::new();
+        while !request.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            stream.read_exact(&mut byte).unwrap();
+            request.push(byte[0]);
+        }
+        assert!(request.starts_with(b"GET / HTTP/1.0\r\nHost: ip.test\r\n"));
+        write!(stream, "HTTP/1.0 200 OK\r\nContent-Type: text/plain\r\n\r\n{}", ip).unwrap();
+    }
+
+    #[test]
+    fn http_proxy_tunnels_with_basic_auth() {
+        let (port, server) = fake_proxy(|mut stream| {
+            let mut head = Vec::new();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                head.push(line.trim_end().to_string());
+            }
+            assert_eq!(head[0], "CONNECT ip.test:80 HTTP/1.1");
+            // base64("user:p@ss")
+            assert!(head.contains(&"Proxy-Authorization: Basic dXNlcjpwQHNz".to_string()), "{:?}", head);
+            stream.write_all(b"HTTP/1.1 200 Connection established\r\n\r\n").unwrap();
+            answer_ip(&mut stream, "203.0.113.7\n");
+        });
+
+        let ip = egress_ip_from(&proxy("http", port, "user", "p@ss"), "ip.test", 80, TIMEOUT).unwrap();
+        assert_eq!(ip.to_string(), "203.0.113.7");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn http_proxy_rejecting_credentials_is_a_validation_error() {
+        let (port, server) = fake_proxy(|mut stream| {
+            let mut buf = [0u8; 512];
+            let _ = stream.read(&mut buf).unwrap();
+            stream.write_all(b"HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").unwrap();
+        });
+
+        let result = egress_ip_from(&proxy("http", port, "user", "wrong"), "ip.test", 80, TIMEOUT);
+        assert!(matches!(result, Err(AppError::Validation(_))), "{:?}", result);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn socks5_proxy_authenticates_and_connects_by_name() {
+        let (port, server) = fake_proxy(|mut stream| {
+            let mut greeting = [0u8; 4];
+            stream.read_exact(&mut greeting).unwrap();
+            assert_eq!(greeting, [5, 2, 0, 2]);
+            stream.write_all(&[5, 2]).unwrap();
+
+            let mut auth = [0u8; 11];
+            stream.read_exact(&mut auth).unwrap();
+            assert_eq!(&auth, b"\x01\x04user\x04pass");
+            stream.write_all(&[1, 0]).unwrap();
+
+            let mut request = [0u8; 14];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(&request, b"\x05\x01\x00\x03\x07ip.test\x00\x50");
+            stream.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 80]).unwrap();
+            answer_ip(&mut stream, "2001:db8::1");
+        });
+
+        let ip = egress_ip_from(&proxy("socks5", port, "user", "pass"), "ip.test", 80, TIMEOUT).unwrap();
+        assert_eq!(ip.to_string(), "2001:db8::1");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn socks4_proxy_connects_by_name() {
+        let (port, server) = fake_proxy(|mut stream| {
+            let mut request = [0u8; 21];
+            stream.read_exact(&mut request).unwrap();
+            assert_eq!(&request, b"\x04\x01\x00\x50\x00\x00\x00\x01user\x00ip.test\x00");
+            stream.write_all(&[0, 0x5A, 0, 0, 0, 0, 0, 0]).unwrap();
+            answer_ip(&mut stream, "198.51.100.4");
+        });
+
+        let ip = egress_ip_from(&proxy("socks4", port, "user", ""), "ip.test", 80, TIMEOUT).unwrap();
+        assert_eq!(ip.to_string(), "198.51.100.4");
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn unreachable_proxy_fails() {
+        // Bind then release a port so nothing is listening on it
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let result = egress_ip_from(&proxy("http", port, "", ""), "ip.test", 80, TIMEOUT);
+        assert!(matches!(result, Err(AppError::Io(_))), "{:?}", result);
+    }
+
+    #[test]
+    fn proxy_that_accepts_but_never_answers_times_out() {
+        let (port, server) = fake_proxy(|stream| {
+            std::thread::sleep(Duration::from_millis(600));
+            drop(stream);
+        });
+
+        let result = egress_ip_from(&proxy("socks5", port, "", ""), "ip.test", 80, Duration::from_millis(200));
+        assert!(matches!(result, Err(AppError::Timeout(_))), "{:?}", result);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn non_ip_body_is_rejected() {
+        let (port, server) = fake_proxy(|mut stream| {
+            let mut greeting = [0u8; 3];
+            stream.read_exact(&mut greeting).unwrap();
+            stream.write_all(&[5, 0]).unwrap();
+            let mut request = [0u8; 14];
+            stream.read_exact(&mut request).unwrap();
+            stream.write_all(&[5, 0, 0, 1, 0, 0, 0, 0, 0, 0]).unwrap();
+            answer_ip(&mut stream, "<html>captive portal</html>");
+        });
+
+        let result = egress_ip_from(&proxy("socks5", port, "", ""), "ip.test", 80, TIMEOUT);
+        assert!(matches!(result, Err(AppError::Io(_))), "{:?}", result);
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,102 @@
+use serde_json::Value;
+
+use crate::database::DbWorkflow;
+
+// ============ Block Types ============
+
+/// Action types registered by the sidecar (sidecar/automation/actions). Keep in sync when
+/// adding an action there, otherwise workflows using it can't be saved.
+pub const KNOWN_BLOCK_TYPES: &[&str] = &[
+    // navigation
+    "navigate", "go-back", "go-forward", "refresh", "new-tab", "close-tab", "switch-tab",
+    // interaction
+    "click", "type", "fill", "select", "check", "upload", "hover", "scroll", "press-key",
+    "focus", "clear", "mouse-move", "human-click", "human-drag",
+    // wait
+    "wait-element", "wait-time", "wait-navigation", "wait-network", "wait-text", "wait-url",
+    "wait-function",
+    // data
+    "get-text", "get-attribute", "get-url", "get-title", "count-elements", "set-variable",
+    "calculate", "screenshot", "export-cookies", "import-cookies", "clear-cookies", "evaluate",
+    "get-all-texts",
+    // control
+    "condition", "loop-elements", "loop-count", "loop-while", "loop-array", "break", "continue",
+    "try-catch", "call-workflow", "stop", "log", "comment",
+    // utility
+    "clipboard-copy", "clipboard-paste", "clipboard-get", "random-text", "random-number",
+    "random-email", "random-phone", "random-uuid", "random-choice", "string-split",
+    "string-join", "string-replace", "string-substring", "regex-match", "regex-replace",
+    "array-push", "array-pop", "array-get", "array-length", "array-filter", "array-sort",
+    "array-shuffle",
+    // file
+    "file-read", "file-write", "file-append-csv", "file-exists", "file-delete", "file-list",
+    // http
+    "http-request", "http-get", "http-post", "http-download", "json-parse",
+    // googlesheets
+    "gsheet-read", "gsheet-write", "gsheet-append", "gsheet-clear", "gsheet-info",
+    // ai
+    "ai-openai", "ai-claude", "ai-gemini", "ai-deepseek", "ai-chat", "ai-extract",
+    // advanced
+    "javascript", "random", "clipboard", "dialog", "download", "notification", "smart-delay",
+    "assert", "get-html",
+];
+
+/// Keys holding nested block lists (condition branches, loop bodies, try/catch)
+const NESTED_BLOCK_KEYS: &[&str] = &["then", "else", "body", "try", "catch"];
+
+// ============ Validator ============
+
+/// Structural checks on a workflow's JSON fields before it is saved. Collects every problem
+/// rather than stopping at the first so the editor can show them all at once.
+pub fn validate_workflow_json(workflow: &DbWorkflow) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+
+    match serde_json::from_str::<Value>(&workflow.blocks) {
+        Ok(Value::Array(blocks)) => check_blocks(&blocks, "blocks", &mut errors),
+        Ok(_) => errors.push("blocks must be an array".to_string()),
+        Err(e) => errors.push(format!("blocks is not valid JSON: {}", e)),
+    }
+
+    check_object(&workflow.variables, "variables", &mut errors);
+    check_object(&workflow.settings, "settings", &mut errors);
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_blocks(blocks: &[Value], path: &str, errors: &mut Vec<String>) {
+    for (i, block) in blocks.iter().enumerate() {
+        let at = format!("{}[{}]", path, i);
+
+        let Some(block) = block.as_object() else {
+            errors.push(format!("{}: block must be an object", at));
+            continue;
+        };
+
+        match block.get("type") {
+            Some(Value::String(t)) if KNOWN_BLOCK_TYPES.contains(&t.as_str()) => {}
+            Some(Value::String(t)) => errors.push(format!("{}: unknown block type '{}'", at, t)),
+            Some(_) => errors.push(format!("{}: type must be a string", at)),
+            None => errors.push(format!("{}: missing type", at)),
+        }
+
+        for key in NESTED_BLOCK_KEYS {
+            match block.get(*key) {
+                None | Some(Value::Null) => {}
+                Some(Value::Array(nested)) => check_blocks(nested, &format!("{}.{}", at, key), errors),
+                Some(_) => errors.push(format!("{}.{}: must be an array of blocks", at, key)),
+            }
+        }
+    }
+}
+
+fn check_object(json: &str, field: &str, errors: &mut Vec<String>) {
+    match serde_json::from_str::<Value>(json) {
+        Ok(Value::Object(_)) => {}
+        Ok(_) => errors.push(format!("{} must be an object", field)),
+        Err(e) => errors.push(format!("{} is not valid JSON: {}", field, e)),
+    }
+}
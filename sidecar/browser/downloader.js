@@ -72,8 +72,9 @@ function getChromiumPath() {
 
 /**
  * Download file from URL
+ * @param {Function} [onProgress] - Called with { phase, percent, downloadedBytes, totalBytes } as the percentage changes
  */
-function downloadFile(url, destPath) {
+function downloadFile(url, destPath, onProgress) {
   return new Promise((resolve, reject) => {
     console.error(`[DOWNLOAD] Downloading from ${url}`);
 
@@ -86,7 +87,7 @@ function downloadFile(url, destPath) {
       if (response.statusCode === 301 || response.statusCode === 302) {
         file.close();
         fs.unlinkSync(destPath);
-        return downloadFile(response.headers.location, destPath).then(resolve).catch(reject);
+        return downloadFile(response.headers.location, destPath, onProgress).then(resolve).catch(reject);
       }
 
       if (response.statusCode !== 200) {
@@ -96,12 +97,16 @@ function downloadFile(url, destPath) {
       }
 
       totalBytes = parseInt(response.headers['content-length'], 10) || 0;
+      let lastPercent = -1;
 
       response.on('data', (chunk) => {
         downloadedBytes += chunk.length;
         if (totalBytes > 0) {
           const percent = Math.round((downloadedBytes / totalBytes) * 100);
+          if (percent === lastPercent) return;
+          lastPercent = percent;
           process.stderr.write(`\r[DOWNLOAD] Progress: ${percent}% (${Math.round(downloadedBytes / 1024 / 1024)}MB)`);
+          if (onProgress) onProgress({ phase: 'download', percent, downloadedBytes, totalBytes });
         }
       });
 
@@ -151,8 +156,10 @@ function extractZip(zipPath, destDir) {
 
 /**
  * Download and install Chromium
+ * @param {Function} [onProgress] - Called with { phase, percent, downloadedBytes, totalBytes }
+ *   while downloading, then once each for the 'extract' and 'done' phases
  */
-async function downloadChromium() {
+async function downloadChromium(onProgress) {
   if (isChromiumInstalled()) {
     console.error('[DOWNLOAD] Chromium already installed');
     return getChromiumPath();
@@ -174,9 +181,10 @@ async function downloadChromium() {
 
   try {
     // Download
-    await downloadFile(downloadUrl, zipPath);
+    await downloadFile(downloadUrl, zipPath, onProgress);
 
     // Extract
+    if (onProgress) onProgress({ phase: 'extract', percent: 100 });
     extractZip(zipPath, chromiumDir);
 
     // Make executable on Unix
@@ -190,6 +198,7 @@ async function downloadChromium() {
     fs.rmdirSync(tempDir, { recursive: true });
 
     console.error('[DOWNLOAD] Chromium installed successfully');
+    if (onProgress) onProgress({ phase: 'done', percent: 100 });
     return getChromiumPath();
 
   } catch (e) {
@@ -7,6 +7,7 @@
  * - Netscape (cookies.txt)
  * - EditThisCookie (JSON array)
  * - Base64 encoded JSON
+ * - Header string (name=value; name2=value2) - export only
  */
 
 const fs = require('fs');
@@ -160,6 +161,15 @@ function importBase64(base64String) {
   return importJSON(json);
 }
 
+/**
+ * Export cookies as a Cookie header string
+ * @param {Array} cookies - Playwright cookies array
+ * @returns {string} Header string (name=value; name2=value2)
+ */
+function exportHeaderString(cookies) {
+  return cookies.map(cookie => `${cookie.name}=${cookie.value}`).join('; ');
+}
+
 /**
  * Detect cookie format from string
  * @param {string} cookieString - Cookie string
@@ -254,6 +264,9 @@ function saveToFile(cookies, filePath, format = 'json') {
     case 'base64':
       content = exportBase64(cookies);
       break;
+    case 'header':
+      content = exportHeaderString(cookies);
+      break;
     default:
       content = exportJSON(cookies);
   }
@@ -375,6 +388,7 @@ module.exports = {
   exportNetscape,
   exportEditThisCookie,
   exportBase64,
+  exportHeaderString,
 
   // Import functions
   importJSON,
@@ -6,6 +6,7 @@
 const { chromium, firefox, webkit } = require('playwright');
 const crypto = require('crypto');
 const readline = require('readline');
+const { AsyncLocalStorage } = require('async_hooks');
 const http = require('http');
 const path = require('path');
 const os = require('os');
@@ -36,7 +37,7 @@ async function ensureChromiumInstalled() {
  */
 async function downloadChromium() {
   try {
-    await browserDownloader.downloadChromium();
+    await browserDownloader.downloadChromium(progress => emitEvent('browser-install-progress', progress));
     return { success: true, path: browserDownloader.getChromiumPath() };
   } catch (e) {
     return { success: false, error: e.message };
@@ -54,6 +55,19 @@ function getChromiumStatus() {
   };
 }
 
+// Request being handled, so long-running handlers can stream progress to its caller
+const requestContext = new AsyncLocalStorage();
+
+/**
+ * Send a progress event for the request being handled. The app routes it to the
+ * caller by request id; outside a Tauri request (e.g. over HTTP) it is dropped.
+ */
+function emitEvent(event, data) {
+  const request = requestContext.getStore();
+  if (!request || request.id === undefined) return;
+  console.log(JSON.stringify({ id: request.id, event, data }));
+}
+
 // UUID v4 generator
 function uuidv4() {
   return crypto.randomUUID();
@@ -289,8 +303,15 @@ const scheduler = new Scheduler({
   },
 });
 
+// The app may run several sidecars; only worker 0 owns the scheduler, the HTTP
+// server and browser installation so they aren't duplicated per process
+const WORKER_INDEX = Number(process.env.SIDECAR_WORKER_INDEX || 0);
+const IS_PRIMARY = WORKER_INDEX === 0;
+
 // Initialize scheduler
-scheduler.init().catch(err => console.error('[SCHEDULER] Init error:', err));
+if (IS_PRIMARY) {
+  scheduler.init().catch(err => console.error('[SCHEDULER] Init error:', err));
+}
 
 // Parallel Executor instances (one per workflow execution)
 const parallelExecutors = new Map();
@@ -362,6 +383,7 @@ function clearExecutionLogs(executionId) {
 
 /**
  * Initialize browser instance for engine type
+ * @param {Object} options - { engine, headless, args, proxy: { host, port, type, username, password }, locale }
  */
 async function initBrowser(options = {}) {
   const engineName = options.engine || 'chromium';
@@ -370,13 +392,54 @@ async function initBrowser(options = {}) {
     return { success: true, message: `${engineName} browser already initialized` };
   }
 
+  const launchOptions = {
+    headless: options.headless ?? false,
+    args: [...(options.args || [])],
+  };
+
+  if (options.proxy && options.proxy.host) {
+    launchOptions.proxy = {
+      server: `${options.proxy.type || 'http'}://${options.proxy.host}:${options.proxy.port}`,
+    };
+    if (options.proxy.username && options.proxy.password) {
+      launchOptions.proxy.username = options.proxy.username;
+      launchOptions.proxy.password = options.proxy.password;
+    }
+  }
+
+  // Locale is a context option in Playwright; set the browser-wide default instead
+  if (options.locale) {
+    if (engineName === 'firefox') {
+      launchOptions.firefoxUserPrefs = { 'intl.accept_languages': options.locale, 'intl.locale.requested': options.locale };
+    } else {
+      launchOptions.args.push(`--lang=${options.locale}`);
+    }
+  }
+
+  // Sessions launch the downloaded Chromium, so fetch it before reporting ready
+  if (engineName === 'chromium' && !browserDownloader.isChromiumInstalled()) {
+    try {
+      await browserDownloader.downloadChromium(progress => emitEvent('browser-install-progress', progress));
+    } catch (error) {
+      return { success: false, error: `Chromium download failed: ${error.message}` };
+    }
+  }
+
   try {
-    browsers[engineName] = await launchBrowser(engineName, {
-      headless: options.headless ?? false,
-    });
+    browsers[engineName] = await launchBrowser(engineName, launchOptions);
 
     console.error(`[BROWSER] ${engineName} initialized successfully`);
-    return { success: true, message: `${engineName} browser initialized`, engine: engineName };
+    return {
+      success: true,
+      message: `${engineName} browser initialized`,
+      engine: engineName,
+      options: {
+        headless: launchOptions.headless,
+        args: options.args || [],
+        proxy: launchOptions.proxy ? launchOptions.proxy.server : null,
+        locale: options.locale || null,
+      },
+    };
   } catch (error) {
     console.error(`[BROWSER] Failed to initialize ${engineName}:`, error.message);
     return { success: false, error: error.message };
@@ -713,13 +776,20 @@ async function createSession(profile, proxyConfig = null, options = {}) {
       profileName: fullProfile.name || 'Unnamed',
       profile: fullProfile,
       engine: engineName,
+      userDataDir,
       browser,
       context,
       page,
       status: 'running',
-      startedAt: new Date().toISOString()
+      startedAt: new Date().toISOString(),
+      cookieFirstSeen: new Map(),
     });
 
+    // Playwright exposes no cookie creation time, so track when each cookie was first seen.
+    // Cookies restored from the profile dir are seeded now, i.e. at least as old as the session.
+    await trackCookieAges(sessions.get(sessionId));
+    page.on('load', () => trackCookieAges(sessions.get(sessionId)).catch(() => {}));
+
     // Auto-cleanup when browser is closed by user (click X on window)
     browser.on('disconnected', () => {
       if (sessions.has(sessionId)) {
@@ -750,6 +820,7 @@ async function createSession(profile, proxyConfig = null, options = {}) {
       profileId: fullProfile.id,
       profileName: fullProfile.name,
       engine: engineName,
+      publicIP: fullProfile.webrtcPublicIP || (proxyConfig && proxyConfig.host) || '',
     };
   } catch (error) {
     console.error('[SESSION] Failed to create:', error.message);
@@ -818,11 +889,87 @@ function getSessions() {
   return { success: true, sessions: result };
 }
 
+/**
+ * Browser instances this worker launched and still tracks, by profile directory.
+ * The app matches these against running processes to find orphans.
+ */
+function listBrowserProcesses() {
+  const browsers = [];
+  for (const session of sessions.values()) {
+    browsers.push({
+      sessionId: session.id,
+      profileId: session.profileId,
+      engine: session.engine,
+      userDataDir: session.userDataDir,
+    });
+  }
+  return { success: true, profilesDir: PROFILES_DIR, browsers };
+}
+
+// How long CPU time is sampled over for getSessionResourceUsage
+const CPU_SAMPLE_MS = 500;
+
+/**
+ * Browser pid and CPU use per session. CPU is sampled over DevTools, so it is only measured
+ * for Chromium sessions connected over CDP; memory is left null for the app to read from the OS.
+ */
+async function getSessionResourceUsage() {
+  const samples = [];
+  for (const session of sessions.values()) {
+    const sample = {
+      sessionId: session.id,
+      pid: session.browser?._chromeProcess?.pid ?? null,
+      cdp: null,
+      cpuTime: null,
+    };
+    if (typeof session.browser?.newBrowserCDPSession === 'function') {
+      try {
+        sample.cdp = await session.browser.newBrowserCDPSession();
+        sample.cpuTime = await totalCpuTime(sample.cdp);
+      } catch (e) {
+        // Not Chromium, or the browser is going away
+      }
+    }
+    samples.push(sample);
+  }
+
+  if (samples.some(sample => sample.cpuTime !== null)) {
+    await new Promise(resolve => setTimeout(resolve, CPU_SAMPLE_MS));
+  }
+
+  const result = [];
+  for (const sample of samples) {
+    let cpuPercent = null;
+    if (sample.cpuTime !== null) {
+      try {
+        const elapsed = (await totalCpuTime(sample.cdp)) - sample.cpuTime;
+        cpuPercent = Math.round(Math.max(0, elapsed) / (CPU_SAMPLE_MS / 1000) * 1000) / 10;
+      } catch (e) {
+        // Browser closed while sampling
+      }
+    }
+    if (sample.cdp) {
+      sample.cdp.detach().catch(() => {});
+    }
+    result.push({ sessionId: sample.sessionId, pid: sample.pid, memoryMb: null, cpuPercent });
+  }
+  return { success: true, sessions: result };
+}
+
+/**
+ * CPU seconds used so far by every process of a Chromium browser
+ */
+async function totalCpuTime(cdp) {
+  const { processInfo } = await cdp.send('SystemInfo.getProcessInfo');
+  return processInfo.reduce((sum, process) => sum + process.cpuTime, 0);
+}
+
 /**
  * Close browser and all sessions
  */
 async function shutdown() {
   try {
+    const closedSessions = sessions.size;
     for (const [id, session] of sessions) {
       await session.context.close();
     }
@@ -836,7 +983,7 @@ async function shutdown() {
     }
 
     console.error('[BROWSER] Shutdown complete');
-    return { success: true };
+    return { success: true, closedSessions };
   } catch (error) {
     return { success: false, error: error.message };
   }
@@ -893,6 +1040,63 @@ async function evaluate(sessionId, script) {
   }
 }
 
+/**
+ * Run a function body in the page with JSON arguments, available to it as `args`.
+ * Evaluated as `new Function('args', body)(args)`; values are never spliced into code.
+ */
+async function evaluateWithArgs(sessionId, body, args = []) {
+  const session = sessions.get(sessionId);
+  if (!session) {
+    return { success: false, error: 'Session not found' };
+  }
+
+  try {
+    // Built here and sent to the page as a function, so a page CSP without unsafe-eval doesn't block it
+    const fn = new Function('args', body);
+    const result = await session.page.evaluate(fn, args);
+    return { success: true, result: result === undefined ? null : result };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
+/**
+ * Resize the viewport of a live session. On Chromium the device scale factor and
+ * mobile mode are applied too (via CDP); other engines only support a resize, so
+ * those come back as null.
+ */
+async function setViewport(sessionId, viewport = {}) {
+  const session = sessions.get(sessionId);
+  if (!session) {
+    return { success: false, error: 'Session not found' };
+  }
+
+  try {
+    const { width, height } = viewport;
+    await session.page.setViewportSize({ width, height });
+
+    const applied = { width, height, deviceScaleFactor: null, isMobile: null };
+    if (session.engine === 'chromium') {
+      const deviceScaleFactor = viewport.deviceScaleFactor || 1;
+      const isMobile = !!viewport.isMobile;
+      const cdpSession = await session.context.newCDPSession(session.page);
+      await cdpSession.send('Emulation.setDeviceMetricsOverride', {
+        width,
+        height,
+        deviceScaleFactor,
+        mobile: isMobile,
+      });
+      await cdpSession.send('Emulation.setTouchEmulationEnabled', { enabled: isMobile });
+      applied.deviceScaleFactor = deviceScaleFactor;
+      applied.isMobile = isMobile;
+    }
+
+    return { success: true, viewport: applied };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
 /**
  * Take screenshot
  */
@@ -910,6 +1114,37 @@ async function screenshot(sessionId, path) {
   }
 }
 
+/**
+ * Take a PNG screenshot and return it as a data URL instead of a file.
+ * Fails if the base64 payload would exceed options.maxBytes.
+ */
+async function screenshotBase64(sessionId, options = {}) {
+  const session = sessions.get(sessionId);
+  if (!session) {
+    return { success: false, error: 'Session not found' };
+  }
+
+  try {
+    const buffer = await session.page.screenshot({ type: 'png', fullPage: options.fullPage || false });
+
+    const encodedSize = Math.ceil(buffer.length / 3) * 4;
+    if (options.maxBytes && encodedSize > options.maxBytes) {
+      return {
+        success: false,
+        error: `Screenshot is ${encodedSize} bytes as base64, over the ${options.maxBytes} byte limit`,
+      };
+    }
+
+    // PNG IHDR: width and height are big-endian u32s at bytes 16 and 20
+    const width = buffer.readUInt32BE(16);
+    const height = buffer.readUInt32BE(20);
+
+    return { success: true, dataUrl: `data:image/png;base64,${buffer.toString('base64')}`, width, height };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
 /**
  * Get current page URL
  */
@@ -944,6 +1179,40 @@ async function getTitle(sessionId) {
   }
 }
 
+/**
+ * No-op for measuring protocol round-trip time; touches no browser state
+ */
+function ping() {
+  return { success: true };
+}
+
+/**
+ * Health check: sidecar/runtime versions and browser install state
+ */
+function health() {
+  let playwrightVersion = null;
+  try {
+    playwrightVersion = require('playwright/package.json').version;
+  } catch (e) {
+    // Bundled builds may not ship playwright's package.json
+  }
+
+  const browserVersions = {};
+  for (const [name, browser] of Object.entries(browsers)) {
+    browserVersions[name] = browser ? browser.version() : null;
+  }
+
+  return {
+    success: true,
+    sidecarVersion: require('./package.json').version,
+    nodeVersion: process.version,
+    playwrightVersion,
+    browserVersions,
+    chromium: getChromiumStatus(),
+    uptimeMs: Math.round(process.uptime() * 1000)
+  };
+}
+
 /**
  * Get available mobile devices
  */
@@ -952,6 +1221,20 @@ function getDevices() {
   return { success: true, devices: getAllDevices() };
 }
 
+/**
+ * Generate a random profile; the same seed always yields the same profile
+ * @param {string} platform - windows, macos, linux, or null for a weighted random pick
+ * @param {string} seed - Unsigned 64-bit seed as a decimal string
+ */
+function generateProfile(platform = null, seed = null) {
+  const { generateRandom } = require('./profile/generator');
+  try {
+    return { success: true, profile: generateRandom(platform, seed) };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
 /**
  * Get available browser engines
  */
@@ -1132,6 +1415,9 @@ async function exportCookiesFormat(sessionId, format = 'json') {
       case 'base64':
         result = cookieManager.exportBase64(cookies);
         break;
+      case 'header':
+        result = cookieManager.exportHeaderString(cookies);
+        break;
       default:
         result = cookieManager.exportJSON(cookies);
     }
@@ -1199,9 +1485,10 @@ async function loadCookiesFromFile(sessionId, filePath) {
 }
 
 /**
- * Clear cookies for session
+ * Clear cookies for session, optionally only those matching domain patterns
+ * (exact domains or `*.example.com`). Returns per-pattern removal counts.
  */
-async function clearCookies(sessionId, domain = null) {
+async function clearCookies(sessionId, domains = null) {
   const session = sessions.get(sessionId);
   if (!session) {
     return { success: false, error: 'Session not found' };
@@ -1209,20 +1496,101 @@ async function clearCookies(sessionId, domain = null) {
 
   try {
     const cookies = await session.context.cookies();
-    let toRemove = cookies;
+    const patterns = domains == null ? [] : [].concat(domains);
 
-    if (domain) {
-      toRemove = cookieManager.filterByDomain(cookies, domain);
+    if (patterns.length === 0) {
+      await session.context.clearCookies();
+      return { success: true, cleared: cookies.length, byDomain: {} };
     }
 
-    // Clear by setting empty array for each cookie's URL
-    await session.context.clearCookies();
-    return { success: true, cleared: toRemove.length };
+    const byDomain = {};
+    const toRemove = new Set();
+    for (const pattern of patterns) {
+      const matched = cookies.filter(cookie => matchesDomainPattern(cookie.domain, pattern));
+      byDomain[pattern] = matched.length;
+      matched.forEach(cookie => toRemove.add(cookie));
+    }
+
+    await removeCookies(session, cookies, toRemove);
+    return { success: true, cleared: toRemove.size, byDomain };
   } catch (error) {
     return { success: false, error: error.message };
   }
 }
 
+/**
+ * Clear cookies first seen more than maxAgeSecs ago, plus any already expired
+ */
+async function clearCookiesOlderThan(sessionId, maxAgeSecs) {
+  const session = sessions.get(sessionId);
+  if (!session) {
+    return { success: false, error: 'Session not found' };
+  }
+
+  try {
+    await trackCookieAges(session);
+
+    const cookies = await session.context.cookies();
+    const now = Date.now();
+    const cutoff = now - maxAgeSecs * 1000;
+
+    const toRemove = new Set(cookies.filter(cookie => {
+      const expired = cookie.expires > 0 && cookie.expires * 1000 < now;
+      const firstSeen = session.cookieFirstSeen.get(cookieKey(cookie)) ?? now;
+      return expired || firstSeen <= cutoff;
+    }));
+
+    await removeCookies(session, cookies, toRemove);
+    return { success: true, cleared: toRemove.size };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
+/**
+ * Match a cookie domain against an exact domain or a `*.example.com` pattern
+ * (which covers example.com and all of its subdomains)
+ */
+function matchesDomainPattern(cookieDomain, pattern) {
+  const domain = cookieDomain.toLowerCase().replace(/^\./, '');
+  const normalized = pattern.toLowerCase().replace(/^\./, '');
+
+  if (normalized.startsWith('*.')) {
+    const base = normalized.slice(2);
+    return domain === base || domain.endsWith(`.${base}`);
+  }
+  return domain === normalized;
+}
+
+function cookieKey(cookie) {
+  return `${cookie.domain}|${cookie.path}|${cookie.name}`;
+}
+
+async function trackCookieAges(session) {
+  if (!session) return;
+  const now = Date.now();
+  for (const cookie of await session.context.cookies()) {
+    const key = cookieKey(cookie);
+    if (!session.cookieFirstSeen.has(key)) {
+      session.cookieFirstSeen.set(key, now);
+    }
+  }
+}
+
+/**
+ * Playwright can only clear everything, so clear and re-add the cookies to keep
+ */
+async function removeCookies(session, cookies, toRemove) {
+  if (toRemove.size === 0) return;
+
+  const keep = cookies.filter(cookie => !toRemove.has(cookie));
+  await session.context.clearCookies();
+  if (keep.length > 0) {
+    await session.context.addCookies(keep);
+  }
+  toRemove.forEach(cookie => session.cookieFirstSeen.delete(cookieKey(cookie)));
+}
+
 // ============ Testing ============
 
 /**
@@ -1242,6 +1610,34 @@ async function runAntidetectTest(sessionId, options = {}) {
   }
 }
 
+/**
+ * Gather the IPs a session exposes through WebRTC ICE candidates, along with the
+ * egress IP its HTTP traffic (i.e. the proxy, if any) appears from.
+ */
+async function runWebRTCLeakTest(sessionId) {
+  const session = sessions.get(sessionId);
+  if (!session) {
+    return { success: false, error: 'Session not found' };
+  }
+
+  try {
+    const webrtc = await testRunner.antidetect.testWebRTC(session.page);
+
+    // The context's request client goes through the same proxy as the page
+    let egressIp = null;
+    try {
+      const response = await session.context.request.get('https://api.ipify.org?format=json', { timeout: 15000 });
+      egressIp = (await response.json()).ip || null;
+    } catch (e) {
+      console.error(`[TEST] Egress IP lookup failed: ${e.message}`);
+    }
+
+    return { success: true, detectedIps: [...new Set(webrtc.data.ips)], egressIp };
+  } catch (error) {
+    return { success: false, error: error.message };
+  }
+}
+
 /**
  * Run quick benchmark on session
  */
@@ -1253,7 +1649,7 @@ async function runQuickBenchmark(sessionId) {
 
   try {
     const results = await testRunner.benchmark.quickBenchmark(session.page);
-    return { success: true, results };
+    return { success: true, engine: session.engine, results };
   } catch (error) {
     return { success: false, error: error.message };
   }
@@ -2881,7 +3277,9 @@ const httpServer = http.createServer(async (req, res) => {
         } else if (action === 'loadCookiesFromFile') {
           result = await handler(params.sessionId, params.filePath);
         } else if (action === 'clearCookies') {
-          result = await handler(params.sessionId, params.domain);
+          result = await handler(params.sessionId, params.domains ?? params.domain);
+        } else if (action === 'clearCookiesOlderThan') {
+          result = await handler(params.sessionId, params.maxAgeSecs);
         } else if (action === 'importExtension') {
           result = await handler(params.sourcePath, params.extensionId);
         } else if (action === 'importExtensionCRX') {
@@ -3014,9 +3412,11 @@ httpServer.on('error', (err) => {
   }
 });
 
-httpServer.listen(HTTP_PORT, () => {
-  console.error(`[HTTP] Server listening on port ${HTTP_PORT}`);
-});
+if (IS_PRIMARY) {
+  httpServer.listen(HTTP_PORT, () => {
+    console.error(`[HTTP] Server listening on port ${HTTP_PORT}`);
+  });
+}
 
 // ============ IPC Communication (for Tauri) ============
 
@@ -3034,6 +3434,8 @@ const handlers = {
   navigate,
   closeSession,
   getSessions,
+  listBrowserProcesses,
+  getSessionResourceUsage,
   shutdown,
 
   // Basic cookies (Playwright native)
@@ -3046,6 +3448,7 @@ const handlers = {
   saveCookiesToFile,
   loadCookiesFromFile,
   clearCookies,
+  clearCookiesOlderThan,
 
   // Extensions
   listExtensions,
@@ -3062,17 +3465,24 @@ const handlers = {
   getChromiumStatus,
 
   // Utilities
+  ping,
+  health,
   evaluate,
+  evaluateWithArgs,
+  setViewport,
   screenshot,
+  screenshotBase64,
   getUrl,
   getTitle,
   getDevices,
   getEngines,
+  generateProfile,
   getSystemInfo,
   geoLookup,
 
   // Testing
   runAntidetectTest,
+  runWebRTCLeakTest,
   runQuickBenchmark,
   runFullBenchmark,
   runTestSuite,
@@ -3189,8 +3599,11 @@ const handlers = {
 
 // Listen for commands from Tauri
 rl.on('line', async (line) => {
+  let id;
   try {
-    const { id, command, args } = JSON.parse(line);
+    const request = JSON.parse(line);
+    id = request.id;
+    const { command, args } = request;
     const handler = handlers[command];
 
     if (!handler) {
@@ -3198,10 +3611,11 @@ rl.on('line', async (line) => {
       return;
     }
 
-    const result = await handler(...(args || []));
+    const result = await requestContext.run({ id }, () => handler(...(args || [])));
     console.log(JSON.stringify({ id, result }));
   } catch (error) {
-    console.log(JSON.stringify({ error: error.message }));
+    // Echo the id so the app can route the error to the waiting caller
+    console.log(JSON.stringify({ id, error: error.message }));
   }
 });
 
@@ -3217,9 +3631,13 @@ process.on('SIGINT', async () => {
 });
 
 // Check and install Chromium if needed
-ensureChromiumInstalled().then(() => {
-  console.error('[SIDECAR] Ready - Multi-browser support enabled');
-}).catch(err => {
-  console.error('[SIDECAR] Warning:', err.message);
-  console.error('[SIDECAR] Ready - Multi-browser support enabled (Chromium may be missing)');
-});
+if (IS_PRIMARY) {
+  ensureChromiumInstalled().then(() => {
+    console.error('[SIDECAR] Ready - Multi-browser support enabled');
+  }).catch(err => {
+    console.error('[SIDECAR] Warning:', err.message);
+    console.error('[SIDECAR] Ready - Multi-browser support enabled (Chromium may be missing)');
+  });
+} else {
+  console.error(`[SIDECAR] Worker ${WORKER_INDEX} ready`);
+}
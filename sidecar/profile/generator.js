@@ -122,16 +122,37 @@ const macVoices = ['Alex', 'Samantha', 'Victoria', 'Daniel'];
 
 // ============ Helper Functions ============
 
-function randomItem(arr) {
-  return arr[Math.floor(Math.random() * arr.length)];
+// Helpers take an optional rng (returning [0, 1) like Math.random) so seeded generation is reproducible
+
+function randomItem(arr, rng = Math.random) {
+  return arr[Math.floor(rng() * arr.length)];
+}
+
+function randomInt(min, max, rng = Math.random) {
+  return Math.floor(rng() * (max - min + 1)) + min;
 }
 
-function randomInt(min, max) {
-  return Math.floor(Math.random() * (max - min + 1)) + min;
+function randomFloat(min, max, rng = Math.random) {
+  return min + rng() * (max - min);
 }
 
-function randomFloat(min, max) {
-  return min + Math.random() * (max - min);
+const MASK_64 = (1n << 64n) - 1n;
+
+/**
+ * SplitMix64 generator: the same seed always yields the same sequence
+ * @param {string|number|bigint} seed - Unsigned 64-bit seed (pass large seeds as strings to keep precision)
+ * @returns {Function} rng returning [0, 1)
+ */
+function seededRandom(seed) {
+  let state = BigInt(seed) & MASK_64;
+  return () => {
+    state = (state + 0x9E3779B97F4A7C15n) & MASK_64;
+    let z = state;
+    z = ((z ^ (z >> 30n)) * 0xBF58476D1CE4E5B9n) & MASK_64;
+    z = ((z ^ (z >> 27n)) * 0x94D049BB133111EBn) & MASK_64;
+    z ^= z >> 31n;
+    return Number(z >> 11n) / 2 ** 53;
+  };
 }
 
 // ============ Generator Functions ============
@@ -139,36 +160,36 @@ function randomFloat(min, max) {
 /**
  * Generate a random profile for Windows
  */
-function generateWindows() {
-  const gpuType = randomInt(0, 2);
+function generateWindows(rng = Math.random) {
+  const gpuType = randomInt(0, 2, rng);
   let webglVendor, webglRenderer, cpuCores, deviceMemory;
 
   switch (gpuType) {
     case 0: // NVIDIA
       webglVendor = 'Google Inc. (NVIDIA)';
-      webglRenderer = `ANGLE (NVIDIA, ${randomItem(nvidiaGPUs)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
-      cpuCores = randomItem([8, 12, 16, 24]);
-      deviceMemory = randomItem([16, 32, 64]);
+      webglRenderer = `ANGLE (NVIDIA, ${randomItem(nvidiaGPUs, rng)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
+      cpuCores = randomItem([8, 12, 16, 24], rng);
+      deviceMemory = randomItem([16, 32, 64], rng);
       break;
     case 1: // AMD
       webglVendor = 'Google Inc. (AMD)';
-      webglRenderer = `ANGLE (AMD, ${randomItem(amdGPUs)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
-      cpuCores = randomItem([8, 12, 16]);
-      deviceMemory = randomItem([16, 32]);
+      webglRenderer = `ANGLE (AMD, ${randomItem(amdGPUs, rng)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
+      cpuCores = randomItem([8, 12, 16], rng);
+      deviceMemory = randomItem([16, 32], rng);
       break;
     default: // Intel
       webglVendor = 'Google Inc. (Intel)';
-      webglRenderer = `ANGLE (Intel, ${randomItem(intelGPUs)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
-      cpuCores = randomItem([4, 6, 8]);
-      deviceMemory = randomItem([8, 16]);
+      webglRenderer = `ANGLE (Intel, ${randomItem(intelGPUs, rng)} Direct3D11 vs_5_0 ps_5_0, D3D11)`;
+      cpuCores = randomItem([4, 6, 8], rng);
+      deviceMemory = randomItem([8, 16], rng);
   }
 
   return {
     os: 'windows',
     platform: 'Win32',
     browserType: 'chrome',
-    browserVersion: randomItem(['119', '120', '121', '122']),
-    userAgent: randomItem(windowsUAs),
+    browserVersion: randomItem(['119', '120', '121', '122'], rng),
+    userAgent: randomItem(windowsUAs, rng),
     pixelRatio: 1.0,
     maxTouchPoints: 0,
     fonts: windowsFonts,
@@ -183,15 +204,15 @@ function generateWindows() {
 /**
  * Generate a random profile for macOS
  */
-function generateMacOS() {
-  const gpu = randomItem(appleGPUs);
+function generateMacOS(rng = Math.random) {
+  const gpu = randomItem(appleGPUs, rng);
 
   return {
     os: 'macos',
     platform: 'MacIntel',
-    browserType: randomItem(['chrome', 'safari']),
-    browserVersion: randomItem(['120', '17']),
-    userAgent: randomItem(macUAs),
+    browserType: randomItem(['chrome', 'safari'], rng),
+    browserVersion: randomItem(['120', '17'], rng),
+    userAgent: randomItem(macUAs, rng),
     pixelRatio: 2.0,
     colorDepth: 30,
     maxTouchPoints: 0,
@@ -199,45 +220,50 @@ function generateMacOS() {
     speechVoices: macVoices,
     webglVendor: 'Google Inc. (Apple)',
     webglRenderer: `ANGLE (Apple, ${gpu}, OpenGL 4.1)`,
-    cpuCores: randomItem([8, 10, 12]),
-    deviceMemory: randomItem([8, 16, 32]),
+    cpuCores: randomItem([8, 10, 12], rng),
+    deviceMemory: randomItem([8, 16, 32], rng),
   };
 }
 
 /**
  * Generate a random profile for Linux
  */
-function generateLinux() {
-  const gpu = randomItem(intelGPUs);
-  const useMesa = Math.random() > 0.5;
+function generateLinux(rng = Math.random) {
+  const gpu = randomItem(intelGPUs, rng);
+  const useMesa = rng() > 0.5;
 
   return {
     os: 'linux',
     platform: 'Linux x86_64',
-    browserType: randomItem(['chrome', 'firefox']),
-    browserVersion: randomItem(['120', '121']),
-    userAgent: randomItem(linuxUAs),
+    browserType: randomItem(['chrome', 'firefox'], rng),
+    browserVersion: randomItem(['120', '121'], rng),
+    userAgent: randomItem(linuxUAs, rng),
     pixelRatio: 1.0,
     maxTouchPoints: 0,
     fonts: linuxFonts,
     speechVoices: [],
-    doNotTrack: Math.random() > 0.5,
+    doNotTrack: rng() > 0.5,
     webglVendor: useMesa ? 'Mesa/X.org' : 'Intel Inc.',
     webglRenderer: useMesa ? `Mesa ${gpu}` : gpu,
-    cpuCores: randomItem([4, 6, 8, 12]),
-    deviceMemory: randomItem([8, 16, 32]),
+    cpuCores: randomItem([4, 6, 8, 12], rng),
+    deviceMemory: randomItem([8, 16, 32], rng),
   };
 }
 
 /**
  * Generate a random profile
  * @param {string} [platform] - Specific platform (windows, macos, linux) or random
+ * @param {string|number} [seed] - Unsigned 64-bit seed. With a seed the result depends on nothing
+ *   else: id and timestamps are left empty (assigned when saved) and the seed is kept in fingerprintSeed
  * @returns {Profile}
  */
-function generateRandom(platform = null) {
+function generateRandom(platform = null, seed = null) {
+  const seeded = seed !== null && seed !== undefined && seed !== '';
+  const rng = seeded ? seededRandom(seed) : Math.random;
+
   // Select platform with weighted probability
   if (!platform) {
-    const rand = Math.random() * 100;
+    const rand = rng() * 100;
     if (rand < 70) platform = 'windows';
     else if (rand < 90) platform = 'macos';
     else platform = 'linux';
@@ -247,28 +273,28 @@ function generateRandom(platform = null) {
   let profile;
   switch (platform) {
     case 'windows':
-      profile = generateWindows();
+      profile = generateWindows(rng);
       break;
     case 'macos':
-      profile = generateMacOS();
+      profile = generateMacOS(rng);
       break;
     case 'linux':
-      profile = generateLinux();
+      profile = generateLinux(rng);
       break;
     default:
-      profile = generateWindows();
+      profile = generateWindows(rng);
   }
 
   // Add common fields
-  const geo = randomItem(geoProfiles);
-  const res = randomItem(resolutions);
-  const now = new Date().toISOString();
+  const geo = randomItem(geoProfiles, rng);
+  const res = randomItem(resolutions, rng);
+  const now = seeded ? '' : new Date().toISOString();
 
   return {
     ...defaultProfile,
     ...profile,
-    id: uuidv4(),
-    name: `${profile.os.charAt(0).toUpperCase() + profile.os.slice(1)} Profile ${randomInt(1000, 9999)}`,
+    id: seeded ? '' : uuidv4(),
+    name: `${profile.os.charAt(0).toUpperCase() + profile.os.slice(1)} Profile ${randomInt(1000, 9999, rng)}`,
 
     // Screen
     viewportWidth: res.width,
@@ -286,9 +312,9 @@ function generateRandom(platform = null) {
     country: geo.country,
 
     // Fingerprint noise (recommended values)
-    canvasNoise: randomFloat(0.02, 0.03),
-    audioNoise: randomFloat(0.0001, 0.001),
-    clientRectsNoise: randomFloat(0.1, 0.3),
+    canvasNoise: randomFloat(0.02, 0.03, rng),
+    audioNoise: randomFloat(0.0001, 0.001, rng),
+    clientRectsNoise: randomFloat(0.1, 0.3, rng),
 
     // WebGL
     webglImageMode: 'noise',
@@ -303,11 +329,13 @@ function generateRandom(platform = null) {
     // Timestamps
     createdAt: now,
     updatedAt: now,
+    fingerprintSeed: seeded ? BigInt(seed).toString() : '',
   };
 }
 
 module.exports = {
   generateRandom,
+  seededRandom,
   generateWindows,
   generateMacOS,
   generateLinux,